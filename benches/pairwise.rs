@@ -19,7 +19,7 @@ fn bench_local_reuse_profile(b: &mut Bencher) {
     let identity_matrix = Matrix::new(MatrixType::Identity);
     let profile = Profile::new(STR_1, &identity_matrix);
 
-    b.iter(|| local_alignment_score(&profile, STR_2, 1, 1));
+    b.iter(|| local_alignment_score(&profile, STR_2, 1, 1).unwrap());
 }
 
 #[bench]
@@ -27,7 +27,7 @@ fn bench_global_reuse_profile(b: &mut Bencher) {
     let identity_matrix = Matrix::new(MatrixType::Identity);
     let profile = Profile::new(STR_1, &identity_matrix);
 
-    b.iter(|| global_alignment_score(&profile, STR_2, 1, 1));
+    b.iter(|| global_alignment_score(&profile, STR_2, 1, 1).unwrap());
 }
 
 #[bench]
@@ -35,7 +35,7 @@ fn bench_semiglobal_reuse_profile(b: &mut Bencher) {
     let identity_matrix = Matrix::new(MatrixType::Identity);
     let profile = Profile::new(STR_1, &identity_matrix);
 
-    b.iter(|| semi_global_alignment_score(&profile, STR_2, 1, 1));
+    b.iter(|| semi_global_alignment_score(&profile, STR_2, 1, 1).unwrap());
 }
 
 #[bench]
@@ -43,7 +43,7 @@ fn bench_local_fresh_profile(b: &mut Bencher) {
     let identity_matrix = Matrix::new(MatrixType::Identity);
     b.iter(|| {
         let profile = Profile::new(STR_1, &identity_matrix);
-        local_alignment_score(&profile, STR_2, 1, 1)
+        local_alignment_score(&profile, STR_2, 1, 1).unwrap()
     });
 }
 
@@ -53,7 +53,7 @@ fn bench_global_fresh_profile(b: &mut Bencher) {
 
     b.iter(|| {
         let profile = Profile::new(STR_1, &identity_matrix);
-        global_alignment_score(&profile, STR_2, 1, 1)
+        global_alignment_score(&profile, STR_2, 1, 1).unwrap()
     });
 }
 
@@ -63,6 +63,6 @@ fn bench_semiglobal_fresh_profile(b: &mut Bencher) {
 
     b.iter(|| {
         let profile = Profile::new(STR_1, &identity_matrix);
-        semi_global_alignment_score(&profile, STR_2, 1, 1)
+        semi_global_alignment_score(&profile, STR_2, 1, 1).unwrap()
     });
 }
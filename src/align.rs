@@ -5,25 +5,41 @@
 
 use libc::c_int;
 
+use crate::error::ParasailError;
 use crate::matrix::Matrix;
+use crate::ops::{ops_from_traces, ops_to_cigar};
 use parasail_sys::{
-    parasail_nw_striped_profile_sat, parasail_result_free, parasail_result_get_length,
-    parasail_result_get_matches, parasail_result_get_score, parasail_result_get_similar,
-    parasail_sg_qx_striped_profile_sat,
+    parasail_nw_striped_profile_16, parasail_nw_striped_profile_32, parasail_nw_striped_profile_64,
+    parasail_nw_striped_profile_8, parasail_nw_striped_profile_sat, parasail_nw_banded,
+    parasail_sg_qx_striped_profile_16, parasail_sg_qx_striped_profile_32, parasail_sg_qx_striped_profile_64,
+    parasail_sg_qx_striped_profile_8, parasail_sg_qx_striped_profile_sat,
+    parasail_sg_striped_profile_16, parasail_sg_striped_profile_32, parasail_sg_striped_profile_64,
+    parasail_sg_striped_profile_8, parasail_sw_striped_profile_16, parasail_sw_striped_profile_32,
+    parasail_sw_striped_profile_64, parasail_sw_striped_profile_8,
     parasail_sg_stats_striped_sat, parasail_sg_striped_profile_sat, parasail_sw_stats_striped_sat,
     parasail_sw_striped_profile_sat, parasail_sw_striped_sat,
-    parasail_traceback_free, parasail_result_get_traceback,parasail_sg_trace_striped_sat,
-    parasail_sg_dx_trace_striped_sat, // parasail_sg_dx_stats_striped_sat,
-    parasail_sg_qx_stats_striped_sat, // parasail_sg_qx_trace_striped_sat,
-    parasail_sg_trace_scan_sat,
-    parasail_result_get_cigar,
-    parasail_cigar_free,
-    parasail_cigar_decode
+    parasail_sg_trace_striped_sat,
+    parasail_sg_dx_trace_striped_sat, parasail_sg_dx_stats_striped_sat,
+    parasail_sg_qx_stats_striped_sat, parasail_sg_qx_trace_striped_sat,
+    parasail_sw_trace_striped_sat,
+    parasail_sg_trace_scan_sat, parasail_nw_trace_striped_sat, parasail_nw_stats_striped_sat,
+    parasail_nw_stats_striped_profile_8, parasail_nw_stats_striped_profile_16, parasail_nw_stats_striped_profile_32,
+    parasail_nw_stats_striped_profile_64, parasail_nw_stats_striped_profile_sat,
+    parasail_sg_stats_striped_profile_8, parasail_sg_stats_striped_profile_16, parasail_sg_stats_striped_profile_32,
+    parasail_sg_stats_striped_profile_64, parasail_sg_stats_striped_profile_sat,
+    parasail_sw_stats_striped_profile_8, parasail_sw_stats_striped_profile_16, parasail_sw_stats_striped_profile_32,
+    parasail_sw_stats_striped_profile_64, parasail_sw_stats_striped_profile_sat,
+    parasail_nw_trace_striped_profile_8, parasail_nw_trace_striped_profile_16, parasail_nw_trace_striped_profile_32,
+    parasail_nw_trace_striped_profile_64, parasail_nw_trace_striped_profile_sat,
+    parasail_sg_trace_striped_profile_8, parasail_sg_trace_striped_profile_16, parasail_sg_trace_striped_profile_32,
+    parasail_sg_trace_striped_profile_64, parasail_sg_trace_striped_profile_sat,
+    parasail_sw_trace_striped_profile_8, parasail_sw_trace_striped_profile_16, parasail_sw_trace_striped_profile_32,
+    parasail_sw_trace_striped_profile_64, parasail_sw_trace_striped_profile_sat,
 };
-use crate::profile::Profile;
+use crate::profile::{profile_kernel, Profile, StatsProfile};
+use crate::result::{ParasailResult, Traceback};
 // use crate::MatrixType;
 // use std::os::raw::c_char;
-use std::ffi::{CString, CStr};
 
 /// Provides a score for global pairwise alignment, using a vectorized version of [Needleman-Wunsch](https://en.wikipedia.org/wiki/Needleman%E2%80%93Wunsch_algorithm).
 ///
@@ -38,30 +54,34 @@ use std::ffi::{CString, CStr};
 /// let profile_ident = Profile::new(query, &identity_matrix);
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
-/// assert_eq!(50, global_alignment_score(&profile_ident, reference, 1, 1));
+/// assert_eq!(50, global_alignment_score(&profile_ident, reference, 1, 1).unwrap());
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTCCTTTTTTNNNNNNNNN";
-/// assert_eq!(48, global_alignment_score(&profile_ident, reference, 1, 1));
+/// assert_eq!(48, global_alignment_score(&profile_ident, reference, 1, 1).unwrap());
 /// ```
 pub fn global_alignment_score(
     query_profile: &Profile,
     database_sequence: &[u8],
     open_cost: i32,
     gap_extend_cost: i32,
-) -> i32 {
-    unsafe {
-        let result = parasail_nw_striped_profile_sat(
-            **query_profile,
+) -> Result<i32, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(profile_kernel!(
+            query_profile,
+            [
+                parasail_nw_striped_profile_8,
+                parasail_nw_striped_profile_16,
+                parasail_nw_striped_profile_32,
+                parasail_nw_striped_profile_64,
+                parasail_nw_striped_profile_sat
+            ],
             database_sequence.as_ptr(),
             database_sequence.len() as c_int,
             open_cost,
             gap_extend_cost,
-        );
-        
-        let score = (*result).score;
-        parasail_result_free(result);
-        score
-    }
+        ))?
+    };
+    Ok(result.score())
 }
 
 /// Provides a score for semi-global pairwise alignment using a vectorized algorithm.
@@ -77,32 +97,37 @@ pub fn global_alignment_score(
 /// let profile_ident = Profile::new(query, &identity_matrix);
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
-/// assert_eq!(50, semi_global_alignment_score(&profile_ident, reference, 1, 1));
+/// assert_eq!(50, semi_global_alignment_score(&profile_ident, reference, 1, 1).unwrap());
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTCCTTTTTTNNNNNNNNN";
-/// assert_eq!(48, semi_global_alignment_score(&profile_ident, reference, 1, 1));
+/// assert_eq!(48, semi_global_alignment_score(&profile_ident, reference, 1, 1).unwrap());
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTT";
-/// assert_eq!(35, semi_global_alignment_score(&profile_ident, reference, 1, 1));
+/// assert_eq!(35, semi_global_alignment_score(&profile_ident, reference, 1, 1).unwrap());
 /// ```
 pub fn semi_global_alignment_score(
     query_profile: &Profile,
     database_sequence: &[u8],
     open_cost: i32,
     gap_extend_cost: i32,
-) -> i32 {
-    unsafe {
-        let result = parasail_sg_striped_profile_sat(
-            **query_profile,
+) -> Result<i32, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(profile_kernel!(
+            query_profile,
+            [
+                parasail_sg_striped_profile_8,
+                parasail_sg_striped_profile_16,
+                parasail_sg_striped_profile_32,
+                parasail_sg_striped_profile_64,
+                parasail_sg_striped_profile_sat
+            ],
             database_sequence.as_ptr(),
             database_sequence.len() as c_int,
             open_cost,
             gap_extend_cost,
-        );
-        let score = (*result).score;
-        parasail_result_free(result);
-        score
-    }
+        ))?
+    };
+    Ok(result.score())
 }
 
 /// Provides a score for semi-global pairwise alignment using a vectorized algorithm. Does not penalize gaps at beginning and end of s1/query only.
@@ -111,19 +136,24 @@ pub fn semi_global_qx_alignment_score(
     database_sequence: &[u8],
     open_cost: i32,
     gap_extend_cost: i32,
-) -> i32 {
-    unsafe {
-        let result = parasail_sg_qx_striped_profile_sat(
-            **query_profile,
+) -> Result<i32, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(profile_kernel!(
+            query_profile,
+            [
+                parasail_sg_qx_striped_profile_8,
+                parasail_sg_qx_striped_profile_16,
+                parasail_sg_qx_striped_profile_32,
+                parasail_sg_qx_striped_profile_64,
+                parasail_sg_qx_striped_profile_sat
+            ],
             database_sequence.as_ptr(),
             database_sequence.len() as c_int,
             open_cost,
             gap_extend_cost,
-        );
-        let score = (*result).score;
-        parasail_result_free(result);
-        score
-    }
+        ))?
+    };
+    Ok(result.score())
 }
 
 /// Returns a score for local pairwise alignment using a vectorized version of [Smith-Waterman](https://en.wikipedia.org/wiki/Smith%E2%80%93Waterman_algorithm).
@@ -137,32 +167,37 @@ pub fn semi_global_qx_alignment_score(
 /// let profile_ident = Profile::new(query, &identity_matrix);
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
-/// assert_eq!(50, local_alignment_score(&profile_ident, reference, 1, 1));
+/// assert_eq!(50, local_alignment_score(&profile_ident, reference, 1, 1).unwrap());
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTCCTTTTTTNNNNNNNNN";
-/// assert_eq!(48, local_alignment_score(&profile_ident, reference, 1, 1));
+/// assert_eq!(48, local_alignment_score(&profile_ident, reference, 1, 1).unwrap());
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTT";
-/// assert_eq!(35, local_alignment_score(&profile_ident, reference, 1, 1));
+/// assert_eq!(35, local_alignment_score(&profile_ident, reference, 1, 1).unwrap());
 /// ```
 pub fn local_alignment_score(
     query_profile: &Profile,
     database_sequence: &[u8],
     open_cost: i32,
     gap_extend_cost: i32,
-) -> i32 {
-    unsafe {
-        let result = parasail_sw_striped_profile_sat(
-            **query_profile,
+) -> Result<i32, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(profile_kernel!(
+            query_profile,
+            [
+                parasail_sw_striped_profile_8,
+                parasail_sw_striped_profile_16,
+                parasail_sw_striped_profile_32,
+                parasail_sw_striped_profile_64,
+                parasail_sw_striped_profile_sat
+            ],
             database_sequence.as_ptr(),
             database_sequence.len() as c_int,
             open_cost,
             gap_extend_cost,
-        );
-        let score = (*result).score;
-        parasail_result_free(result);
-        score
-    }
+        ))?
+    };
+    Ok(result.score())
 }
 
 /// Returns a score for local pairwise alignment using a vectorized version of [Smith-Waterman](https://en.wikipedia.org/wiki/Smith%E2%80%93Waterman_algorithm).
@@ -175,13 +210,13 @@ pub fn local_alignment_score(
 /// let query = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
-/// assert_eq!(50, local_alignment_score_no_profile(query, reference, 1, 1, &identity_matrix));
+/// assert_eq!(50, local_alignment_score_no_profile(query, reference, 1, 1, &identity_matrix).unwrap());
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTCCTTTTTTNNNNNNNNN";
-/// assert_eq!(48, local_alignment_score_no_profile(query, reference, 1, 1, &identity_matrix));
+/// assert_eq!(48, local_alignment_score_no_profile(query, reference, 1, 1, &identity_matrix).unwrap());
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTT";
-/// assert_eq!(35, local_alignment_score_no_profile(query, reference, 1, 1, &identity_matrix));
+/// assert_eq!(35, local_alignment_score_no_profile(query, reference, 1, 1, &identity_matrix).unwrap());
 /// ```
 pub fn local_alignment_score_no_profile(
     query: &[u8],
@@ -189,9 +224,9 @@ pub fn local_alignment_score_no_profile(
     open_cost: i32,
     gap_extend_cost: i32,
     sub_matrix: &Matrix,
-) -> i32 {
-    unsafe {
-        let result = parasail_sw_striped_sat(
+) -> Result<i32, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sw_striped_sat(
             query.as_ptr(),
             query.len() as c_int,
             database_sequence.as_ptr(),
@@ -199,14 +234,53 @@ pub fn local_alignment_score_no_profile(
             open_cost,
             gap_extend_cost,
             **sub_matrix,
-        );
-        let score = (*result).score;
-        parasail_result_free(result);
-        score
-    }
+        ))?
+    };
+    Ok(result.score())
+}
+
+/// Returns a score for global pairwise alignment computed only within `band` cells of the main diagonal.
+///
+/// Filling a band of the dynamic programming matrix instead of all of it takes time and memory proportional to the
+/// sequence length times the band, so highly similar sequences of roughly equal length can be aligned far faster than
+/// with `global_alignment_score`. The score is exact as long as the best alignment stays within the band, which needs
+/// `band` to be at least the difference in length of the sequences; otherwise it's the best score of an alignment that
+/// does.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let query = b"ACGTACGTACGTACGTACGT";
+/// let reference = b"ACGTACGTACCGTACGTACGT";
+/// assert_eq!(18, global_alignment_score_banded(query, reference, 2, 1, 3, &matrix).unwrap());
+/// ```
+pub fn global_alignment_score_banded(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    band: usize,
+    substitution_matrix: &Matrix,
+) -> Result<i32, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_nw_banded(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            band as c_int,
+            **substitution_matrix,
+        ))?
+    };
+    Ok(result.score())
 }
 
 /// Stores statistics from an alignment.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AlignmentStats {
     /// The score according to the substitution matrix and gap penalty scheme used.
     pub score: i64,
@@ -220,9 +294,84 @@ pub struct AlignmentStats {
     pub query_end: usize,
     /// The starting index (0-based) of the alignment in the reference.
     pub ref_end: usize,
+    /// The length of the whole query.
+    pub query_len: usize,
+    /// The length of the whole reference.
+    pub ref_len: usize,
+}
+
+impl AlignmentStats {
+    /// The score divided by the alignment length, `score / align_length`, or 0 for an empty alignment.
+    ///
+    /// Raw scores grow with the length of the alignment, so this is the fairer way to compare hits from queries of
+    /// different lengths.
+    pub fn length_normalized_score(&self) -> f64 {
+        normalize(self.score, self.align_length as i64)
+    }
+
+    /// The score as a fraction of the best score the query could get, `score / self_score`, where `self_score` is
+    /// usually the query's `self_alignment_score`. A perfect full-length hit scores 1.
+    pub fn identity_normalized_score(&self, self_score: i64) -> f64 {
+        normalize(self.score, self_score)
+    }
+}
+
+/// Statistics from an alignment, along with where it begins and how many mismatches and gaps it has, which the stats
+/// kernels can't tell. These come from a traceback of the same alignment, so they cost a second pass and memory for
+/// the whole dynamic programming matrix; see `semi_global_alignment_stats_with_trace` and the like.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AlignmentStatsWithTrace {
+    /// The statistics from the stats kernel.
+    pub stats: AlignmentStats,
+    /// The index (0-based) of the first query character in the alignment.
+    pub query_begin: usize,
+    /// The index (0-based) of the first reference character in the alignment.
+    pub ref_begin: usize,
+    /// Number of aligned pairs of differing characters.
+    pub num_mismatches: u64,
+    /// Number of gaps opened in either sequence, not counting free end gaps outside the aligned region.
+    pub num_gap_opens: u64,
+    /// Number of characters aligned against a gap, not counting free end gaps outside the aligned region.
+    pub num_gap_bases: u64,
+}
+
+impl AlignmentStatsWithTrace {
+    /// The fraction of the query covered by the alignment, `(query_end - query_begin) / query_len`, or 0 for an empty
+    /// query.
+    pub fn query_coverage(&self) -> f64 {
+        ratio(self.stats.query_end - self.query_begin, self.stats.query_len)
+    }
+
+    /// The fraction of the reference covered by the alignment, `(ref_end - ref_begin) / ref_len`, or 0 for an empty
+    /// reference.
+    pub fn ref_coverage(&self) -> f64 {
+        ratio(self.stats.ref_end - self.ref_begin, self.stats.ref_len)
+    }
+}
+
+/// The score of a sequence aligned against itself with no gaps: the sum of the substitution scores of each character
+/// with itself. This is the most any alignment of the sequence can score, which makes it the natural scale for
+/// `identity_normalized_score`.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let query = b"ACGTACGTAC";
+/// let self_score = self_alignment_score(query, &matrix);
+/// assert_eq!(10, self_score);
+///
+/// let alignment = semi_global_traceback(query, b"TTACGTTCGTACTT", 1, 1, &matrix).unwrap();
+/// assert_eq!(8.0 / 10.0, alignment.identity_normalized_score(self_score));
+/// assert_eq!(8.0 / 10.0, alignment.length_normalized_score());
+/// ```
+pub fn self_alignment_score(sequence: &[u8], matrix: &Matrix) -> i64 {
+    sequence.iter().map(|&c| matrix.score(c, c) as i64).sum()
 }
 
 /// Stores statistics and traceback strings from an alignment.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TracebackResults {
     /// The score according to the substitution matrix and gap penalty scheme used.
     pub score: i64,
@@ -241,10 +390,469 @@ pub struct TracebackResults {
     /// String representing query sequence in traceback
     pub comp_trace: String,
     /// String representing query sequence in traceback
-    pub ref_trace: String
+    pub ref_trace: String,
+    /// Number of query characters before the aligned region, soft-clip style.
+    pub query_prefix_clip: usize,
+    /// Number of query characters after the aligned region, soft-clip style.
+    pub query_suffix_clip: usize,
+}
+
+impl TracebackResults {
+    /// The number of columns in the alignment. Gaps at either end of the trace (the free end gaps of a semi-global
+    /// alignment) aren't part of the aligned region, so they're excluded here and from all of the other statistics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// let query = b"ACGTTTACGCACCA";
+    /// let reference = b"ACGTTTTACGGACCA";
+    ///
+    /// let alignment = semi_global_traceback(query, reference, 1, 1, &matrix).unwrap();
+    /// assert_eq!(15, alignment.aligned_length());
+    /// assert_eq!(13, alignment.num_matches());
+    /// assert_eq!(1, alignment.num_mismatches());
+    /// assert_eq!(1, alignment.num_gap_opens());
+    /// assert_eq!(1, alignment.num_gap_bases());
+    /// assert_eq!(13.0 / 15.0, alignment.identity());
+    /// ```
+    pub fn aligned_length(&self) -> usize {
+        let (first, last) = self.aligned_columns();
+        last - first
+    }
+
+    /// The number of columns where both sequences have the same character (ignoring case).
+    pub fn num_matches(&self) -> usize {
+        self.column_pairs().filter(|&(q, r)| q != b'-' && r != b'-' && q.eq_ignore_ascii_case(&r)).count()
+    }
+
+    /// The number of columns where the sequences have different characters.
+    pub fn num_mismatches(&self) -> usize {
+        self.column_pairs().filter(|&(q, r)| q != b'-' && r != b'-' && !q.eq_ignore_ascii_case(&r)).count()
+    }
+
+    /// The number of gaps opened in either sequence, i.e. the number of runs of consecutive gap columns.
+    pub fn num_gap_opens(&self) -> usize {
+        let mut opens = 0;
+        let (mut in_query_gap, mut in_ref_gap) = (false, false);
+        for (q, r) in self.column_pairs() {
+            if q == b'-' && !in_query_gap {
+                opens += 1;
+            }
+            if r == b'-' && !in_ref_gap {
+                opens += 1;
+            }
+            in_query_gap = q == b'-';
+            in_ref_gap = r == b'-';
+        }
+        opens
+    }
+
+    /// The number of columns with a gap in either sequence.
+    pub fn num_gap_bases(&self) -> usize {
+        self.column_pairs().filter(|&(q, r)| q == b'-' || r == b'-').count()
+    }
+
+    /// The fraction of alignment columns which are exact matches.
+    pub fn identity(&self) -> f64 {
+        ratio(self.num_matches(), self.aligned_length())
+    }
+
+    /// The fraction of alignment columns which are matches or positively scoring substitutions, as marked in
+    /// `comp_trace`.
+    pub fn similarity(&self) -> f64 {
+        let (first, last) = self.aligned_columns();
+        let similar = self.comp_trace.as_bytes()[first..last].iter().filter(|&&c| c == b'|').count();
+        ratio(similar, self.aligned_length())
+    }
+
+    /// The fraction of alignment columns which contain a gap.
+    pub fn gap_fraction(&self) -> f64 {
+        ratio(self.num_gap_bases(), self.aligned_length())
+    }
+
+    /// The score divided by the number of aligned columns, `score / aligned_length()`, or 0 for an empty alignment.
+    pub fn length_normalized_score(&self) -> f64 {
+        normalize(self.score, self.aligned_length() as i64)
+    }
+
+    /// The score as a fraction of the best score the query could get, `score / self_score`, where `self_score` is
+    /// usually the query's `self_alignment_score`. A perfect full-length hit scores 1.
+    pub fn identity_normalized_score(&self, self_score: i64) -> f64 {
+        normalize(self.score, self_score)
+    }
+
+    /// The starting index (0-based) of the trace in the query. The trace ends at `query_end`, since the traceback
+    /// functions drop the end gaps parasail adds past the end of a semi-global alignment.
+    pub fn query_begin(&self) -> usize {
+        self.query_end - self.query_trace.bytes().filter(|&c| c != b'-').count()
+    }
+
+    /// The starting index (0-based) of the trace in the reference. The trace ends at `ref_end`, as for `query_begin`.
+    pub fn ref_begin(&self) -> usize {
+        self.ref_end - self.ref_trace.bytes().filter(|&c| c != b'-').count()
+    }
+
+    /// Iterates over the columns of the trace as pairs of query and reference indices (0-based), with `None` on the
+    /// side of a gap, like pysam's `get_aligned_pairs`. Every column of the trace is included, end gaps too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// let query = b"GATACA";
+    /// let reference = b"CCGATTACA";
+    ///
+    /// let alignment = semi_global_dx_traceback(query, reference, 1, 1, &matrix).unwrap();
+    /// let pairs: Vec<_> = alignment.aligned_pairs().skip_while(|&(q, _)| q.is_none()).collect();
+    /// assert_eq!((Some(0), Some(2)), pairs[0]);
+    /// assert_eq!(7, pairs.len());
+    /// assert_eq!(1, pairs.iter().filter(|&&(q, _)| q.is_none()).count());
+    /// ```
+    pub fn aligned_pairs(&self) -> impl Iterator<Item = (Option<usize>, Option<usize>)> + '_ {
+        let mut query_pos = self.query_begin();
+        let mut ref_pos = self.ref_begin();
+
+        self.query_trace.bytes().zip(self.ref_trace.bytes()).map(move |(q, r)| {
+            let query_index = if q == b'-' {
+                None
+            } else {
+                query_pos += 1;
+                Some(query_pos - 1)
+            };
+            let ref_index = if r == b'-' {
+                None
+            } else {
+                ref_pos += 1;
+                Some(ref_pos - 1)
+            };
+            (query_index, ref_index)
+        })
+    }
+
+    /// Slides a window of `window` columns over the aligned region, `step` columns at a time, and reports the identity
+    /// within each, so locally divergent stretches such as recombination breakpoints or chimeric junctions stand out
+    /// against the identity of the alignment as a whole. Windows only cover the aligned region (see
+    /// `aligned_length`); one shorter than `window` makes up a single window, and columns past the last full window
+    /// are left out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` or `step` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// let query = b"ACGTACGTAATAATAA";
+    /// let reference = b"ACGTACGTAAAAAAAA";
+    ///
+    /// let alignment = semi_global_traceback(query, reference, 1, 1, &matrix).unwrap();
+    /// let windows = alignment.window_identities(8, 8);
+    /// assert_eq!(vec![1.0, 0.75], windows.iter().map(|w| w.identity).collect::<Vec<_>>());
+    /// assert_eq!((8, 16), (windows[1].query_begin, windows[1].query_end));
+    /// ```
+    pub fn window_identities(&self, window: usize, step: usize) -> Vec<WindowIdentity> {
+        assert!(window > 0 && step > 0, "windows must have a length and a step");
+
+        // the query and reference positions before each column of the trace
+        let (mut query_pos, mut ref_pos) = (self.query_begin(), self.ref_begin());
+        let mut positions = Vec::with_capacity(self.query_trace.len() + 1);
+        let mut matches = Vec::with_capacity(self.query_trace.len() + 1);
+        let mut matched = 0;
+        for (q, r) in self.query_trace.bytes().zip(self.ref_trace.bytes()) {
+            positions.push((query_pos, ref_pos));
+            matches.push(matched);
+            query_pos += (q != b'-') as usize;
+            ref_pos += (r != b'-') as usize;
+            matched += (q != b'-' && r != b'-' && q.eq_ignore_ascii_case(&r)) as usize;
+        }
+        positions.push((query_pos, ref_pos));
+        matches.push(matched);
+
+        let (first, last) = self.aligned_columns();
+        let window = window.min(last - first);
+        if window == 0 {
+            return Vec::new();
+        }
+
+        (first..=last - window)
+            .step_by(step)
+            .map(|start| {
+                let end = start + window;
+                WindowIdentity {
+                    query_begin: positions[start].0,
+                    query_end: positions[end].0,
+                    ref_begin: positions[start].1,
+                    ref_end: positions[end].1,
+                    identity: ratio(matches[end] - matches[start], window),
+                }
+            })
+            .collect()
+    }
+
+    /// The extended CIGAR string of the trace (with `=` and `X` rather than `M`), end gaps included.
+    pub fn cigar(&self) -> String {
+        ops_to_cigar(&ops_from_traces(&self.query_trace, &self.ref_trace))
+    }
+
+    /// Trims junk off either end of the alignment, as BWA clips read ends: at each end, the run of columns with the
+    /// lowest cumulative score is cut off if that score is negative (the shortest such run, on a tie). Columns are
+    /// scored with `matrix` and the gap costs, a gap's first column costing `open_cost` and the rest `gap_extend_cost`.
+    ///
+    /// The result covers the rest of the aligned region (see `aligned_length`), without any end gaps around it. Its
+    /// score goes up by whatever the trimmed columns cost, and trimmed query characters become part of the soft clips,
+    /// so the coordinates and `cigar` describe the trimmed alignment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// let query = b"TTTTACGTACGTACGT";
+    /// let reference = b"GGCCCCACGTACGTACGTGG";
+    ///
+    /// let alignment = semi_global_dx_traceback(query, reference, 3, 1, &matrix).unwrap();
+    /// assert_eq!(8, alignment.score);
+    ///
+    /// let trimmed = alignment.trim_ends(3, 1, &matrix);
+    /// assert_eq!(12, trimmed.score);
+    /// assert_eq!("12=", trimmed.cigar());
+    /// assert_eq!((4, 6), (trimmed.query_begin(), trimmed.ref_begin()));
+    /// assert_eq!(4, trimmed.query_prefix_clip);
+    /// ```
+    pub fn trim_ends(&self, open_cost: i32, gap_extend_cost: i32, matrix: &Matrix) -> TracebackResults {
+        let query = self.query_trace.as_bytes();
+        let reference = self.ref_trace.as_bytes();
+        let (first, last) = self.aligned_columns();
+
+        let column_score = |k: usize| -> i64 {
+            let (q, r) = (query[k], reference[k]);
+            let extends = k > first && ((q == b'-' && query[k - 1] == b'-') || (r == b'-' && reference[k - 1] == b'-'));
+            if q != b'-' && r != b'-' {
+                matrix.score(q, r) as i64
+            } else if extends {
+                -(gap_extend_cost as i64)
+            } else {
+                -(open_cost as i64)
+            }
+        };
+
+        // the number of columns to cut, and what they scored
+        let worst_run = |columns: &mut dyn Iterator<Item = usize>| {
+            let (mut sum, mut worst, mut cut) = (0, 0, 0);
+            for (n, k) in columns.enumerate() {
+                sum += column_score(k);
+                if sum < worst {
+                    worst = sum;
+                    cut = n + 1;
+                }
+            }
+            (cut, worst)
+        };
+        let (head, head_score) = worst_run(&mut (first..last));
+        let (tail, tail_score) = worst_run(&mut (first + head..last).rev());
+        let (start, end) = (first + head, last - tail);
+
+        let residues = |trace: &[u8]| trace.iter().filter(|&&c| c != b'-').count();
+        TracebackResults {
+            score: self.score - head_score - tail_score,
+            query_end: self.query_end - residues(&query[end..]),
+            ref_end: self.ref_end - residues(&reference[end..]),
+            query_trace: self.query_trace[start..end].to_string(),
+            comp_trace: self.comp_trace.get(start..end).unwrap_or("").to_string(),
+            ref_trace: self.ref_trace[start..end].to_string(),
+            query_prefix_clip: self.query_prefix_clip - residues(&query[..first]) + residues(&query[..start]),
+            query_suffix_clip: self.query_suffix_clip - residues(&query[last..]) + residues(&query[end..]),
+        }
+    }
+
+    /// The range of trace columns between the first and last columns where both sequences have a character.
+    fn aligned_columns(&self) -> (usize, usize) {
+        let query = self.query_trace.as_bytes();
+        let reference = self.ref_trace.as_bytes();
+        let aligned = |k: &usize| query[*k] != b'-' && reference[*k] != b'-';
+
+        match (0..query.len()).find(aligned) {
+            Some(first) => (first, (0..query.len()).rev().find(aligned).unwrap_or(first) + 1),
+            None => (0, 0),
+        }
+    }
+
+    fn column_pairs(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        let (first, last) = self.aligned_columns();
+        self.query_trace.as_bytes()[first..last]
+            .iter()
+            .cloned()
+            .zip(self.ref_trace.as_bytes()[first..last].iter().cloned())
+    }
+}
+
+/// The identity within one window of an alignment, from `TracebackResults::window_identities`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowIdentity {
+    /// The index (0-based) of the first query character in the window.
+    pub query_begin: usize,
+    /// The index (0-based, exclusive) of the last query character in the window.
+    pub query_end: usize,
+    /// The index (0-based) of the first reference character in the window.
+    pub ref_begin: usize,
+    /// The index (0-based, exclusive) of the last reference character in the window.
+    pub ref_end: usize,
+    /// The fraction of the window's columns which are exact matches.
+    pub identity: f64,
+}
+
+/// Copies the score, end positions, and trace strings of a trace kernel's result into a `TracebackResults`.
+///
+/// parasail's semi-global traces carry on past the alignment's last cell with the end gaps out to the ends of both
+/// sequences, so the trace would cover characters past `query_end` or `ref_end`. Those columns are dropped, and query
+/// characters among them count towards the suffix clip instead.
+fn traceback_results(
+    result: &ParasailResult,
+    traceback: &Traceback,
+    query_len: usize,
+    ref_len: usize,
+) -> TracebackResults {
+    let (query_end, ref_end) = (result.query_end(), result.ref_end());
+    let (query, reference) = (traceback.query(), traceback.reference());
+    let keep = query.len() - end_overhang(query, reference, query_len - query_end, ref_len - ref_end);
+    let (query_trace, ref_trace) = (&query[..keep], &reference[..keep]);
+    let (query_prefix_clip, query_suffix_clip) = soft_clips(query_trace, ref_trace, query_end, query_len);
+
+    TracebackResults {
+        score: result.score() as i64,
+        query_end,
+        ref_end,
+        query_trace: query_trace.to_string(),
+        comp_trace: traceback.comp().get(..keep).unwrap_or("").to_string(),
+        ref_trace: ref_trace.to_string(),
+        query_prefix_clip,
+        query_suffix_clip,
+    }
+}
+
+/// The number of gap columns at the end of a trace holding characters past the end of the alignment, when
+/// `query_left` query and `ref_left` reference characters follow it.
+fn end_overhang(query_trace: &str, ref_trace: &str, mut query_left: usize, mut ref_left: usize) -> usize {
+    let mut columns = 0;
+    for (q, r) in query_trace.bytes().zip(ref_trace.bytes()).rev() {
+        if r == b'-' && q != b'-' && query_left > 0 {
+            query_left -= 1;
+        } else if q == b'-' && r != b'-' && ref_left > 0 {
+            ref_left -= 1;
+        } else {
+            break;
+        }
+        columns += 1;
+    }
+    columns
+}
+
+/// Counts the query characters outside the aligned region at each end of a trace. These are the characters before
+/// the trace starts or after it ends, plus any run of query characters facing gaps at either end of the trace (the
+/// free end gaps of a semi-global alignment), which would be soft clips in a SAM record.
+pub(crate) fn soft_clips(query_trace: &str, ref_trace: &str, query_end: usize, query_len: usize) -> (usize, usize) {
+    let columns: Vec<(u8, u8)> = query_trace.bytes().zip(ref_trace.bytes()).collect();
+    let query_residues = columns.iter().filter(|&&(q, _)| q != b'-').count();
+    let overhang = |&&(q, r): &&(u8, u8)| r == b'-' || q == b'-';
+
+    let leading = columns
+        .iter()
+        .take_while(overhang)
+        .filter(|&&(q, _)| q != b'-')
+        .count();
+    let trailing = columns
+        .iter()
+        .rev()
+        .take_while(overhang)
+        .filter(|&&(q, _)| q != b'-')
+        .count();
+
+    (
+        query_end - query_residues + leading,
+        query_len - query_end + trailing,
+    )
+}
+
+fn normalize(score: i64, scale: i64) -> f64 {
+    if scale == 0 {
+        0.0
+    } else {
+        score as f64 / scale as f64
+    }
+}
+
+pub(crate) fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+/// Collects the statistics of a stats kernel's result.
+fn alignment_stats(result: &ParasailResult, query_len: usize, ref_len: usize) -> AlignmentStats {
+    AlignmentStats {
+        score: result.score() as i64,
+        num_matches: result.matches().unwrap_or(0) as u64,
+        num_positive_subs: result.similar().unwrap_or(0) as u64,
+        align_length: result.length().unwrap_or(0),
+        query_end: result.query_end(),
+        ref_end: result.ref_end(),
+        query_len,
+        ref_len,
+    }
+}
+
+/// Adds where the alignment begins and how many mismatches and gaps it has, from the result of the trace kernel
+/// matching the one `stats` came from. Gaps at the very start or end of the trace are the free end gaps of a
+/// semi-global alignment rather than part of the aligned region, so they're skipped.
+fn with_trace(
+    stats: AlignmentStats,
+    trace: &ParasailResult,
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    substitution_matrix: &Matrix,
+) -> Result<AlignmentStatsWithTrace, ParasailError> {
+    let mut cigar = trace.cigar(query_sequence, database_sequence, substitution_matrix)?;
+    cigar.strip_leading_gaps();
+
+    let is_gap = |op: u8| op == b'I' || op == b'D';
+    let trailing_gaps = cigar.ops.iter().rev().take_while(|&&(op, _)| is_gap(op)).count();
+    let aligned = &cigar.ops[..cigar.ops.len() - trailing_gaps];
+    let (mut num_mismatches, mut num_gap_opens, mut num_gap_bases) = (0, 0, 0);
+    let mut previous = None;
+    for &(op, len) in aligned {
+        match op {
+            b'X' => num_mismatches += len as u64,
+            b'I' | b'D' => {
+                // an insertion right after a deletion opens a second gap
+                if previous != Some(op) {
+                    num_gap_opens += 1;
+                }
+                num_gap_bases += len as u64;
+            }
+            _ => {}
+        }
+        previous = Some(op);
+    }
+
+    Ok(AlignmentStatsWithTrace {
+        stats,
+        query_begin: cigar.query_begin,
+        ref_begin: cigar.ref_begin,
+        num_mismatches,
+        num_gap_opens,
+        num_gap_bases,
+    })
 }
 
 /// Stores statistics and traceback strings from an alignment with SAM Cigar.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TracebackResultsWithCigar {
     /// The score according to the substitution matrix and gap penalty scheme used.
     pub score: i64,
@@ -265,7 +873,11 @@ pub struct TracebackResultsWithCigar {
     /// String representing query sequence in traceback
     pub ref_trace: String,
     /// String with SAM Cigar data
-    pub cigar_trace: String
+    pub cigar_trace: String,
+    /// Number of query characters before the aligned region, soft-clip style.
+    pub query_prefix_clip: usize,
+    /// Number of query characters after the aligned region, soft-clip style.
+    pub query_suffix_clip: usize,
 }
 
 
@@ -273,7 +885,7 @@ pub struct TracebackResultsWithCigar {
 ///
 /// This results in a series of statistics, including a score that corresponds to a global alignment for the query sequence and a local alignment for the reference sequence. This is particularly useful when checking for the presence of an NGS read in a much longer reference sequence. This behaves like a global alignment, except that gaps at the start or end of the reference sequence's alignment are ignored.
 ///
-/// Other statistics include the number of matching characters, the number of positive substitutions, the length of the found alignment, and the starting point in both sequences where the alignment starts.
+/// Other statistics include the number of matching characters, the number of positive substitutions, the length of the found alignment, and where the alignment ends in both sequences. For where it starts, see `semi_global_alignment_stats_with_trace`.
 ///
 /// # Examples
 ///
@@ -283,7 +895,7 @@ pub struct TracebackResultsWithCigar {
 /// let query = b"AAAACCCCCCCCCCGGG";
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
-/// let stats = semi_global_alignment_stats(query, reference, 1, 1, &identity_matrix);
+/// let stats = semi_global_alignment_stats(query, reference, 1, 1, &identity_matrix).unwrap();
 /// assert_eq!(17, stats.score);
 /// assert_eq!(17, stats.num_matches);
 /// assert_eq!(17, stats.num_positive_subs);
@@ -297,9 +909,9 @@ pub fn semi_global_alignment_stats(
     open_cost: i32,
     gap_extend_cost: i32,
     substitution_matrix: &Matrix,
-) -> AlignmentStats {
-    unsafe {
-        let result = parasail_sg_stats_striped_sat(
+) -> Result<AlignmentStats, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sg_stats_striped_sat(
             query_sequence.as_ptr(),
             query_sequence.len() as c_int,
             database_sequence.as_ptr(),
@@ -307,28 +919,49 @@ pub fn semi_global_alignment_stats(
             open_cost,
             gap_extend_cost,
             **substitution_matrix,
-        );
-
-        let score = parasail_result_get_score(result) as i64;
-        let num_matches = parasail_result_get_matches(result) as u64;
-        let num_subs = parasail_result_get_similar(result) as u64;
-        let align_len = parasail_result_get_length(result) as usize;
-
-        // calculate start from end
-        let query_end = (*result).end_query as usize + 1;
-        let ref_end = (*result).end_ref as usize + 1;
-
-        parasail_result_free(result);
-
-        AlignmentStats {
-            score: score,
-            num_matches: num_matches,
-            num_positive_subs: num_subs,
-            align_length: align_len,
-            query_end: query_end,
-            ref_end: ref_end,
-        }
-    }
+        ))?
+    };
+    Ok(alignment_stats(&result, query_sequence.len(), database_sequence.len()))
+}
+
+/// Provides statistics for semi-global pairwise alignment as `semi_global_alignment_stats` does, along with where
+/// the alignment begins and its mismatches and gaps. These take a second, traceback pass over the sequences, which
+/// keeps the whole dynamic programming matrix.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let identity_matrix = Matrix::new(MatrixType::Identity);
+/// let query = b"AAAACCCCCCCCCCGGG";
+///
+/// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
+/// let traced = semi_global_alignment_stats_with_trace(query, reference, 1, 1, &identity_matrix).unwrap();
+/// assert_eq!(23, traced.stats.ref_end);
+/// assert_eq!(6, traced.ref_begin);
+/// assert_eq!(1.0, traced.query_coverage());
+/// assert_eq!(17.0 / 50.0, traced.ref_coverage());
+/// ```
+pub fn semi_global_alignment_stats_with_trace(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<AlignmentStatsWithTrace, ParasailError> {
+    let stats = semi_global_alignment_stats(query_sequence, database_sequence, open_cost, gap_extend_cost, substitution_matrix)?;
+    let trace = unsafe {
+        ParasailResult::from_raw(parasail_sg_trace_striped_sat(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    with_trace(stats, &trace, query_sequence, database_sequence, substitution_matrix)
 }
 
 /// Provides statistics for semi-global pairwise alignment using a vectorized algorithm. Does not penalize gaps at beginning and end of s1/query only
@@ -338,9 +971,9 @@ pub fn semi_global_qx_alignment_stats(
     open_cost: i32,
     gap_extend_cost: i32,
     substitution_matrix: &Matrix,
-) -> AlignmentStats {
-    unsafe {
-        let result = parasail_sg_qx_stats_striped_sat(
+) -> Result<AlignmentStats, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sg_qx_stats_striped_sat(
             query_sequence.as_ptr(),
             query_sequence.len() as c_int,
             database_sequence.as_ptr(),
@@ -348,40 +981,21 @@ pub fn semi_global_qx_alignment_stats(
             open_cost,
             gap_extend_cost,
             **substitution_matrix,
-        );
-
-        let score = parasail_result_get_score(result) as i64;
-        let num_matches = parasail_result_get_matches(result) as u64;
-        let num_subs = parasail_result_get_similar(result) as u64;
-        let align_len = parasail_result_get_length(result) as usize;
-
-        // calculate start from end
-        let query_end = (*result).end_query as usize + 1;
-        let ref_end = (*result).end_ref as usize + 1;
-
-        parasail_result_free(result);
-
-        AlignmentStats {
-            score: score,
-            num_matches: num_matches,
-            num_positive_subs: num_subs,
-            align_length: align_len,
-            query_end: query_end,
-            ref_end: ref_end,
-        }
-    }
+        ))?
+    };
+    Ok(alignment_stats(&result, query_sequence.len(), database_sequence.len()))
 }
 
-/// For isOnClust-rust
-pub fn semi_global_alignment_trace_scan_sat_cigar(
+/// Provides statistics for semi-global pairwise alignment using a vectorized algorithm. Does not penalize gaps at beginning and end of s2/reference only
+pub fn semi_global_dx_alignment_stats(
     query_sequence: &[u8],
     database_sequence: &[u8],
     open_cost: i32,
     gap_extend_cost: i32,
     substitution_matrix: &Matrix,
-) -> TracebackResultsWithCigar {
-    unsafe {
-        let result = parasail_sg_trace_scan_sat(
+) -> Result<AlignmentStats, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sg_dx_stats_striped_sat(
             query_sequence.as_ptr(),
             query_sequence.len() as c_int,
             database_sequence.as_ptr(),
@@ -389,91 +1003,45 @@ pub fn semi_global_alignment_trace_scan_sat_cigar(
             open_cost,
             gap_extend_cost,
             **substitution_matrix,
-        );
-
-        let score = parasail_result_get_score(result) as i64;
-
-
-        // calculate start from end
-        let query_end = (*result).end_query as usize + 1;
-        let ref_end = (*result).end_ref as usize + 1;
-
-        // Initialize CStrings
-        let c_query_seq = CString::new(query_sequence).unwrap().into_raw();
-        let c_db_seq = CString::new(database_sequence).unwrap().into_raw();
-        let match_char = CString::new("|").unwrap().into_raw();
-        let positive_mismatch_char = CString::new("|").unwrap().into_raw();
-        let negative_mismatch_char = CString::new(":").unwrap().into_raw();
-
+        ))?
+    };
+    Ok(alignment_stats(&result, query_sequence.len(), database_sequence.len()))
+}
 
-        let traceback = parasail_result_get_traceback(
-            result,
-            c_query_seq,
+/// Provides statistics for semi-global pairwise alignment as `semi_global_qx_alignment_stats` does, along with where
+/// the alignment begins and its mismatches and gaps, from a second, traceback pass.
+pub fn semi_global_qx_alignment_stats_with_trace(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<AlignmentStatsWithTrace, ParasailError> {
+    let stats = semi_global_qx_alignment_stats(query_sequence, database_sequence, open_cost, gap_extend_cost, substitution_matrix)?;
+    let trace = unsafe {
+        ParasailResult::from_raw(parasail_sg_qx_trace_striped_sat(
+            query_sequence.as_ptr(),
             query_sequence.len() as c_int,
-            c_db_seq,
+            database_sequence.as_ptr(),
             database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
             **substitution_matrix,
-            *match_char,
-            *positive_mismatch_char,
-            *negative_mismatch_char,
-        );
-
-        // Reclaim CStrings to allow dropping
-        let _c_query_seq = CString::from_raw(c_query_seq);
-        let _c_db_seq = CString::from_raw(c_db_seq);
-        let _match_char = CString::from_raw(match_char);
-        let _positive_mismatch_char = CString::from_raw(positive_mismatch_char);
-        let _negative_mismatch_char = CString::from_raw(negative_mismatch_char);
-        
-     
-
-        // Convert results in the traceback opaque point to rust Strings
-        let query_str = CStr::from_ptr((*traceback).query).to_str().unwrap();
-        let query_trace = String::from(query_str);
-        let comp_str = CStr::from_ptr((*traceback).comp).to_str().unwrap();
-        let comp_trace = String::from(comp_str);
-        let ref_str = CStr::from_ptr((*traceback).ref_).to_str().unwrap();
-        let ref_trace = String::from(ref_str);
-
-        let cigar_result = parasail_result_get_cigar(result,
-            query_str.as_ptr(),
-            query_str.len() as c_int,
-            ref_str.as_ptr(),
-            ref_str.len() as c_int,
-            **substitution_matrix
-        );
-       
-
-        let cigar_str = CStr::from_ptr(parasail_cigar_decode(cigar_result)).to_str().unwrap();
-        let cigar_trace = String::from(cigar_str);
-        
-        parasail_cigar_free(cigar_result);
-        parasail_traceback_free(traceback);
-        parasail_result_free(result);
-
-        TracebackResultsWithCigar {
-            score,
-            query_end,
-            ref_end,
-            query_trace,
-            comp_trace,
-            ref_trace,
-            cigar_trace
-        }
-    }
-    
+        ))?
+    };
+    with_trace(stats, &trace, query_sequence, database_sequence, substitution_matrix)
 }
 
-/// Provides traceback for semi-global pairwise alignment using a vectorized algorithm. Does not penalize gaps at beginning and end of s2/reference only
-pub fn semi_global_dx_traceback(
+/// For isOnClust-rust
+pub fn semi_global_alignment_trace_scan_sat_cigar(
     query_sequence: &[u8],
     database_sequence: &[u8],
     open_cost: i32,
     gap_extend_cost: i32,
     substitution_matrix: &Matrix,
-) -> TracebackResults {
-    unsafe {
-        let result = parasail_sg_dx_trace_striped_sat(
+) -> Result<TracebackResultsWithCigar, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sg_trace_scan_sat(
             query_sequence.as_ptr(),
             query_sequence.len() as c_int,
             database_sequence.as_ptr(),
@@ -481,60 +1049,79 @@ pub fn semi_global_dx_traceback(
             open_cost,
             gap_extend_cost,
             **substitution_matrix,
-        );
+        ))?
+    };
+    let traceback = result.traceback(query_sequence, database_sequence, substitution_matrix)?;
+    let cigar = result.cigar(query_sequence, database_sequence, substitution_matrix)?;
+    let TracebackResults {
+        score,
+        query_end,
+        ref_end,
+        query_trace,
+        comp_trace,
+        ref_trace,
+        query_prefix_clip,
+        query_suffix_clip,
+    } = traceback_results(&result, &traceback, query_sequence.len(), database_sequence.len());
 
-        let score = parasail_result_get_score(result) as i64;
-
-        // calculate start from end
-        let query_end = (*result).end_query as usize + 1;
-        let ref_end = (*result).end_ref as usize + 1;
-
-        // Initialize CStrings
-        let c_query_seq = CString::new(query_sequence).unwrap().into_raw();
-        let c_db_seq = CString::new(database_sequence).unwrap().into_raw();
-        let match_char = CString::new("|").unwrap().into_raw();
-        let positive_mismatch_char = CString::new("|").unwrap().into_raw();
-        let negative_mismatch_char = CString::new(":").unwrap().into_raw();
+    Ok(TracebackResultsWithCigar {
+        score,
+        query_end,
+        ref_end,
+        query_trace,
+        comp_trace,
+        ref_trace,
+        cigar_trace: cigar.to_cigar_string(),
+        query_prefix_clip,
+        query_suffix_clip,
+    })
+}
 
-        let traceback = parasail_result_get_traceback(
-            result,
-            c_query_seq,
+/// Provides traceback for global pairwise alignment using a vectorized algorithm. The whole matrix is kept for the
+/// traceback, so for long sequences see `chunked_global_traceback`.
+pub fn global_traceback(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<TracebackResults, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_nw_trace_striped_sat(
+            query_sequence.as_ptr(),
             query_sequence.len() as c_int,
-            c_db_seq,
+            database_sequence.as_ptr(),
             database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
             **substitution_matrix,
-            *match_char,
-            *positive_mismatch_char,
-            *negative_mismatch_char,
-        );
-
-        // Reclaim CStrings to allow dropping
-        let _c_query_seq = CString::from_raw(c_query_seq);
-        let _c_db_seq = CString::from_raw(c_db_seq);
-        let _match_char = CString::from_raw(match_char);
-        let _positive_mismatch_char = CString::from_raw(positive_mismatch_char);
-        let _negative_mismatch_char = CString::from_raw(negative_mismatch_char);
-
-        // Convert results in the traceback opaque point to rust Strings
-        let query_str = CStr::from_ptr((*traceback).query).to_str().unwrap();
-        let query_trace = String::from(query_str);
-        let comp_str = CStr::from_ptr((*traceback).comp).to_str().unwrap();
-        let comp_trace = String::from(comp_str);
-        let ref_str = CStr::from_ptr((*traceback).ref_).to_str().unwrap();
-        let ref_trace = String::from(ref_str);
-
-        parasail_traceback_free(traceback);
-        parasail_result_free(result);
+        ))?
+    };
+    let traceback = result.traceback(query_sequence, database_sequence, substitution_matrix)?;
+    Ok(traceback_results(&result, &traceback, query_sequence.len(), database_sequence.len()))
+}
 
-        TracebackResults {
-            score,
-            query_end,
-            ref_end,
-            query_trace,
-            comp_trace,
-            ref_trace,
-        }
-    }
+/// Provides traceback for semi-global pairwise alignment using a vectorized algorithm. Does not penalize gaps at beginning and end of s2/reference only
+pub fn semi_global_dx_traceback(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<TracebackResults, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sg_dx_trace_striped_sat(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    let traceback = result.traceback(query_sequence, database_sequence, substitution_matrix)?;
+    Ok(traceback_results(&result, &traceback, query_sequence.len(), database_sequence.len()))
 }
 
 /// Provides traceback for semi-global pairwise alignment using a vectorized algorithm. Does not penalize gaps at beginning and end of either sequence
@@ -544,9 +1131,9 @@ pub fn semi_global_traceback(
     open_cost: i32,
     gap_extend_cost: i32,
     substitution_matrix: &Matrix,
-) -> TracebackResults {
-    unsafe {
-        let result = parasail_sg_trace_striped_sat(
+) -> Result<TracebackResults, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sg_trace_striped_sat(
             query_sequence.as_ptr(),
             query_sequence.len() as c_int,
             database_sequence.as_ptr(),
@@ -554,61 +1141,79 @@ pub fn semi_global_traceback(
             open_cost,
             gap_extend_cost,
             **substitution_matrix,
-        );
-
-        let score = parasail_result_get_score(result) as i64;
-
-        // calculate start from end
-        let query_end = (*result).end_query as usize + 1;
-        let ref_end = (*result).end_ref as usize + 1;
-
-        // Initialize CStrings
-        let c_query_seq = CString::new(query_sequence).unwrap().into_raw();
-        let c_db_seq = CString::new(database_sequence).unwrap().into_raw();
-        let match_char = CString::new("|").unwrap().into_raw();
-        let positive_mismatch_char = CString::new("|").unwrap().into_raw();
-        let negative_mismatch_char = CString::new(":").unwrap().into_raw();
+        ))?
+    };
+    let traceback = result.traceback(query_sequence, database_sequence, substitution_matrix)?;
+    Ok(traceback_results(&result, &traceback, query_sequence.len(), database_sequence.len()))
+}
 
-        // Convert results in the traceback opaque point to rust Strings
-        let traceback = parasail_result_get_traceback(
-            result,
-            c_query_seq,
+/// Provides traceback for local pairwise alignment using a vectorized algorithm.
+pub fn local_traceback(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<TracebackResults, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sw_trace_striped_sat(
+            query_sequence.as_ptr(),
             query_sequence.len() as c_int,
-            c_db_seq,
+            database_sequence.as_ptr(),
             database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
             **substitution_matrix,
-            *match_char,
-            *positive_mismatch_char,
-            *negative_mismatch_char,
-        );
-
-
-        // Reclaim CStrings to allow dropping
-        let _c_query_seq = CString::from_raw(c_query_seq);
-        let _c_db_seq = CString::from_raw(c_db_seq);
-        let _match_char = CString::from_raw(match_char);
-        let _positive_mismatch_char = CString::from_raw(positive_mismatch_char);
-        let _negative_mismatch_char = CString::from_raw(negative_mismatch_char);
-
-        let query_str = CStr::from_ptr((*traceback).query).to_str().unwrap();
-        let query_trace = String::from(query_str);
-        let comp_str = CStr::from_ptr((*traceback).comp).to_str().unwrap();
-        let comp_trace = String::from(comp_str);
-        let ref_str = CStr::from_ptr((*traceback).ref_).to_str().unwrap();
-        let ref_trace = String::from(ref_str);
+        ))?
+    };
+    let traceback = result.traceback(query_sequence, database_sequence, substitution_matrix)?;
+    Ok(traceback_results(&result, &traceback, query_sequence.len(), database_sequence.len()))
+}
 
-        parasail_traceback_free(traceback);
-        parasail_result_free(result);
+/// Provides statistics for global pairwise alignment using a vectorized algorithm.
+pub fn global_alignment_stats(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<AlignmentStats, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_nw_stats_striped_sat(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    Ok(alignment_stats(&result, query_sequence.len(), database_sequence.len()))
+}
 
-        TracebackResults {
-            score,
-            query_end,
-            ref_end,
-            query_trace,
-            comp_trace,
-            ref_trace,
-        }
-    }
+/// Provides statistics for global pairwise alignment as `global_alignment_stats` does, along with its mismatches and
+/// gaps, from a second, traceback pass.
+pub fn global_alignment_stats_with_trace(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<AlignmentStatsWithTrace, ParasailError> {
+    let stats = global_alignment_stats(query_sequence, database_sequence, open_cost, gap_extend_cost, substitution_matrix)?;
+    let trace = unsafe {
+        ParasailResult::from_raw(parasail_nw_trace_striped_sat(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    with_trace(stats, &trace, query_sequence, database_sequence, substitution_matrix)
 }
 
 /// Provides statistics for local pairwise alignment using a vectorized algorithm.
@@ -621,7 +1226,7 @@ pub fn semi_global_traceback(
 /// let query = b"AAAACCCCCCCCCCGGG";
 ///
 /// let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
-/// let stats = local_alignment_stats(query, reference, 1, 1, &identity_matrix);
+/// let stats = local_alignment_stats(query, reference, 1, 1, &identity_matrix).unwrap();
 /// assert_eq!(17, stats.score);
 /// assert_eq!(17, stats.num_matches);
 /// assert_eq!(17, stats.num_positive_subs);
@@ -635,9 +1240,9 @@ pub fn local_alignment_stats(
     open_cost: i32,
     gap_extend_cost: i32,
     substitution_matrix: &Matrix,
-) -> AlignmentStats {
-    unsafe {
-        let result = parasail_sw_stats_striped_sat(
+) -> Result<AlignmentStats, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sw_stats_striped_sat(
             query_sequence.as_ptr(),
             query_sequence.len() as c_int,
             database_sequence.as_ptr(),
@@ -645,28 +1250,207 @@ pub fn local_alignment_stats(
             open_cost,
             gap_extend_cost,
             **substitution_matrix,
-        );
-
-        let score = parasail_result_get_score(result) as i64;
-        let num_matches = parasail_result_get_matches(result) as u64;
-        let num_subs = parasail_result_get_similar(result) as u64;
-        let align_len = parasail_result_get_length(result) as usize;
-
-        // calculate start from end
-        let query_end = (*result).end_query as usize + 1;
-        let ref_end = (*result).end_ref as usize + 1;
-
-        parasail_result_free(result);
-
-        AlignmentStats {
-            score: score,
-            num_matches: num_matches,
-            num_positive_subs: num_subs,
-            align_length: align_len,
-            query_end: query_end,
-            ref_end: ref_end,
-        }
-    }
+        ))?
+    };
+    Ok(alignment_stats(&result, query_sequence.len(), database_sequence.len()))
+}
+
+/// Provides statistics for local pairwise alignment as `local_alignment_stats` does, along with where the alignment
+/// begins and its mismatches and gaps, from a second, traceback pass.
+pub fn local_alignment_stats_with_trace(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<AlignmentStatsWithTrace, ParasailError> {
+    let stats = local_alignment_stats(query_sequence, database_sequence, open_cost, gap_extend_cost, substitution_matrix)?;
+    let trace = unsafe {
+        ParasailResult::from_raw(parasail_sw_trace_striped_sat(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    with_trace(stats, &trace, query_sequence, database_sequence, substitution_matrix)
+}
+
+/// Provides statistics for global pairwise alignment from a stats profile, as `global_alignment_stats` does from
+/// the query.
+pub fn global_alignment_stats_profile(
+    query_profile: &StatsProfile,
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+) -> Result<AlignmentStats, ParasailError> {
+    let query_sequence = query_profile.query();
+    let profile: &Profile = query_profile;
+    let result = unsafe {
+        ParasailResult::from_raw(profile_kernel!(
+            profile,
+            [
+                parasail_nw_stats_striped_profile_8,
+                parasail_nw_stats_striped_profile_16,
+                parasail_nw_stats_striped_profile_32,
+                parasail_nw_stats_striped_profile_64,
+                parasail_nw_stats_striped_profile_sat
+            ],
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+        ))?
+    };
+    Ok(alignment_stats(&result, query_sequence.len(), database_sequence.len()))
+}
+
+/// Provides statistics for semi-global pairwise alignment from a stats profile, as `semi_global_alignment_stats`
+/// does from the query.
+pub fn semi_global_alignment_stats_profile(
+    query_profile: &StatsProfile,
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+) -> Result<AlignmentStats, ParasailError> {
+    let query_sequence = query_profile.query();
+    let profile: &Profile = query_profile;
+    let result = unsafe {
+        ParasailResult::from_raw(profile_kernel!(
+            profile,
+            [
+                parasail_sg_stats_striped_profile_8,
+                parasail_sg_stats_striped_profile_16,
+                parasail_sg_stats_striped_profile_32,
+                parasail_sg_stats_striped_profile_64,
+                parasail_sg_stats_striped_profile_sat
+            ],
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+        ))?
+    };
+    Ok(alignment_stats(&result, query_sequence.len(), database_sequence.len()))
+}
+
+/// Provides statistics for local pairwise alignment from a stats profile, as `local_alignment_stats` does from
+/// the query.
+pub fn local_alignment_stats_profile(
+    query_profile: &StatsProfile,
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+) -> Result<AlignmentStats, ParasailError> {
+    let query_sequence = query_profile.query();
+    let profile: &Profile = query_profile;
+    let result = unsafe {
+        ParasailResult::from_raw(profile_kernel!(
+            profile,
+            [
+                parasail_sw_stats_striped_profile_8,
+                parasail_sw_stats_striped_profile_16,
+                parasail_sw_stats_striped_profile_32,
+                parasail_sw_stats_striped_profile_64,
+                parasail_sw_stats_striped_profile_sat
+            ],
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+        ))?
+    };
+    Ok(alignment_stats(&result, query_sequence.len(), database_sequence.len()))
+}
+
+/// Provides traceback for global pairwise alignment from a profile, as `global_traceback` does from the query. Any
+/// profile works, including a `StatsProfile`.
+pub fn global_traceback_profile(
+    query_profile: &Profile,
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+) -> Result<TracebackResults, ParasailError> {
+    let query_sequence = query_profile.query();
+    let result = unsafe {
+        ParasailResult::from_raw(profile_kernel!(
+            query_profile,
+            [
+                parasail_nw_trace_striped_profile_8,
+                parasail_nw_trace_striped_profile_16,
+                parasail_nw_trace_striped_profile_32,
+                parasail_nw_trace_striped_profile_64,
+                parasail_nw_trace_striped_profile_sat
+            ],
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+        ))?
+    };
+    let traceback = result.traceback(query_sequence, database_sequence, query_profile.matrix())?;
+    Ok(traceback_results(&result, &traceback, query_sequence.len(), database_sequence.len()))
+}
+
+/// Provides traceback for semi-global pairwise alignment from a profile, as `semi_global_traceback` does from the query. Any
+/// profile works, including a `StatsProfile`.
+pub fn semi_global_traceback_profile(
+    query_profile: &Profile,
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+) -> Result<TracebackResults, ParasailError> {
+    let query_sequence = query_profile.query();
+    let result = unsafe {
+        ParasailResult::from_raw(profile_kernel!(
+            query_profile,
+            [
+                parasail_sg_trace_striped_profile_8,
+                parasail_sg_trace_striped_profile_16,
+                parasail_sg_trace_striped_profile_32,
+                parasail_sg_trace_striped_profile_64,
+                parasail_sg_trace_striped_profile_sat
+            ],
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+        ))?
+    };
+    let traceback = result.traceback(query_sequence, database_sequence, query_profile.matrix())?;
+    Ok(traceback_results(&result, &traceback, query_sequence.len(), database_sequence.len()))
+}
+
+/// Provides traceback for local pairwise alignment from a profile, as `local_traceback` does from the query. Any
+/// profile works, including a `StatsProfile`.
+pub fn local_traceback_profile(
+    query_profile: &Profile,
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+) -> Result<TracebackResults, ParasailError> {
+    let query_sequence = query_profile.query();
+    let result = unsafe {
+        ParasailResult::from_raw(profile_kernel!(
+            query_profile,
+            [
+                parasail_sw_trace_striped_profile_8,
+                parasail_sw_trace_striped_profile_16,
+                parasail_sw_trace_striped_profile_32,
+                parasail_sw_trace_striped_profile_64,
+                parasail_sw_trace_striped_profile_sat
+            ],
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+        ))?
+    };
+    let traceback = result.traceback(query_sequence, database_sequence, query_profile.matrix())?;
+    Ok(traceback_results(&result, &traceback, query_sequence.len(), database_sequence.len()))
 }
 
 #[test]
@@ -677,7 +1461,7 @@ fn test_semiglobal_stats() {
     let query = b"AAAACCCCCCCCCCGGG";
 
     let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
-    let stats = semi_global_alignment_stats(query, reference, 1, 1, &identity_matrix);
+    let stats = semi_global_alignment_stats(query, reference, 1, 1, &identity_matrix).unwrap();
     assert_eq!(17, stats.score);
     assert_eq!(17, stats.num_matches);
     assert_eq!(17, stats.num_positive_subs);
@@ -685,6 +1469,14 @@ fn test_semiglobal_stats() {
     assert_eq!(17, stats.query_end);
     assert_eq!(23, stats.ref_end);
 
+    let traced = semi_global_alignment_stats_with_trace(query, reference, 1, 1, &identity_matrix).unwrap();
+    assert_eq!(stats, traced.stats);
+    assert_eq!(0, traced.query_begin);
+    assert_eq!(6, traced.ref_begin);
+    assert_eq!(0, traced.num_mismatches);
+    assert_eq!(0, traced.num_gap_opens);
+    assert_eq!(0, traced.num_gap_bases);
+
     assert_eq!(
         str::from_utf8(query).unwrap(),
         str::from_utf8(&query[stats.query_end - stats.align_length..stats.query_end]).unwrap()
@@ -698,15 +1490,47 @@ fn test_semiglobal_stats() {
     let x = b"ACCGTGGAT";
     let y = b"AAAAACCGTTGAT";
     let ident_with_penalty = Matrix::new(MatrixType::IdentityWithPenalty);
-    let alignment = semi_global_alignment_stats(x, y, 5, 1, &ident_with_penalty);
+    let alignment = semi_global_alignment_stats(x, y, 5, 1, &ident_with_penalty).unwrap();
     assert_eq!(7, alignment.score);
     assert_eq!(13, alignment.ref_end);
     assert_eq!(9, alignment.query_end);
+    let traced = semi_global_alignment_stats_with_trace(x, y, 5, 1, &ident_with_penalty).unwrap();
+    assert_eq!(1, traced.num_mismatches);
+    assert_eq!(0, traced.num_gap_opens);
 
     let x = b"CCGGCA";
     let y = b"ACCGTTGACGC";
-    let alignment = semi_global_alignment_stats(x, y, 5, 1, &ident_with_penalty);
+    let alignment = semi_global_alignment_stats(x, y, 5, 1, &ident_with_penalty).unwrap();
     assert_eq!(1, alignment.score);
     assert_eq!(1, alignment.ref_end);
     assert_eq!(6, alignment.query_end);
+
+    // a two base insertion in the query, between free end gaps in the reference
+    let query = b"AAAAACCCCCTTGGGGGTTTTT";
+    let reference = b"ACGTACGTAAAAACCCCCGGGGGTTTTTACGT";
+    let alignment = semi_global_alignment_stats_with_trace(query, reference, 1, 1, &identity_matrix).unwrap();
+    assert_eq!(18, alignment.stats.score);
+    assert_eq!(0, alignment.num_mismatches);
+    assert_eq!(1, alignment.num_gap_opens);
+    assert_eq!(2, alignment.num_gap_bases);
+}
+
+#[test]
+fn test_semiglobal_traceback_overhang() {
+    use crate::matrix::{Matrix, MatrixType};
+    let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+
+    // the query runs on past the end of the reference
+    let alignment = semi_global_traceback(b"ACGTACGTAC", b"ACGTACG", 1, 1, &matrix).unwrap();
+    assert_eq!(7, alignment.score);
+    assert_eq!((7, 7), (alignment.query_end, alignment.ref_end));
+    assert_eq!("ACGTACG", alignment.query_trace);
+    assert_eq!((0, 3), (alignment.query_prefix_clip, alignment.query_suffix_clip));
+    assert_eq!("7=", alignment.cigar());
+
+    // and the reference past the end of the query
+    let alignment = semi_global_traceback(b"GTACG", b"ACGTACGTAC", 1, 1, &matrix).unwrap();
+    assert_eq!((5, 7), (alignment.query_end, alignment.ref_end));
+    assert_eq!((0, 2), (alignment.query_begin(), alignment.ref_begin()));
+    assert_eq!((Some(0), Some(2)), alignment.aligned_pairs().nth(2).unwrap());
 }
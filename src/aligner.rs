@@ -0,0 +1,312 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::slice;
+
+use crate::backend::AlignmentBackend;
+use crate::batch::AlignmentMode;
+use crate::matrix::Matrix;
+use crate::error::ParasailError;
+use crate::profile::Profile;
+use crate::scalar::{asymmetric_alignment, AsymmetricGaps};
+use crate::scheme::ScoringScheme;
+use crate::sequence::check_matrix_alphabet;
+
+/// Aligns many pairs with fixed scoring parameters, reusing the query profile while the query stays the same and
+/// optionally remembering recent results.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let mut aligner = Aligner::new(&matrix, AlignmentMode::Local, 1, 1).with_cache(1024);
+///
+/// assert_eq!(8, aligner.score(b"ACGTACGT", b"TTACGTACGTTT"));
+/// assert_eq!(8, aligner.score(b"ACGTACGT", b"TTACGTACGTTT"));
+/// assert_eq!(1, aligner.cache_hits());
+/// ```
+pub struct Aligner<'m> {
+    matrix: &'m Matrix,
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    profile: Option<CachedProfile<'m>>,
+    cache: Option<ScoreCache>,
+    check_alphabet: bool,
+    asymmetric_gaps: Option<AsymmetricGaps>,
+    backend: Option<Box<dyn AlignmentBackend + 'm>>,
+}
+
+/// A profile along with the copy of the query it points into.
+struct CachedProfile<'m> {
+    // declared first so it's dropped before the query it points at
+    profile: Profile<'m>,
+    query: Box<[u8]>,
+}
+
+impl<'m> Aligner<'m> {
+    /// Creates an aligner for the given mode and scoring parameters, with no result cache.
+    pub fn new<S>(scheme: &'m S, mode: AlignmentMode, open_cost: i32, gap_extend_cost: i32) -> Self
+    where
+        S: ScoringScheme + ?Sized,
+    {
+        Aligner {
+            matrix: scheme.matrix(),
+            mode,
+            open_cost,
+            gap_extend_cost,
+            profile: None,
+            cache: None,
+            check_alphabet: false,
+            asymmetric_gaps: None,
+            backend: None,
+        }
+    }
+
+    /// Turns on a cache remembering the scores of the `capacity` most recently used (query, reference) pairs, so
+    /// that repeated alignments return without running the kernel. The cache keeps a copy of each pair and compares
+    /// it on a hit, so two pairs with the same hash never share a score. The scoring parameters can't change after
+    /// construction, so they don't need to be part of the key.
+    pub fn with_cache(mut self, capacity: usize) -> Self {
+        self.cache = Some(ScoreCache::new(capacity));
+        self
+    }
+
+    /// Turns on a check that each query looks like the kind of sequence the matrix is meant for (see
+    /// `detect_alphabet`), catching mistakes like aligning proteins with a nucleotide matrix. Queries whose alphabet
+    /// can't be told are let through.
+    pub fn with_alphabet_check(mut self) -> Self {
+        self.check_alphabet = true;
+        self
+    }
+
+    /// Charges insertions and deletions differently, replacing the gap costs given at construction. parasail's
+    /// kernels can't do this, so alignments are run by the much slower pure Rust backend (see
+    /// `asymmetric_alignment`) from then on.
+    pub fn with_asymmetric_gaps(mut self, gaps: AsymmetricGaps) -> Self {
+        self.asymmetric_gaps = Some(gaps);
+        // scores cached so far were made with the old costs
+        self.clear_cache();
+        self
+    }
+
+    /// Runs every alignment on `backend` instead of parasail's striped kernels, e.g. `InterSequenceBackend` for very
+    /// short sequences. Asymmetric gaps (see `with_asymmetric_gaps`) still take precedence, since only the scalar
+    /// backend supports them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// let mut aligner = Aligner::new(&matrix, AlignmentMode::Global, 1, 1).with_backend(InterSequenceBackend);
+    /// assert_eq!(6, aligner.score(b"ACGTACGT", b"ACGTTCGT"));
+    /// ```
+    pub fn with_backend<B: AlignmentBackend + 'm>(mut self, backend: B) -> Self {
+        self.backend = Some(Box::new(backend));
+        self.profile = None;
+        // scores cached so far were made by the old backend
+        self.clear_cache();
+        self
+    }
+
+    /// Scores `reference` against `query` with this aligner's mode and parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the alphabet check is on and the query doesn't suit the matrix, or if parasail reports an error. Use
+    /// `try_score` to handle those cases.
+    pub fn score(&mut self, query: &[u8], reference: &[u8]) -> i32 {
+        match self.try_score(query, reference) {
+            Ok(score) => score,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Scores `reference` against `query` with this aligner's mode and parameters, returning an error instead of
+    /// aligning if the alphabet check is on and the query doesn't suit the matrix, or if the alignment fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::DNAFull);
+    /// let mut aligner = Aligner::new(&matrix, AlignmentMode::Local, 10, 1).with_alphabet_check();
+    ///
+    /// assert!(aligner.try_score(b"ACGTACGT", b"TTACGTACGTTT").is_ok());
+    /// assert!(aligner.try_score(b"MEEPQSDPSVEPPLSQ", b"TTACGTACGTTT").is_err());
+    /// ```
+    pub fn try_score(&mut self, query: &[u8], reference: &[u8]) -> Result<i32, ParasailError> {
+        if self.check_alphabet {
+            check_matrix_alphabet(query, self.matrix)?;
+        }
+
+        let key = self.cache.as_ref().map(|_| CacheKey::new(query, reference));
+        if let (Some(cache), Some(key)) = (self.cache.as_mut(), key.as_ref()) {
+            if let Some(score) = cache.get(key, query, reference) {
+                return Ok(score);
+            }
+        }
+
+        let score = match (&self.asymmetric_gaps, &self.backend) {
+            (Some(gaps), _) => asymmetric_alignment(query, reference, self.mode, gaps, self.matrix).score as i32,
+            (None, Some(backend)) => {
+                backend.score(query, reference, self.mode, self.open_cost, self.gap_extend_cost, self.matrix)?
+            }
+            (None, None) => {
+                let (mode, open_cost, gap_extend_cost) = (self.mode, self.open_cost, self.gap_extend_cost);
+                mode.score(self.profile_for(query)?, reference, open_cost, gap_extend_cost)?
+            }
+        };
+
+        if let (Some(cache), Some(key)) = (self.cache.as_mut(), key) {
+            cache.insert(key, query, reference, score);
+        }
+        Ok(score)
+    }
+
+    /// The number of alignments answered from the cache.
+    pub fn cache_hits(&self) -> u64 {
+        self.cache.as_ref().map_or(0, |cache| cache.hits)
+    }
+
+    /// The number of alignments which had to be run despite the cache being on.
+    pub fn cache_misses(&self) -> u64 {
+        self.cache.as_ref().map_or(0, |cache| cache.misses)
+    }
+
+    /// Forgets all cached results, keeping the cache turned on.
+    pub fn clear_cache(&mut self) {
+        if let Some(cache) = self.cache.as_mut() {
+            *cache = ScoreCache::new(cache.capacity);
+        }
+    }
+
+    fn profile_for(&mut self, query: &[u8]) -> Result<&Profile<'m>, ParasailError> {
+        let stale = match self.profile {
+            Some(ref cached) => &*cached.query != query,
+            None => true,
+        };
+
+        if stale {
+            // release the old profile before its query copy goes away
+            self.profile = None;
+
+            let query: Box<[u8]> = query.into();
+            // the boxed query never moves and outlives the profile (see CachedProfile), so handing the profile a
+            // reference with the matrix's lifetime is sound
+            let query_ref: &'m [u8] = unsafe { slice::from_raw_parts(query.as_ptr(), query.len()) };
+            self.profile = Some(CachedProfile {
+                profile: Profile::try_new(query_ref, self.matrix)?,
+                query,
+            });
+        }
+
+        match self.profile {
+            Some(ref cached) => Ok(&cached.profile),
+            None => unreachable!(),
+        }
+    }
+}
+
+// only a fingerprint of the pair, so a hit still has to compare the sequences kept in its CacheEntry
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    query_hash: u64,
+    query_len: usize,
+    ref_hash: u64,
+    ref_len: usize,
+}
+
+impl CacheKey {
+    fn new(query: &[u8], reference: &[u8]) -> Self {
+        CacheKey {
+            query_hash: hash_bytes(query),
+            query_len: query.len(),
+            ref_hash: hash_bytes(reference),
+            ref_len: reference.len(),
+        }
+    }
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A cached score, with the pair it was computed for.
+struct CacheEntry {
+    score: i32,
+    last_used: u64,
+    query: Box<[u8]>,
+    reference: Box<[u8]>,
+}
+
+/// A least-recently-used map from sequence pairs to scores.
+struct ScoreCache {
+    capacity: usize,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    entries: HashMap<CacheKey, CacheEntry>,
+    // cached pairs by last use, oldest first
+    recency: BTreeMap<u64, CacheKey>,
+}
+
+impl ScoreCache {
+    fn new(capacity: usize) -> Self {
+        ScoreCache {
+            capacity,
+            tick: 0,
+            hits: 0,
+            misses: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &CacheKey, query: &[u8], reference: &[u8]) -> Option<i32> {
+        self.tick += 1;
+        match self.entries.get_mut(key) {
+            // a different pair with the same fingerprint is a miss, and gets replaced by the insert that follows
+            Some(entry) if *entry.query == *query && *entry.reference == *reference => {
+                self.recency.remove(&entry.last_used);
+                entry.last_used = self.tick;
+                self.recency.insert(self.tick, key.clone());
+                self.hits += 1;
+                Some(entry.score)
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, query: &[u8], reference: &[u8], score: i32) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.tick += 1;
+        let entry = CacheEntry { score, last_used: self.tick, query: query.into(), reference: reference.into() };
+        if let Some(replaced) = self.entries.insert(key.clone(), entry) {
+            self.recency.remove(&replaced.last_used);
+        }
+        self.recency.insert(self.tick, key);
+
+        while self.entries.len() > self.capacity {
+            match self.recency.pop_first() {
+                Some((_, evicted)) => self.entries.remove(&evicted),
+                None => break,
+            };
+        }
+    }
+}
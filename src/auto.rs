@@ -0,0 +1,214 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::collections::HashSet;
+
+use crate::align::global_alignment_score_banded;
+use crate::batch::AlignmentMode;
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::profile::{Profile, ProfileCaps, ScoreWidth};
+use crate::sequence::{detect_alphabet, SequenceAlphabet};
+use crate::sketch::mash_identity;
+
+/// The k-mer Jaccard similarity of two sequences: the number of distinct k-mers they share over the number of distinct
+/// k-mers in either. Bytes are compared exactly. Sequences shorter than `k` have no k-mers, and score 0.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// assert_eq!(1.0, kmer_jaccard(b"ACGTACGT", b"ACGTACGT", 3));
+/// assert_eq!(0.8, kmer_jaccard(b"ACGTACGT", b"ACGTACGA", 3));
+/// assert_eq!(0.0, kmer_jaccard(b"ACGTACGT", b"AC", 3));
+/// ```
+pub fn kmer_jaccard(a: &[u8], b: &[u8], k: usize) -> f64 {
+    if k == 0 {
+        return 0.0;
+    }
+    let a: HashSet<&[u8]> = a.windows(k).collect();
+    let b: HashSet<&[u8]> = b.windows(k).collect();
+
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// How `auto_score` aligned a pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Engine {
+    /// parasail's striped kernel over the whole dynamic programming matrix, with scores of the given width.
+    Striped(ScoreWidth),
+    /// parasail's banded global kernel (see `global_alignment_score_banded`).
+    Banded {
+        /// How far from the main diagonal the alignment may stray.
+        band: usize,
+    },
+    /// The wavefront aligner (see `WavefrontBackend`). Requires the `wfa` feature.
+    #[cfg(feature = "wfa")]
+    Wavefront,
+}
+
+/// What `auto_score` estimated about a pair, and the engine it picked from that.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoDecision {
+    /// The k-mer Jaccard similarity of the pair (see `kmer_jaccard`).
+    pub jaccard: f64,
+    /// The identity of the pair estimated from `jaccard`, between 0 and 1.
+    pub estimated_identity: f64,
+    /// The engine the pair is aligned with.
+    pub engine: Engine,
+}
+
+/// A score from `auto_score`, along with how it was computed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoAlignment {
+    /// The alignment score.
+    pub score: i32,
+    /// How the pair was aligned.
+    pub decision: AutoDecision,
+}
+
+// pairs estimated at least this identical, and at least this long, are worth aligning near the diagonal
+const NEAR_IDENTICAL: f64 = 0.95;
+const MIN_NARROW_LEN: usize = 500;
+// extra room in a band beyond the edits the estimated identity allows for
+const BAND_SLACK: usize = 16;
+
+impl AutoDecision {
+    /// Estimates how similar `query` and `reference` are and picks an engine for them, without aligning.
+    ///
+    /// The similarity comes from the pair's k-mer Jaccard similarity, with 11-mers for nucleotides and 3-mers
+    /// otherwise, converted to an identity with the Mash distance. Long global alignments estimated at least 95%
+    /// identical stay near the diagonal: they go to the wavefront aligner if the `wfa` feature is on and the matrix
+    /// is a plain match/mismatch one, or else to the banded kernel when the band covers only part of the matrix.
+    /// Everything else gets the striped kernel at the narrowest score width that can't saturate for the pair's
+    /// lengths, gap costs, and the matrix's largest score.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// let decision = AutoDecision::choose(b"ACGTACGT", b"ACGTTCGT", AlignmentMode::Local, 1, 1, &matrix);
+    /// assert_eq!(Engine::Striped(ScoreWidth::Bits8), decision.engine);
+    /// ```
+    pub fn choose(
+        query: &[u8],
+        reference: &[u8],
+        mode: AlignmentMode,
+        open_cost: i32,
+        gap_extend_cost: i32,
+        matrix: &Matrix,
+    ) -> Self {
+        let k = match detect_alphabet(query) {
+            SequenceAlphabet::Dna | SequenceAlphabet::Rna => 11,
+            _ => 3,
+        };
+        let jaccard = kmer_jaccard(query, reference, k);
+        let estimated_identity = mash_identity(jaccard, k);
+
+        let shorter = query.len().min(reference.len());
+        let longer = query.len().max(reference.len());
+        let near_diagonal = mode == AlignmentMode::Global && shorter >= MIN_NARROW_LEN;
+        let engine = if near_diagonal && estimated_identity >= NEAR_IDENTICAL {
+            narrow_engine(query, reference, open_cost, gap_extend_cost, matrix, estimated_identity)
+        } else {
+            None
+        };
+        let engine = engine.unwrap_or_else(|| {
+            let largest_score = matrix.score_table().into_iter().flatten().map(i32::abs).max().unwrap_or(0) as i64;
+            let bound = (shorter as i64 * largest_score).max(open_cost as i64 + longer as i64 * gap_extend_cost as i64);
+            Engine::Striped(if bound < i8::MAX as i64 {
+                ScoreWidth::Bits8
+            } else if bound < i16::MAX as i64 {
+                ScoreWidth::Bits16
+            } else {
+                ScoreWidth::Bits32
+            })
+        });
+
+        AutoDecision {
+            jaccard,
+            estimated_identity,
+            engine,
+        }
+    }
+}
+
+/// An engine aligning only near the diagonal, or `None` if a narrow alignment wouldn't save anything.
+#[cfg_attr(not(feature = "wfa"), allow(unused_variables))]
+fn narrow_engine(
+    query: &[u8],
+    reference: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    matrix: &Matrix,
+    estimated_identity: f64,
+) -> Option<Engine> {
+    #[cfg(feature = "wfa")]
+    {
+        if crate::wfa::supports(query, reference, open_cost, gap_extend_cost, matrix) {
+            return Some(Engine::Wavefront);
+        }
+    }
+
+    let longer = query.len().max(reference.len());
+    let edits = (longer as f64 * (1.0 - estimated_identity)).ceil() as usize;
+    let band = query.len().abs_diff(reference.len()) + 2 * edits + BAND_SLACK;
+    if 2 * band + 1 < query.len().min(reference.len()) {
+        Some(Engine::Banded { band })
+    } else {
+        None
+    }
+}
+
+/// Scores `reference` against `query`, letting `AutoDecision::choose` pick how, and returns the decision along with
+/// the score.
+///
+/// Striped and wavefront scores are exact. A banded score is exact unless the alignment strays further from the
+/// diagonal than the k-mer estimate suggested, which takes a long indel the k-mers didn't reflect.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let alignment = auto_score(b"ACGTACGT", b"TTACGTACGTTT", AlignmentMode::Local, 1, 1, &matrix).unwrap();
+/// assert_eq!(8, alignment.score);
+/// assert_eq!(Engine::Striped(ScoreWidth::Bits8), alignment.decision.engine);
+/// ```
+pub fn auto_score(
+    query: &[u8],
+    reference: &[u8],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    matrix: &Matrix,
+) -> Result<AutoAlignment, ParasailError> {
+    let decision = AutoDecision::choose(query, reference, mode, open_cost, gap_extend_cost, matrix);
+    let score = match decision.engine {
+        Engine::Striped(width) => {
+            let caps = ProfileCaps {
+                width,
+                ..ProfileCaps::default()
+            };
+            let profile = Profile::new_with(query, matrix, caps)?;
+            mode.score(&profile, reference, open_cost, gap_extend_cost)?
+        }
+        Engine::Banded { band } => {
+            global_alignment_score_banded(query, reference, open_cost, gap_extend_cost, band, matrix)?
+        }
+        #[cfg(feature = "wfa")]
+        Engine::Wavefront => {
+            use crate::backend::AlignmentBackend;
+            crate::wfa::WavefrontBackend.score(query, reference, mode, open_cost, gap_extend_cost, matrix)?
+        }
+    };
+
+    Ok(AutoAlignment { score, decision })
+}
@@ -0,0 +1,354 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::batch::AlignmentMode;
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::profile::Profile;
+
+/// An engine that scores pairwise alignments. Backends share one interface so they can be swapped to suit a workload,
+/// either per call or for every alignment an `Aligner` runs (see `Aligner::with_backend`). Unless a backend's
+/// documentation says otherwise, it gives exactly the scores parasail does.
+///
+/// Gap costs follow parasail's convention: a gap of length `L` costs `open_cost + (L - 1) * gap_extend_cost`. The
+/// modes are those of `AlignmentMode`, with `SemiGlobal` leaving gaps at either end of either sequence free, as
+/// `semi_global_alignment_score` does.
+pub trait AlignmentBackend {
+    /// Scores each `(query, reference)` pair, returning the scores in the same order as the pairs, or the first error
+    /// the engine reports.
+    fn score_pairs(
+        &self,
+        pairs: &[(&[u8], &[u8])],
+        mode: AlignmentMode,
+        open_cost: i32,
+        gap_extend_cost: i32,
+        matrix: &Matrix,
+    ) -> Result<Vec<i32>, ParasailError>;
+
+    /// Scores a single pair.
+    fn score(
+        &self,
+        query: &[u8],
+        reference: &[u8],
+        mode: AlignmentMode,
+        open_cost: i32,
+        gap_extend_cost: i32,
+        matrix: &Matrix,
+    ) -> Result<i32, ParasailError> {
+        Ok(self.score_pairs(&[(query, reference)], mode, open_cost, gap_extend_cost, matrix)?[0])
+    }
+}
+
+/// parasail's striped kernels, which vectorize along the query. This is the engine every other function in the crate
+/// uses, and the best choice for all but very short sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ParasailBackend;
+
+impl AlignmentBackend for ParasailBackend {
+    fn score_pairs(
+        &self,
+        pairs: &[(&[u8], &[u8])],
+        mode: AlignmentMode,
+        open_cost: i32,
+        gap_extend_cost: i32,
+        matrix: &Matrix,
+    ) -> Result<Vec<i32>, ParasailError> {
+        let mut profile: Option<Profile> = None;
+        pairs
+            .iter()
+            .map(|&(query, reference)| {
+                // runs of pairs sharing a query reuse its profile
+                if profile.as_ref().map(|p| p.query()) != Some(query) {
+                    profile = Some(Profile::new(query, matrix));
+                }
+                let profile = profile.as_ref().expect("profile was just built");
+                mode.score(profile, reference, open_cost, gap_extend_cost)
+            })
+            .collect()
+    }
+}
+
+/// The number of pairs `InterSequenceBackend` aligns at once.
+const LANES: usize = 8;
+
+/// Far enough below any real score to never win, and far enough above `i32::MIN` to never overflow.
+const NEG_INF: i32 = i32::MIN / 4;
+
+/// A pure Rust engine which aligns several independent pairs at once, one pair per SIMD lane, in the style of SSW's
+/// and block-aligner's inter-sequence kernels.
+///
+/// parasail's striped kernels spread a single query across a vector, which only pays off once the query fills many
+/// vectors, and each call pays for building a profile and crossing the FFI boundary. For millions of pairs of short
+/// sequences, such as barcodes, those overheads dominate. This backend instead fills the dynamic programming matrices
+/// of eight pairs in lockstep, so each cell update works on all eight pairs together and the compiler can vectorize
+/// it, with no profiles at all. Pairs are taken in the given order, so grouping pairs of similar lengths keeps the
+/// lanes busy. Long sequences are better left to `ParasailBackend`.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let pairs: Vec<(&[u8], &[u8])> = vec![(b"ACGTACGT", b"ACGTACGT"), (b"ACGTACGT", b"ACGTTCGT")];
+///
+/// let scores = InterSequenceBackend.score_pairs(&pairs, AlignmentMode::Global, 1, 1, &matrix).unwrap();
+/// assert_eq!(ParasailBackend.score_pairs(&pairs, AlignmentMode::Global, 1, 1, &matrix).unwrap(), scores);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct InterSequenceBackend;
+
+impl AlignmentBackend for InterSequenceBackend {
+    fn score_pairs(
+        &self,
+        pairs: &[(&[u8], &[u8])],
+        mode: AlignmentMode,
+        open_cost: i32,
+        gap_extend_cost: i32,
+        matrix: &Matrix,
+    ) -> Result<Vec<i32>, ParasailError> {
+        let scores = SubstitutionTable::new(pairs, matrix);
+        Ok(pairs
+            .chunks(LANES)
+            .flat_map(|group| score_group(group, mode, open_cost, gap_extend_cost, &scores))
+            .collect())
+    }
+}
+
+/// The substitution scores of every pair of characters appearing in a batch of pairs, looked up once from the matrix.
+pub(crate) struct SubstitutionTable {
+    // each character's index in the table, for characters appearing in the batch
+    index: [usize; 256],
+    width: usize,
+    scores: Vec<i32>,
+}
+
+impl SubstitutionTable {
+    pub(crate) fn new(pairs: &[(&[u8], &[u8])], matrix: &Matrix) -> Self {
+        let mut seen = [false; 256];
+        for &(query, reference) in pairs {
+            for &c in query.iter().chain(reference) {
+                seen[c as usize] = true;
+            }
+        }
+        let characters: Vec<u8> = (0..=255u8).filter(|&c| seen[c as usize]).collect();
+
+        let mut index = [0; 256];
+        for (i, &c) in characters.iter().enumerate() {
+            index[c as usize] = i;
+        }
+        let scores = characters
+            .iter()
+            .flat_map(|&a| characters.iter().map(move |&b| (a, b)))
+            .map(|(a, b)| matrix.score(a, b))
+            .collect();
+
+        SubstitutionTable {
+            index,
+            width: characters.len(),
+            scores,
+        }
+    }
+
+    pub(crate) fn score(&self, a: u8, b: u8) -> i32 {
+        self.scores[self.index[a as usize] * self.width + self.index[b as usize]]
+    }
+
+    /// Every score in the table.
+    #[cfg(feature = "wfa")]
+    pub(crate) fn values(&self) -> &[i32] {
+        &self.scores
+    }
+}
+
+/// Scores up to `LANES` pairs together, one per lane. Every lane runs over the longest query and reference of the
+/// group, but a lane's cells within its own pair's bounds only ever depend on each other, so the padding never leaks
+/// into its score.
+fn score_group(
+    group: &[(&[u8], &[u8])],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    table: &SubstitutionTable,
+) -> Vec<i32> {
+    let free_ends = mode != AlignmentMode::Global;
+    let local = mode == AlignmentMode::Local;
+    let gap = |len: usize| if len == 0 { 0 } else { -(open_cost + (len as i32 - 1) * gap_extend_cost) };
+    let edge = |len: usize| if free_ends { 0 } else { gap(len) };
+
+    let query_lens: Vec<usize> = group.iter().map(|(q, _)| q.len()).collect();
+    let ref_lens: Vec<usize> = group.iter().map(|(_, r)| r.len()).collect();
+    let rows = query_lens.iter().copied().max().unwrap_or(0);
+    let cols = ref_lens.iter().copied().max().unwrap_or(0);
+
+    // the best score ending at (i, j) in any state, and ending in an insertion, for the previous query row
+    let mut h: Vec<[i32; LANES]> = (0..=cols).map(|j| [edge(j); LANES]).collect();
+    let mut insertion: Vec<[i32; LANES]> = vec![[NEG_INF; LANES]; cols + 1];
+    let mut best = [if local { 0 } else { NEG_INF }; LANES];
+
+    // a global alignment of an empty query is one gap along the reference
+    for (lane, (&query_len, &ref_len)) in query_lens.iter().zip(&ref_lens).enumerate() {
+        if query_len == 0 && mode == AlignmentMode::Global {
+            best[lane] = gap(ref_len);
+        }
+    }
+
+    let mut substitution = [0; LANES];
+    for i in 1..=rows {
+        let mut diagonal = h[0];
+        h[0] = [edge(i); LANES];
+        // the best score ending in a deletion at (i, j)
+        let mut deletion = [NEG_INF; LANES];
+
+        for j in 1..=cols {
+            for (lane, &(query, reference)) in group.iter().enumerate() {
+                substitution[lane] = match (query.get(i - 1), reference.get(j - 1)) {
+                    (Some(&q), Some(&r)) => table.score(q, r),
+                    _ => 0,
+                };
+            }
+
+            let (up, left) = (h[j], h[j - 1]);
+            let mut cell = [0; LANES];
+            for lane in 0..LANES {
+                insertion[j][lane] = (up[lane] - open_cost).max(insertion[j][lane] - gap_extend_cost);
+                deletion[lane] = (left[lane] - open_cost).max(deletion[lane] - gap_extend_cost);
+                cell[lane] = (diagonal[lane] + substitution[lane]).max(insertion[j][lane]).max(deletion[lane]);
+                if local {
+                    cell[lane] = cell[lane].max(0);
+                }
+            }
+            diagonal = up;
+            h[j] = cell;
+
+            for (lane, (&query_len, &ref_len)) in query_lens.iter().zip(&ref_lens).enumerate() {
+                let eligible = i <= query_len
+                    && j <= ref_len
+                    && match mode {
+                        AlignmentMode::Local => true,
+                        AlignmentMode::SemiGlobal => i == query_len || j == ref_len,
+                        AlignmentMode::Global => i == query_len && j == ref_len,
+                    };
+                if eligible {
+                    best[lane] = best[lane].max(cell[lane]);
+                }
+            }
+        }
+
+        // a global alignment against an empty reference is one gap along the query
+        for (lane, (&query_len, &ref_len)) in query_lens.iter().zip(&ref_lens).enumerate() {
+            if ref_len == 0 && i == query_len && mode == AlignmentMode::Global {
+                best[lane] = gap(query_len);
+            }
+        }
+    }
+
+    // free end gaps make any alignment with an empty sequence score nothing
+    for (lane, (&query_len, &ref_len)) in query_lens.iter().zip(&ref_lens).enumerate() {
+        if free_ends && (query_len == 0 || ref_len == 0) {
+            best[lane] = 0;
+        }
+    }
+
+    best[..group.len()].to_vec()
+}
+
+/// The [block-aligner](https://github.com/Daniel-Liu-c0deb0t/block-aligner) crate's adaptive block engine, a pure
+/// Rust alternative to parasail's kernels for global alignment. Requires the `block-aligner` feature.
+///
+/// Rather than filling the whole dynamic programming matrix, block-aligner fills a block around the alignment path
+/// which grows (up to `max_block_size`) where the path gets hard to follow, so it's much faster on long, similar
+/// sequences. The scores are exact whenever the best path stays within the block, which is nearly always for similar
+/// sequences, but can come out lower for very divergent ones.
+///
+/// Only global alignment is supported, so other modes are passed on to `ParasailBackend`, as are matrices and gap
+/// costs that don't fit in block-aligner's `i8` scores. The matrix is copied for the letters `A` to `Z`, matched
+/// case-insensitively.
+#[cfg(feature = "block-aligner")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockAlignerBackend {
+    /// The size of the block to start from. Must be a power of two, at least 32.
+    pub min_block_size: usize,
+    /// The size the block may grow to. Must be a power of two, at least `min_block_size`.
+    pub max_block_size: usize,
+}
+
+#[cfg(feature = "block-aligner")]
+impl Default for BlockAlignerBackend {
+    fn default() -> Self {
+        BlockAlignerBackend {
+            min_block_size: 32,
+            max_block_size: 256,
+        }
+    }
+}
+
+#[cfg(feature = "block-aligner")]
+impl AlignmentBackend for BlockAlignerBackend {
+    fn score_pairs(
+        &self,
+        pairs: &[(&[u8], &[u8])],
+        mode: AlignmentMode,
+        open_cost: i32,
+        gap_extend_cost: i32,
+        matrix: &Matrix,
+    ) -> Result<Vec<i32>, ParasailError> {
+        use block_aligner::scan_block::{Block, PaddedBytes};
+        use block_aligner::scores::{AAMatrix, Gaps};
+        use std::convert::TryFrom;
+
+        let parasail = || ParasailBackend.score_pairs(pairs, mode, open_cost, gap_extend_cost, matrix);
+        if mode != AlignmentMode::Global {
+            return parasail();
+        }
+
+        // like parasail's, block-aligner's open cost covers the first gap column
+        let negated = |cost: i32| cost.checked_neg().and_then(|cost| i8::try_from(cost).ok());
+        let gaps = match (negated(open_cost), negated(gap_extend_cost)) {
+            (Some(open), Some(extend)) => Gaps { open, extend },
+            _ => return parasail(),
+        };
+        let mut scores = AAMatrix::new_simple(0, 0);
+        for a in b'A'..=b'Z' {
+            for b in b'A'..=b'Z' {
+                match i8::try_from(matrix.score(a, b)) {
+                    Ok(score) => scores.set(a, b, score),
+                    Err(_) => return parasail(),
+                }
+            }
+        }
+
+        Ok(pairs
+            .iter()
+            .map(|&(query, reference)| {
+                let query = PaddedBytes::from_bytes::<AAMatrix>(query, self.max_block_size);
+                let reference = PaddedBytes::from_bytes::<AAMatrix>(reference, self.max_block_size);
+                let mut block = Block::<false, false>::new(query.len(), reference.len(), self.max_block_size);
+                block.align(&query, &reference, &scores, gaps, self.min_block_size..=self.max_block_size, 0);
+                block.res().score
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn test_inter_sequence_matches_scalar() {
+    use crate::matrix::MatrixType;
+    use crate::scalar::{asymmetric_alignment, AsymmetricGaps};
+
+    let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    let sequences: Vec<&[u8]> = vec![b"", b"A", b"ACGT", b"ACGTTACGT", b"TTACG", b"GATTACA", b"ACGACGTACGTT"];
+    let pairs: Vec<(&[u8], &[u8])> = sequences
+        .iter()
+        .flat_map(|&q| sequences.iter().map(move |&r| (q, r)))
+        .collect();
+
+    for mode in [AlignmentMode::Global, AlignmentMode::SemiGlobal, AlignmentMode::Local] {
+        let scores = InterSequenceBackend.score_pairs(&pairs, mode, 3, 1, &matrix).unwrap();
+        for (&(query, reference), score) in pairs.iter().zip(scores) {
+            let expected = asymmetric_alignment(query, reference, mode, &AsymmetricGaps::symmetric(3, 1), &matrix);
+            assert_eq!(expected.score, score as i64, "{:?} {:?}", query, reference);
+        }
+    }
+}
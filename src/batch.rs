@@ -0,0 +1,710 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::align::{global_alignment_score, local_alignment_score, semi_global_alignment_score};
+use crate::checkpoint::BatchCheckpoint;
+use crate::error::{AlignError, ParasailError};
+use crate::parallel;
+use crate::profile::{Profile, ProfileSet};
+use crate::progress::Progress;
+use crate::scheme::ScoringScheme;
+use crate::sink::ResultSink;
+use crate::top_hits::TopHits;
+
+/// Which kind of pairwise alignment to score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlignmentMode {
+    /// Needleman-Wunsch, as in `global_alignment_score`.
+    Global,
+    /// Gaps at either end of the reference are free, as in `semi_global_alignment_score`.
+    SemiGlobal,
+    /// Smith-Waterman, as in `local_alignment_score`.
+    Local,
+}
+
+impl AlignmentMode {
+    /// Scores one reference against a query profile using this mode.
+    pub fn score(
+        self,
+        query_profile: &Profile,
+        database_sequence: &[u8],
+        open_cost: i32,
+        gap_extend_cost: i32,
+    ) -> Result<i32, ParasailError> {
+        match self {
+            AlignmentMode::Global => global_alignment_score(query_profile, database_sequence, open_cost, gap_extend_cost),
+            AlignmentMode::SemiGlobal => {
+                semi_global_alignment_score(query_profile, database_sequence, open_cost, gap_extend_cost)
+            }
+            AlignmentMode::Local => local_alignment_score(query_profile, database_sequence, open_cost, gap_extend_cost),
+        }
+    }
+}
+
+/// Scores a query against many references on several threads.
+///
+/// The references are split into contiguous chunks, one per thread, and each chunk's scores are stitched back
+/// together in chunk order, so `scores[i]` is always the score against `references[i]` no matter how the threads are
+/// scheduled. Each thread builds its own profile of the query. A `threads` of 0 uses the available parallelism.
+/// Returns the first error parasail reports, if any.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let references: Vec<&[u8]> = vec![b"ACGTACGT", b"TTTTTTTT", b"ACGTTCGT"];
+///
+/// let scores = align_batch(b"ACGTACGT", &references, AlignmentMode::Global, 1, 1, &matrix, 2).unwrap();
+/// assert_eq!(vec![8, -4, 6], scores);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn align_batch<S: ScoringScheme + ?Sized>(
+    query: &[u8],
+    references: &[&[u8]],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    scheme: &S,
+    threads: usize,
+) -> Result<Vec<i32>, ParasailError> {
+    align_batch_with_progress(query, references, mode, open_cost, gap_extend_cost, scheme, threads, &())
+}
+
+/// Scores a query against many references on several threads, as `align_batch` does, reporting each finished
+/// reference to `progress` (see `Progress`), so long batches can show a progress bar.
+#[allow(clippy::too_many_arguments)]
+pub fn align_batch_with_progress<S: ScoringScheme + ?Sized>(
+    query: &[u8],
+    references: &[&[u8]],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    scheme: &S,
+    threads: usize,
+    progress: &dyn Progress,
+) -> Result<Vec<i32>, ParasailError> {
+    let matrix = scheme.matrix();
+    progress.set_total(references.len() as u64);
+
+    let scores = parallel::map_chunks(references, threads, |chunk| {
+        let profile = Profile::new(query, matrix);
+        chunk
+            .iter()
+            .map(|reference| {
+                let score = mode.score(&profile, reference, open_cost, gap_extend_cost);
+                progress.advance(1);
+                score
+            })
+            .collect()
+    });
+    progress.finish();
+    scores.into_iter().collect()
+}
+
+/// Scores a query against many references on several threads, as `align_batch` does, and returns the indices of the
+/// `n` best scoring references scoring at least `min_score`, best first. References with equal scores are ranked in
+/// input order.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let references: Vec<&[u8]> = vec![b"ACGTTCGT", b"TTTTTTTT", b"ACGTACGT", b"ACGTACCT"];
+///
+/// let best = top_references(b"ACGTACGT", &references, AlignmentMode::Global, 1, 1, &matrix, 2, 2, 0).unwrap();
+/// assert_eq!(vec![(8, 2), (6, 0)], best);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn top_references<S: ScoringScheme + ?Sized>(
+    query: &[u8],
+    references: &[&[u8]],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    scheme: &S,
+    threads: usize,
+    n: usize,
+    min_score: i64,
+) -> Result<Vec<(i64, usize)>, ParasailError> {
+    let scores = align_batch(query, references, mode, open_cost, gap_extend_cost, scheme, threads)?;
+
+    let mut top = TopHits::new(n, min_score);
+    for (i, score) in scores.into_iter().enumerate() {
+        top.push(score as i64, i);
+    }
+    Ok(top.into_sorted_vec())
+}
+
+/// Scores a query against many references as `align_batch` does, in segments of `every` references (at least 1),
+/// recording each finished segment in `checkpoint` and handing it to `save`. If the run is interrupted, the last
+/// saved checkpoint can be loaded and passed back in to score only the references it hadn't reached.
+///
+/// The checkpoint holds every score so far, so it grows with the batch. Each segment builds its profiles again, so
+/// `every` should be large enough for a segment to take a while. Returns an error if the checkpoint is for a batch
+/// of a different size or didn't come from this function, if `save` fails, or if parasail reports one.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let references: Vec<&[u8]> = vec![b"ACGTACGT", b"TTTTTTTT", b"ACGTTCGT"];
+/// let mode = AlignmentMode::Global;
+///
+/// // stands in for a file written with BatchCheckpoint::write_to
+/// let query = b"ACGTACGT";
+/// let mut saved = Vec::new();
+/// let mut checkpoint = BatchCheckpoint::new(references.len());
+/// let scores = align_batch_checkpointed(query, &references, mode, 1, 1, &matrix, 2, &mut checkpoint, 2, |c| {
+///     saved.clear();
+///     c.write(&mut saved)
+/// });
+/// assert_eq!(vec![8, -4, 6], scores.unwrap());
+///
+/// // resuming a finished batch scores nothing more
+/// let mut resumed = BatchCheckpoint::read(&saved[..]).unwrap();
+/// assert!(resumed.is_complete());
+/// let scores = align_batch_checkpointed(query, &references, mode, 1, 1, &matrix, 2, &mut resumed, 2, |_| Ok(()));
+/// assert_eq!(vec![8, -4, 6], scores.unwrap());
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn align_batch_checkpointed<S, F>(
+    query: &[u8],
+    references: &[&[u8]],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    scheme: &S,
+    threads: usize,
+    checkpoint: &mut BatchCheckpoint,
+    every: usize,
+    mut save: F,
+) -> Result<Vec<i32>, ParasailError>
+where
+    S: ScoringScheme + ?Sized,
+    F: FnMut(&BatchCheckpoint) -> io::Result<()>,
+{
+    check_checkpoint(checkpoint, references.len())?;
+    let in_order = checkpoint.hits.iter().enumerate().all(|(i, &(_, index))| index == i);
+    if checkpoint.hits.len() != checkpoint.done || !in_order {
+        return Err(AlignError::InvalidParameter {
+            reason: "checkpoint doesn't hold every score so far; was it saved by top_references_checkpointed?",
+        }
+        .into());
+    }
+
+    while checkpoint.done < references.len() {
+        let (begin, end) = (checkpoint.done, (checkpoint.done + every.max(1)).min(references.len()));
+        let scores = align_batch(query, &references[begin..end], mode, open_cost, gap_extend_cost, scheme, threads)?;
+        checkpoint.hits.extend(scores.into_iter().zip(begin..).map(|(score, i)| (score as i64, i)));
+        checkpoint.done = end;
+        save(checkpoint)?;
+    }
+    Ok(checkpoint.hits.iter().map(|&(score, _)| score as i32).collect())
+}
+
+/// Ranks references as `top_references` does, in segments of `every` references (at least 1), recording the
+/// progress and the best hits so far in `checkpoint` after each segment and handing it to `save`. If the run is
+/// interrupted, the last saved checkpoint can be loaded and passed back in to carry on from where it stopped. The
+/// result is the same as an uninterrupted run's.
+///
+/// The checkpoint holds at most `n` hits, however big the batch. Each segment builds its profiles again, so `every`
+/// should be large enough for a segment to take a while. Returns an error if the checkpoint is for a batch of a
+/// different size, if `save` fails, or if parasail reports one.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let references: Vec<&[u8]> = vec![b"ACGTTCGT", b"TTTTTTTT", b"ACGTACGT", b"ACGTACCT"];
+/// let mode = AlignmentMode::Global;
+///
+/// let query = b"ACGTACGT";
+///
+/// // pretend the job was preempted while saving the second segment
+/// let mut saved = Vec::new();
+/// let mut checkpoint = BatchCheckpoint::new(references.len());
+/// let save = |c: &BatchCheckpoint| {
+///     if c.is_complete() {
+///         return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "preempted"));
+///     }
+///     c.write(&mut saved)
+/// };
+/// let interrupted =
+///     top_references_checkpointed(query, &references, mode, 1, 1, &matrix, 2, 2, 0, &mut checkpoint, 2, save);
+/// assert!(interrupted.is_err());
+///
+/// let mut resumed = BatchCheckpoint::read(&saved[..]).unwrap();
+/// assert_eq!(2, resumed.done());
+/// let ignore = |_: &BatchCheckpoint| Ok(());
+/// let best = top_references_checkpointed(query, &references, mode, 1, 1, &matrix, 2, 2, 0, &mut resumed, 2, ignore);
+/// assert_eq!(vec![(8, 2), (6, 0)], best.unwrap());
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn top_references_checkpointed<S, F>(
+    query: &[u8],
+    references: &[&[u8]],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    scheme: &S,
+    threads: usize,
+    n: usize,
+    min_score: i64,
+    checkpoint: &mut BatchCheckpoint,
+    every: usize,
+    mut save: F,
+) -> Result<Vec<(i64, usize)>, ParasailError>
+where
+    S: ScoringScheme + ?Sized,
+    F: FnMut(&BatchCheckpoint) -> io::Result<()>,
+{
+    check_checkpoint(checkpoint, references.len())?;
+
+    // offering the saved hits again in input order keeps ties ranked as in an uninterrupted run
+    let mut saved = checkpoint.hits.clone();
+    saved.sort_by_key(|&(_, i)| i);
+    let mut top = TopHits::new(n, min_score);
+    for (score, i) in saved {
+        top.push(score, i);
+    }
+
+    while checkpoint.done < references.len() {
+        let (begin, end) = (checkpoint.done, (checkpoint.done + every.max(1)).min(references.len()));
+        let scores = align_batch(query, &references[begin..end], mode, open_cost, gap_extend_cost, scheme, threads)?;
+        for (score, i) in scores.into_iter().zip(begin..) {
+            top.push(score as i64, i);
+        }
+        checkpoint.done = end;
+        checkpoint.hits = top.clone().into_sorted_vec();
+        save(checkpoint)?;
+    }
+    Ok(top.into_sorted_vec())
+}
+
+fn check_checkpoint(checkpoint: &BatchCheckpoint, num_references: usize) -> Result<(), AlignError> {
+    if checkpoint.num_references != num_references {
+        return Err(AlignError::InvalidParameter {
+            reason: "checkpoint is for a batch with a different number of references",
+        });
+    }
+    Ok(())
+}
+
+/// The highest mapping quality `mapping_quality` reports, as in minimap2 and BWA.
+pub const MAX_MAPQ: u8 = 60;
+
+/// A mapping quality for a hit scoring `score` when the best score among the other candidates was `runner_up`: how
+/// confident we can be that the hit, and not one of the others, is where the query really belongs.
+///
+/// This is minimap2's estimate without the anchor count term, `60 * (1 - runner_up / score)`, rounded and clamped to
+/// between 0 and `MAX_MAPQ`. A hit with no competitor (`None`) or only non-positive ones gets `MAX_MAPQ`, and a hit
+/// tied with or beaten by another, or not scoring above zero itself, gets 0.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// assert_eq!(60, mapping_quality(100, None));
+/// assert_eq!(30, mapping_quality(100, Some(50)));
+/// assert_eq!(0, mapping_quality(100, Some(100)));
+/// assert_eq!(0, mapping_quality(80, Some(100)));
+/// ```
+pub fn mapping_quality(score: i64, runner_up: Option<i64>) -> u8 {
+    if score <= 0 {
+        return 0;
+    }
+    let runner_up = runner_up.unwrap_or(0).max(0);
+    let mapq = MAX_MAPQ as f64 * (1.0 - runner_up as f64 / score as f64);
+    mapq.round().clamp(0.0, MAX_MAPQ as f64) as u8
+}
+
+/// One of the references reported by `top_references_with_mapq`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReferenceHit {
+    /// The score according to the substitution matrix and gap penalty scheme used.
+    pub score: i64,
+    /// The index of the reference in the database.
+    pub index: usize,
+    /// The mapping quality of the hit against the best scoring of all the other references (see `mapping_quality`).
+    /// Only the best hit can score above 0.
+    pub mapq: u8,
+}
+
+/// Ranks references as `top_references` does, and gives each reported hit a mapping quality from the gap between its
+/// score and the best score of every other reference in the database, including those not reported or scoring below
+/// `min_score`. A query which scores about as well against two references gets a low quality on both, which is how
+/// ambiguous assignments are usually filtered out.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let references: Vec<&[u8]> = vec![b"ACGTTCGT", b"TTTTTTTT", b"ACGTACGT", b"ACGTACCT"];
+///
+/// let mode = AlignmentMode::Global;
+/// let best = top_references_with_mapq(b"ACGTACGT", &references, mode, 1, 1, &matrix, 2, 2, 0).unwrap();
+/// assert_eq!((8, 2, 15), (best[0].score, best[0].index, best[0].mapq));
+/// assert_eq!((6, 0, 0), (best[1].score, best[1].index, best[1].mapq));
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn top_references_with_mapq<S: ScoringScheme + ?Sized>(
+    query: &[u8],
+    references: &[&[u8]],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    scheme: &S,
+    threads: usize,
+    n: usize,
+    min_score: i64,
+) -> Result<Vec<ReferenceHit>, ParasailError> {
+    let scores = align_batch(query, references, mode, open_cost, gap_extend_cost, scheme, threads)?;
+
+    // the runner-up for the best reference is the second best, and for everyone else it's the best
+    let mut leaders = TopHits::new(2, i64::MIN);
+    let mut top = TopHits::new(n, min_score);
+    for (i, &score) in scores.iter().enumerate() {
+        leaders.push(score as i64, i);
+        top.push(score as i64, i);
+    }
+    let leaders = leaders.into_sorted_vec();
+
+    Ok(top
+        .into_sorted_vec()
+        .into_iter()
+        .map(|(score, index)| {
+            let runner_up = leaders.iter().find(|&&(_, i)| i != index).map(|&(score, _)| score);
+            ReferenceHit {
+                score,
+                index,
+                mapq: mapping_quality(score, runner_up),
+            }
+        })
+        .collect())
+}
+
+/// Scores every query against every reference, tiling the work into blocks so profiles are reused and stay in cache.
+///
+/// The queries and references are cut into blocks of `block_size` sequences, and each thread takes a contiguous run
+/// of (query block, reference block) tiles, ordered query block first. Within a tile each reference is scored
+/// against all of the block's query profiles in turn, so the reference stays hot in cache, and a thread only builds
+/// profiles again when it moves on to a new query block. This avoids both rebuilding a profile per pair and streaming
+/// every reference through cache once per query, which is what dominates naive nested loops on large inputs.
+///
+/// Returns the scores in row-major order, so `scores[q * references.len() + r]` is the score of `queries[q]` against
+/// `references[r]`, regardless of thread scheduling. A `threads` of 0 uses the available parallelism, and a
+/// `block_size` of 0 is treated as 1.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let queries: Vec<&[u8]> = vec![b"ACGTACGT", b"TTTTTTTT"];
+/// let references: Vec<&[u8]> = vec![b"ACGTACGT", b"TTTTTTTT", b"ACGTTCGT"];
+///
+/// let scores = align_many_to_many(&queries, &references, AlignmentMode::Global, 1, 1, &matrix, 2, 64).unwrap();
+/// assert_eq!(vec![8, -4, 6, -4, 8, -2], scores);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn align_many_to_many<S: ScoringScheme + ?Sized>(
+    queries: &[&[u8]],
+    references: &[&[u8]],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    scheme: &S,
+    threads: usize,
+    block_size: usize,
+) -> Result<Vec<i32>, ParasailError> {
+    let matrix = scheme.matrix();
+    let block_size = block_size.max(1);
+
+    let query_blocks = queries.len().div_ceil(block_size);
+    let reference_blocks = references.len().div_ceil(block_size);
+    let tiles: Vec<(usize, usize)> = (0..query_blocks)
+        .flat_map(|qb| (0..reference_blocks).map(move |rb| (qb, rb)))
+        .collect();
+    let block = |len: usize, b: usize| b * block_size..((b + 1) * block_size).min(len);
+
+    let tile_scores: Vec<Result<Vec<i32>, ParasailError>> = parallel::map_chunks(&tiles, threads, |chunk| {
+        let mut profiles: Vec<Profile> = Vec::new();
+        let mut profiled_block = None;
+
+        chunk
+            .iter()
+            .map(|&(qb, rb)| {
+                if profiled_block != Some(qb) {
+                    profiles = queries[block(queries.len(), qb)].iter().map(|q| Profile::new(q, matrix)).collect();
+                    profiled_block = Some(qb);
+                }
+
+                // reference-major within the tile, so each reference is read once per tile
+                let block_refs = &references[block(references.len(), rb)];
+                let mut scores = vec![0; profiles.len() * block_refs.len()];
+                for (r, reference) in block_refs.iter().enumerate() {
+                    for (q, profile) in profiles.iter().enumerate() {
+                        scores[q * block_refs.len() + r] = mode.score(profile, reference, open_cost, gap_extend_cost)?;
+                    }
+                }
+                Ok(scores)
+            })
+            .collect()
+    });
+
+    let mut scores = vec![0; queries.len() * references.len()];
+    for (&(qb, rb), tile) in tiles.iter().zip(tile_scores) {
+        let tile = tile?;
+        let (query_range, reference_range) = (block(queries.len(), qb), block(references.len(), rb));
+        let width = reference_range.len();
+        for (q, row) in query_range.zip(tile.chunks(width)) {
+            let start = q * references.len() + reference_range.start;
+            scores[start..start + width].copy_from_slice(row);
+        }
+    }
+    Ok(scores)
+}
+
+/// Scores every profile of a set against every reference on several threads, so that profiles built once up front
+/// (see `ProfileSet`) can be reused across many jobs.
+///
+/// Each thread takes a contiguous run of the profiles. Returns the scores in row-major order, so
+/// `scores[q * references.len() + r]` is the score of the `q`th profile against `references[r]`, regardless of thread
+/// scheduling. A `threads` of 0 uses the available parallelism.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let queries: Vec<&[u8]> = vec![b"ACGTACGT", b"TTTTTTTT"];
+/// let references: Vec<&[u8]> = vec![b"ACGTACGT", b"TTTTTTTT", b"ACGTTCGT"];
+/// let profiles = ProfileSet::new(&queries, &matrix);
+///
+/// let scores = align_profile_set(&profiles, &references, AlignmentMode::Global, 1, 1, 2).unwrap();
+/// assert_eq!(vec![8, -4, 6, -4, 8, -2], scores);
+/// ```
+pub fn align_profile_set(
+    profiles: &ProfileSet,
+    references: &[&[u8]],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    threads: usize,
+) -> Result<Vec<i32>, ParasailError> {
+    let scores = parallel::map_chunks(profiles.as_slice(), threads, |chunk| {
+        chunk
+            .iter()
+            .flat_map(|profile| {
+                references.iter().map(move |reference| mode.score(profile, reference, open_cost, gap_extend_cost))
+            })
+            .collect()
+    });
+    scores.into_iter().collect()
+}
+
+/// Scores a query against many references on several threads, as `align_batch` does, but sends each
+/// `(index, score)` pair to `sink` as soon as it's computed instead of collecting them, so results can be written out
+/// while the rest are still being aligned.
+///
+/// Threads take references one at a time from a shared counter, so results arrive roughly, but not exactly, in input
+/// order. If the sink stops listening, the threads stop taking more work, and `false` is returned; otherwise every
+/// reference has been sent once this returns `true`. The threads also stop if parasail reports an error, which is
+/// returned instead. A `threads` of 0 uses the available parallelism.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// use std::sync::mpsc;
+///
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let references: Vec<&[u8]> = vec![b"ACGTACGT", b"TTTTTTTT", b"ACGTTCGT"];
+///
+/// // a bounded channel holds the threads back if the consumer can't keep up
+/// let (sender, receiver) = mpsc::sync_channel(16);
+/// let complete = std::thread::scope(|scope| {
+///     let writer = scope.spawn(move || {
+///         let mut scores: Vec<(usize, i32)> = receiver.iter().collect();
+///         scores.sort();
+///         scores
+///     });
+///     let mode = AlignmentMode::Global;
+///     let complete = align_batch_streaming(b"ACGTACGT", &references, mode, 1, 1, &matrix, 2, &sender).unwrap();
+///     drop(sender);
+///     assert_eq!(vec![(0, 8), (1, -4), (2, 6)], writer.join().unwrap());
+///     complete
+/// });
+/// assert!(complete);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn align_batch_streaming<M, S>(
+    query: &[u8],
+    references: &[&[u8]],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    scheme: &M,
+    threads: usize,
+    sink: &S,
+) -> Result<bool, ParasailError>
+where
+    M: ScoringScheme + ?Sized,
+    S: ResultSink<(usize, i32)> + Sync,
+{
+    let matrix = scheme.matrix();
+    let threads = parallel::thread_count(threads);
+    let next = AtomicUsize::new(0);
+    let hung_up = AtomicBool::new(false);
+    let failure = Mutex::new(None);
+
+    thread::scope(|scope| {
+        for _ in 0..threads.min(references.len()) {
+            scope.spawn(|| {
+                let profile = Profile::new(query, matrix);
+                while !hung_up.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let reference = match references.get(index) {
+                        Some(reference) => reference,
+                        None => break,
+                    };
+                    let score = match mode.score(&profile, reference, open_cost, gap_extend_cost) {
+                        Ok(score) => score,
+                        Err(err) => {
+                            failure.lock().unwrap().get_or_insert(err);
+                            hung_up.store(true, Ordering::Relaxed);
+                            break;
+                        }
+                    };
+                    if !sink.send_result((index, score)) {
+                        hung_up.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+
+    match failure.into_inner().unwrap() {
+        Some(err) => Err(err),
+        None => Ok(!hung_up.into_inner()),
+    }
+}
+
+#[test]
+fn test_batch_preserves_input_order() {
+    use crate::matrix::{Matrix, MatrixType};
+
+    let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    let query = b"ACGTACGTTGCA";
+    let owned: Vec<Vec<u8>> = (0..37)
+        .map(|i| {
+            let mut reference = b"TTACGTACGTTGCATT".to_vec();
+            reference.truncate(4 + i % 13);
+            reference
+        })
+        .collect();
+    let references: Vec<&[u8]> = owned.iter().map(|r| r.as_slice()).collect();
+
+    for &mode in &[AlignmentMode::Global, AlignmentMode::SemiGlobal, AlignmentMode::Local] {
+        let profile = Profile::new(query, &matrix);
+        let expected: Vec<i32> = references.iter().map(|r| mode.score(&profile, r, 1, 1).unwrap()).collect();
+
+        for threads in 1..6 {
+            assert_eq!(expected, align_batch(query, &references, mode, 1, 1, &matrix, threads).unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_many_to_many_matches_batch() {
+    use crate::matrix::{Matrix, MatrixType};
+
+    let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    let owned: Vec<Vec<u8>> = (0..23)
+        .map(|i| {
+            let mut sequence = b"TTACGTACGTTGCATTGACA".to_vec();
+            sequence.rotate_left(i % 7);
+            sequence.truncate(5 + i % 11);
+            sequence
+        })
+        .collect();
+    let sequences: Vec<&[u8]> = owned.iter().map(|s| s.as_slice()).collect();
+    let (queries, references) = sequences.split_at(9);
+
+    let expected: Vec<i32> = queries
+        .iter()
+        .flat_map(|q| align_batch(q, references, AlignmentMode::Local, 1, 1, &matrix, 1).unwrap())
+        .collect();
+
+    for threads in 1..5 {
+        for block_size in 0..6 {
+            let scores =
+                align_many_to_many(queries, references, AlignmentMode::Local, 1, 1, &matrix, threads, block_size);
+            assert_eq!(expected, scores.unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_checkpointed_batch_resumes_where_it_stopped() {
+    use crate::matrix::{Matrix, MatrixType};
+
+    let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    let query = b"ACGTACGTTGCA";
+    let owned: Vec<Vec<u8>> = (0..19)
+        .map(|i| {
+            let mut reference = b"TTACGTACGTTGCATT".to_vec();
+            reference.rotate_left(i % 5);
+            reference.truncate(6 + i % 7);
+            reference
+        })
+        .collect();
+    let references: Vec<&[u8]> = owned.iter().map(|r| r.as_slice()).collect();
+    let mode = AlignmentMode::Local;
+
+    let expected_scores = align_batch(query, &references, mode, 1, 1, &matrix, 2).unwrap();
+    let expected_top = top_references(query, &references, mode, 1, 1, &matrix, 2, 4, 3).unwrap();
+
+    // stop after every possible number of saves, then resume from the last checkpoint that was saved
+    for saves in 0..5 {
+        let mut saved = BatchCheckpoint::new(references.len());
+        let mut checkpoint = saved.clone();
+        let interrupt = |c: &BatchCheckpoint, saved: &mut BatchCheckpoint| {
+            if c.done() > saves * 4 {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "stopped"));
+            }
+            *saved = c.clone();
+            Ok(())
+        };
+        let _ = align_batch_checkpointed(query, &references, mode, 1, 1, &matrix, 2, &mut checkpoint, 4, |c| {
+            interrupt(c, &mut saved)
+        });
+        let scores = align_batch_checkpointed(query, &references, mode, 1, 1, &matrix, 3, &mut saved, 5, |_| Ok(()));
+        assert_eq!(expected_scores, scores.unwrap());
+
+        let mut saved = BatchCheckpoint::new(references.len());
+        let mut checkpoint = saved.clone();
+        let _ = top_references_checkpointed(query, &references, mode, 1, 1, &matrix, 2, 4, 3, &mut checkpoint, 4, |c| {
+            interrupt(c, &mut saved)
+        });
+        let ignore = |_: &BatchCheckpoint| Ok(());
+        let top = top_references_checkpointed(query, &references, mode, 1, 1, &matrix, 3, 4, 3, &mut saved, 5, ignore);
+        assert_eq!(expected_top, top.unwrap());
+    }
+
+    let mut wrong_size = BatchCheckpoint::new(3);
+    let scores = align_batch_checkpointed(query, &references, mode, 1, 1, &matrix, 2, &mut wrong_size, 4, |_| Ok(()));
+    assert!(scores.is_err());
+}
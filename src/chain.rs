@@ -0,0 +1,169 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::extend::Extension;
+use crate::suboptimal::LocalHit;
+
+/// A matching region between a query and a reference, used as a building block for chaining.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anchor {
+    /// The score of the anchor on its own.
+    pub score: i64,
+    /// The starting index (0-based) of the anchor in the query.
+    pub query_begin: usize,
+    /// The ending index (0-based, exclusive) of the anchor in the query.
+    pub query_end: usize,
+    /// The starting index (0-based) of the anchor in the reference.
+    pub ref_begin: usize,
+    /// The ending index (0-based, exclusive) of the anchor in the reference.
+    pub ref_end: usize,
+}
+
+impl From<LocalHit> for Anchor {
+    fn from(hit: LocalHit) -> Self {
+        Anchor {
+            score: hit.score,
+            query_begin: hit.query_begin,
+            query_end: hit.query_end,
+            ref_begin: hit.ref_begin,
+            ref_end: hit.ref_end,
+        }
+    }
+}
+
+impl From<Extension> for Anchor {
+    fn from(extension: Extension) -> Self {
+        Anchor {
+            score: extension.score,
+            query_begin: extension.query_begin,
+            query_end: extension.query_end,
+            ref_begin: extension.ref_begin,
+            ref_end: extension.ref_end,
+        }
+    }
+}
+
+/// Controls how anchors may be joined into a chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainParams {
+    /// Anchors further apart than this in either sequence are never joined.
+    pub max_gap: usize,
+    /// How many of the closest preceding anchors (in reference order) are considered as predecessors of each anchor.
+    pub max_lookback: usize,
+    /// The cost per base of difference between the query and reference distances separating two anchors.
+    pub gap_scale: f64,
+}
+
+impl Default for ChainParams {
+    /// Roughly minimap2's defaults for anchors around 15 bases long.
+    fn default() -> Self {
+        ChainParams {
+            max_gap: 5000,
+            max_lookback: 50,
+            gap_scale: 0.15,
+        }
+    }
+}
+
+/// The best-scoring co-linear chain found among a set of anchors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chain {
+    /// The sum of the chained anchors' scores minus the cost of the gaps between them.
+    pub score: i64,
+    /// The chained anchors, in increasing query and reference order.
+    pub anchors: Vec<Anchor>,
+}
+
+/// Finds the highest scoring chain of co-linear anchors, similar to minimap2's chaining stage.
+///
+/// Anchors in a chain must appear in the same order in both sequences without overlapping. Joining two anchors
+/// separated by `dq` query bases and `dr` reference bases costs `gap_scale * l + 0.5 * log2(l)` (rounded up), where
+/// `l = |dq - dr|` is the implied indel length, so chains which stay near a single diagonal are preferred. Returns
+/// `None` if no anchors are given.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let anchors = vec![
+///     Anchor { score: 20, query_begin: 0, query_end: 20, ref_begin: 100, ref_end: 120 },
+///     Anchor { score: 15, query_begin: 500, query_end: 515, ref_begin: 9000, ref_end: 9015 },
+///     Anchor { score: 30, query_begin: 30, query_end: 60, ref_begin: 130, ref_end: 160 },
+/// ];
+///
+/// let chain = chain_anchors(&anchors, &ChainParams::default()).unwrap();
+/// assert_eq!(50, chain.score);
+/// assert_eq!(vec![anchors[0].clone(), anchors[2].clone()], chain.anchors);
+/// ```
+pub fn chain_anchors(anchors: &[Anchor], params: &ChainParams) -> Option<Chain> {
+    if anchors.is_empty() {
+        return None;
+    }
+
+    let mut order: Vec<usize> = (0..anchors.len()).collect();
+    order.sort_by_key(|&i| (anchors[i].ref_begin, anchors[i].query_begin));
+    let sorted: Vec<&Anchor> = order.iter().map(|&i| &anchors[i]).collect();
+
+    // best chain score ending at each anchor, and the anchor before it in that chain
+    let mut scores: Vec<i64> = Vec::with_capacity(sorted.len());
+    let mut predecessors: Vec<Option<usize>> = Vec::with_capacity(sorted.len());
+
+    for (i, anchor) in sorted.iter().enumerate() {
+        let mut best = anchor.score;
+        let mut predecessor = None;
+
+        for j in (i.saturating_sub(params.max_lookback)..i).rev() {
+            let previous = sorted[j];
+            if previous.query_end > anchor.query_begin || previous.ref_end > anchor.ref_begin {
+                continue;
+            }
+
+            let query_gap = anchor.query_begin - previous.query_end;
+            let ref_gap = anchor.ref_begin - previous.ref_end;
+            if query_gap > params.max_gap || ref_gap > params.max_gap {
+                continue;
+            }
+
+            let candidate = scores[j] + anchor.score - gap_cost(query_gap, ref_gap, params.gap_scale);
+            if candidate > best {
+                best = candidate;
+                predecessor = Some(j);
+            }
+        }
+
+        scores.push(best);
+        predecessors.push(predecessor);
+    }
+
+    // ties go to the chain ending earliest in the reference
+    let mut end = 0;
+    for (i, &score) in scores.iter().enumerate() {
+        if score > scores[end] {
+            end = i;
+        }
+    }
+
+    let mut chained = vec![sorted[end].clone()];
+    let mut current = end;
+    while let Some(previous) = predecessors[current] {
+        chained.push(sorted[previous].clone());
+        current = previous;
+    }
+    chained.reverse();
+
+    Some(Chain {
+        score: scores[end],
+        anchors: chained,
+    })
+}
+
+fn gap_cost(query_gap: usize, ref_gap: usize, gap_scale: f64) -> i64 {
+    let indel = (query_gap as i64 - ref_gap as i64).abs();
+    if indel == 0 {
+        0
+    } else {
+        (gap_scale * indel as f64 + 0.5 * (indel as f64).log2()).ceil() as i64
+    }
+}
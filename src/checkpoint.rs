@@ -0,0 +1,173 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// How far a checkpointed batch (`align_batch_checkpointed` or `top_references_checkpointed`) has got: how many of
+/// its references have been scored, and the hits kept from them. A batch given a checkpoint loaded after an
+/// interruption carries on from where it was saved instead of starting over.
+///
+/// The references are always scored in order, so the finished ones are a prefix of the input. The hits are `(score,
+/// index)` pairs: every finished reference for `align_batch_checkpointed`, and only the best ones so far for
+/// `top_references_checkpointed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchCheckpoint {
+    pub(crate) num_references: usize,
+    pub(crate) done: usize,
+    pub(crate) hits: Vec<(i64, usize)>,
+}
+
+impl BatchCheckpoint {
+    /// A checkpoint at the start of a batch over `num_references` references.
+    pub fn new(num_references: usize) -> Self {
+        BatchCheckpoint {
+            num_references,
+            done: 0,
+            hits: Vec::new(),
+        }
+    }
+
+    /// The number of references in the batch the checkpoint belongs to.
+    pub fn num_references(&self) -> usize {
+        self.num_references
+    }
+
+    /// The number of references scored so far.
+    pub fn done(&self) -> usize {
+        self.done
+    }
+
+    /// Whether every reference has been scored.
+    pub fn is_complete(&self) -> bool {
+        self.done == self.num_references
+    }
+
+    /// The `(score, index)` hits kept so far.
+    pub fn hits(&self) -> &[(i64, usize)] {
+        &self.hits
+    }
+
+    /// Writes the checkpoint in a compact, little-endian, versioned binary format, to be loaded again with `read`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let checkpoint = BatchCheckpoint::new(1000);
+    ///
+    /// let mut bytes = Vec::new();
+    /// checkpoint.write(&mut bytes).unwrap();
+    /// assert_eq!(checkpoint, BatchCheckpoint::read(&bytes[..]).unwrap());
+    /// ```
+    pub fn write<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(CHECKPOINT_MAGIC)?;
+        write_u64(&mut out, CHECKPOINT_VERSION)?;
+        write_u64(&mut out, self.num_references as u64)?;
+        write_u64(&mut out, self.done as u64)?;
+
+        write_u64(&mut out, self.hits.len() as u64)?;
+        for &(score, index) in &self.hits {
+            write_u64(&mut out, score as u64)?;
+            write_u64(&mut out, index as u64)?;
+        }
+        out.flush()
+    }
+
+    /// Reads a checkpoint written by `write`. Anything else, including a checkpoint from an incompatible version of
+    /// this crate, gives an error of kind `InvalidData`.
+    pub fn read<R: Read>(mut input: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != CHECKPOINT_MAGIC {
+            return Err(invalid_checkpoint("not a batch checkpoint"));
+        }
+        if read_u64(&mut input)? != CHECKPOINT_VERSION {
+            return Err(invalid_checkpoint("unsupported batch checkpoint version"));
+        }
+        let num_references = read_usize(&mut input)?;
+        let done = read_usize(&mut input)?;
+        if done > num_references {
+            return Err(invalid_checkpoint("batch checkpoint is past the end of its batch"));
+        }
+
+        // the count comes from the file, so it only bounds what's read, not what's allocated up front
+        let num_hits = read_usize(&mut input)?;
+        let mut hits = Vec::with_capacity(num_hits.min(1 << 16));
+        for _ in 0..num_hits {
+            let score = read_u64(&mut input)? as i64;
+            let index = read_usize(&mut input)?;
+            if index >= done {
+                return Err(invalid_checkpoint("batch checkpoint has a hit that wasn't scored yet"));
+            }
+            hits.push((score, index));
+        }
+
+        Ok(BatchCheckpoint {
+            num_references,
+            done,
+            hits,
+        })
+    }
+
+    /// Writes the checkpoint to a file, as `write` does. It's written to a temporary file next to `path` first and
+    /// then renamed over it, so being interrupted part way through leaves the previous checkpoint intact.
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+
+        self.write(BufWriter::new(File::create(&partial)?))?;
+        fs::rename(&partial, path)
+    }
+
+    /// Reads a checkpoint from a file written by `write_to`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        BatchCheckpoint::read(BufReader::new(File::open(path)?))
+    }
+}
+
+const CHECKPOINT_MAGIC: &[u8; 4] = b"PBCK";
+const CHECKPOINT_VERSION: u64 = 1;
+
+fn write_u64<W: Write>(out: &mut W, value: u64) -> io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn read_u64<R: Read>(input: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_usize<R: Read>(input: &mut R) -> io::Result<usize> {
+    let value = read_u64(input)?;
+    usize::try_from(value).map_err(|_| invalid_checkpoint("batch checkpoint value too large for this platform"))
+}
+
+fn invalid_checkpoint(reason: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+#[test]
+fn test_checkpoint_round_trip() {
+    let checkpoint = BatchCheckpoint {
+        num_references: 10,
+        done: 4,
+        hits: vec![(-3, 0), (i64::MAX, 3), (i64::MIN, 1)],
+    };
+    let mut bytes = Vec::new();
+    checkpoint.write(&mut bytes).unwrap();
+    assert_eq!(checkpoint, BatchCheckpoint::read(&bytes[..]).unwrap());
+
+    // a hit past the finished prefix can't have come from a real batch
+    let bad = BatchCheckpoint { hits: vec![(1, 4)], ..checkpoint };
+    let mut bytes = Vec::new();
+    bad.write(&mut bytes).unwrap();
+    assert_eq!(io::ErrorKind::InvalidData, BatchCheckpoint::read(&bytes[..]).unwrap_err().kind());
+    assert!(BatchCheckpoint::read(&b"PMIX"[..]).is_err());
+}
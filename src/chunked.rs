@@ -0,0 +1,199 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::collections::HashMap;
+
+use crate::align::{global_traceback, soft_clips, TracebackResults};
+use crate::chain::{chain_anchors, Anchor, ChainParams};
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::minimizer::minimizers;
+use crate::parallel;
+
+/// Controls how `chunked_global_traceback` splits up a pair of sequences.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkParams {
+    /// The k-mer length of the minimizers used as anchors.
+    pub k: usize,
+    /// The window size (in k-mers) of the minimizers used as anchors.
+    pub w: usize,
+    /// The shortest stretch of query in a chunk. Each chunk's traceback takes memory proportional to the product of
+    /// its lengths in the two sequences, so this sets the memory used, as long as anchors turn up this often.
+    pub chunk_len: usize,
+    /// How anchors are chained into the backbone the chunks are cut along.
+    pub chain: ChainParams,
+    /// The number of threads aligning chunks, or 0 for the available parallelism.
+    pub threads: usize,
+}
+
+impl Default for ChunkParams {
+    /// Minimizers of 19-mers in windows of 10, and chunks of at least 10 kb.
+    fn default() -> Self {
+        ChunkParams {
+            k: 19,
+            w: 10,
+            chunk_len: 10_000,
+            chain: ChainParams::default(),
+            threads: 0,
+        }
+    }
+}
+
+/// Globally aligns two long, similar DNA sequences, such as two assemblies of a bacterial genome, by cutting them into
+/// chunks at shared k-mers and aligning the chunks independently.
+///
+/// Minimizers found exactly once in each sequence are anchors, and the best chain of them (see `chain_anchors`) is the
+/// backbone of the alignment. The sequences are cut at the start of chained anchors at least `chunk_len` query bases
+/// apart, each pair of chunks is aligned with `global_traceback` on its own thread, and the tracebacks are joined end
+/// to end. So the memory used is set by the chunks rather than by the whole sequences, and the result is the optimal
+/// alignment that passes through the cut points, which for similar sequences is nearly always the optimal one. A gap
+/// running across a cut is only charged one opening. Without any chain of anchors, the sequences are aligned whole.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let reference = b"TACGCCGGTACACTACGAGGCATAGGCCGCGGTCCTTACCAATGACCTTATGTGCAACTC\
+///                   TATCATTCCTCCCGGACGCCACCACCTTTGGCATACCGAGGTTGAGTGACAGGAAAGAGA";
+/// let mut query = reference.to_vec();
+/// query[30] = b'T';
+/// query[85] = b'A';
+///
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let params = ChunkParams { k: 7, w: 3, chunk_len: 40, ..ChunkParams::default() };
+/// let alignment = chunked_global_traceback(&query, reference, 3, 1, &matrix, &params).unwrap();
+///
+/// assert_eq!((query.len(), reference.len()), (alignment.query_end, alignment.ref_end));
+/// assert_eq!(query, alignment.query_trace.replace('-', "").into_bytes());
+/// assert!(alignment.identity() > 0.95);
+/// ```
+pub fn chunked_global_traceback(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+    params: &ChunkParams,
+) -> Result<TracebackResults, ParasailError> {
+    let cuts = cut_points(query_sequence, database_sequence, params);
+    let chunks: Vec<((usize, usize), (usize, usize))> = cuts.windows(2).map(|w| (w[0], w[1])).collect();
+
+    let pieces: Vec<Result<TracebackResults, ParasailError>> = parallel::map_chunks(&chunks, params.threads, |group| {
+        group
+            .iter()
+            .map(|&((q0, r0), (q1, r1))| {
+                let (query, reference) = (&query_sequence[q0..q1], &database_sequence[r0..r1]);
+                align_chunk(query, reference, open_cost, gap_extend_cost, substitution_matrix)
+            })
+            .collect()
+    });
+
+    let pieces = pieces.into_iter().collect::<Result<Vec<_>, _>>()?;
+    Ok(stitch(&pieces, query_sequence.len(), database_sequence.len(), open_cost, gap_extend_cost))
+}
+
+/// The points `(query, reference)` the sequences are cut at, from `(0, 0)` to the ends of both, in increasing order.
+fn cut_points(query: &[u8], reference: &[u8], params: &ChunkParams) -> Vec<(usize, usize)> {
+    // minimizer hashes are invertible, so a hash seen once in each sequence is a k-mer found once in each
+    let unique = |sequence: &[u8]| {
+        let mut positions: HashMap<u64, Option<usize>> = HashMap::new();
+        for m in minimizers(sequence, params.k, params.w) {
+            positions.entry(m.hash).and_modify(|pos| *pos = None).or_insert(Some(m.pos));
+        }
+        positions
+    };
+    let in_reference = unique(reference);
+    let anchors: Vec<Anchor> = unique(query)
+        .into_iter()
+        .filter_map(|(hash, pos)| Some((pos?, in_reference.get(&hash).cloned()??)))
+        .map(|(query_begin, ref_begin)| Anchor {
+            score: params.k as i64,
+            query_begin,
+            query_end: query_begin + params.k,
+            ref_begin,
+            ref_end: ref_begin + params.k,
+        })
+        .collect();
+
+    let mut cuts = vec![(0, 0)];
+    if let Some(chain) = chain_anchors(&anchors, &params.chain) {
+        for anchor in chain.anchors {
+            let &(query_cut, ref_cut) = cuts.last().unwrap();
+            if anchor.query_begin >= query_cut + params.chunk_len.max(1) && anchor.ref_begin > ref_cut {
+                cuts.push((anchor.query_begin, anchor.ref_begin));
+            }
+        }
+    }
+    cuts.push((query.len(), reference.len()));
+    cuts
+}
+
+/// Globally aligns one pair of chunks. parasail needs something to align on both sides, so a chunk which is empty in
+/// one sequence is written out as a single gap.
+fn align_chunk(
+    query: &[u8],
+    reference: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<TracebackResults, ParasailError> {
+    if !query.is_empty() && !reference.is_empty() {
+        return global_traceback(query, reference, open_cost, gap_extend_cost, substitution_matrix);
+    }
+
+    let len = query.len() + reference.len();
+    let gaps = "-".repeat(len);
+    let text = |sequence: &[u8]| String::from_utf8_lossy(sequence).into_owned();
+    let (query_trace, ref_trace) = if query.is_empty() {
+        (gaps, text(reference))
+    } else {
+        (text(query), gaps)
+    };
+    Ok(TracebackResults {
+        score: if len == 0 { 0 } else { -(open_cost as i64 + gap_extend_cost as i64 * (len as i64 - 1)) },
+        query_end: query.len(),
+        ref_end: reference.len(),
+        query_trace,
+        comp_trace: " ".repeat(len),
+        ref_trace,
+        query_prefix_clip: 0,
+        query_suffix_clip: 0,
+    })
+}
+
+/// Joins the chunks' tracebacks end to end into one alignment of the whole sequences.
+fn stitch(
+    pieces: &[TracebackResults],
+    query_len: usize,
+    ref_len: usize,
+    open_cost: i32,
+    gap_extend_cost: i32,
+) -> TracebackResults {
+    let mut score = 0;
+    let (mut query_trace, mut comp_trace, mut ref_trace) = (String::new(), String::new(), String::new());
+    for piece in pieces {
+        // a gap carried on from the previous chunk was charged an opening there, so it only extends here
+        let continues = |trace: &str, next: &str| trace.ends_with('-') && next.starts_with('-');
+        if continues(&query_trace, &piece.query_trace) || continues(&ref_trace, &piece.ref_trace) {
+            score += (open_cost - gap_extend_cost) as i64;
+        }
+        score += piece.score;
+        query_trace.push_str(&piece.query_trace);
+        comp_trace.push_str(&piece.comp_trace);
+        ref_trace.push_str(&piece.ref_trace);
+    }
+
+    let (query_prefix_clip, query_suffix_clip) = soft_clips(&query_trace, &ref_trace, query_len, query_len);
+    TracebackResults {
+        score,
+        query_end: query_len,
+        ref_end: ref_len,
+        query_trace,
+        comp_trace,
+        ref_trace,
+        query_prefix_clip,
+        query_suffix_clip,
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::collections::HashMap;
+
+use crate::align::{ratio, semi_global_alignment_stats_profile};
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::parallel;
+use crate::profile::StatsProfile;
+
+/// Controls how `cluster_by_identity` groups sequences.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterParams {
+    /// A sequence joins a cluster if this fraction of its residues match the cluster's representative.
+    pub min_identity: f64,
+    /// The k-mer length of the prefilter.
+    pub k: usize,
+    /// The gap open cost used when aligning.
+    pub open_cost: i32,
+    /// The gap extend cost used when aligning.
+    pub gap_extend_cost: i32,
+    /// The number of threads to align on, or 0 for the available parallelism.
+    pub threads: usize,
+}
+
+impl Default for ClusterParams {
+    fn default() -> Self {
+        ClusterParams {
+            min_identity: 0.97,
+            k: 8,
+            open_cost: 2,
+            gap_extend_cost: 1,
+            threads: 0,
+        }
+    }
+}
+
+/// A group of sequences close enough to a representative sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    /// The index of the sequence representing the cluster, which is always its longest member.
+    pub representative: usize,
+    /// The indices of all of the cluster's sequences, starting with the representative and then in the order they
+    /// were assigned.
+    pub members: Vec<usize>,
+}
+
+/// Greedily clusters sequences by identity, in the style of CD-HIT.
+///
+/// Sequences are visited from longest to shortest (ties in input order). Each one is aligned semi-globally against
+/// every existing representative, and joins the cluster of the representative it matches best if the fraction of its
+/// residues that are exact matches reaches `min_identity`. Otherwise it becomes the representative of a new cluster.
+/// Representatives sharing too few k-mers with a sequence are skipped without aligning. This prefilter is a heuristic:
+/// it allows for every residue short of `min_identity` breaking `k` k-mers, but deletions from the sequence break
+/// k-mers too without costing it any identity, so a representative matched only through many deletions can be
+/// missed. Each sequence's profile is built once per thread and reused against all of the representatives the
+/// thread checks. Clusters are returned in the order they were created, or the first error parasail reports.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let sequences: Vec<&[u8]> = vec![
+///     b"ACGTAGCTAGCTAGGATCGATCGATTAGC",
+///     b"TTGACCGTAGGCATTCAGGCATGCAATCG",
+///     b"ACGTAGCTAGCTAGGATCGATCGATTAGCA",
+///     b"ACGTAGCTAGCTAGGATCGTTCGATTAGC",
+/// ];
+/// let params = ClusterParams { min_identity: 0.95, k: 5, ..ClusterParams::default() };
+///
+/// let clusters = cluster_by_identity(&sequences, &matrix, &params).unwrap();
+/// assert_eq!(2, clusters.len());
+/// assert_eq!(vec![2, 0, 3], clusters[0].members);
+/// assert_eq!(vec![1], clusters[1].members);
+/// ```
+pub fn cluster_by_identity(
+    sequences: &[&[u8]],
+    matrix: &Matrix,
+    params: &ClusterParams,
+) -> Result<Vec<Cluster>, ParasailError> {
+    let k = params.k.max(1);
+    let threads = parallel::thread_count(params.threads);
+
+    let mut order: Vec<usize> = (0..sequences.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sequences[i].len()));
+
+    let mut clusters: Vec<Cluster> = Vec::new();
+    let mut representative_kmers: Vec<HashMap<&[u8], usize>> = Vec::new();
+
+    for i in order {
+        let query = sequences[i];
+        let kmers = kmer_counts(query, k);
+
+        // each residue short of the threshold can break k k-mers; deletions aren't counted, see above
+        let mismatches = ((1.0 - params.min_identity) * query.len() as f64).ceil() as usize;
+        let required = (query.len() + 1).saturating_sub(k).saturating_sub(mismatches * k);
+        let candidates: Vec<usize> = representative_kmers
+            .iter()
+            .enumerate()
+            .filter(|(_, rep_kmers)| shared_kmers(&kmers, rep_kmers) >= required)
+            .map(|(c, _)| c)
+            .collect();
+
+        match best_representative(query, &candidates, &clusters, sequences, matrix, params, threads)? {
+            Some((c, identity)) if identity >= params.min_identity => clusters[c].members.push(i),
+            _ => {
+                clusters.push(Cluster {
+                    representative: i,
+                    members: vec![i],
+                });
+                representative_kmers.push(kmers);
+            }
+        }
+    }
+
+    Ok(clusters)
+}
+
+/// Aligns a sequence against the candidate clusters' representatives, returning the cluster with the highest identity
+/// (the earliest one on ties) along with the identity. An empty sequence matches nothing.
+fn best_representative(
+    query: &[u8],
+    candidates: &[usize],
+    clusters: &[Cluster],
+    sequences: &[&[u8]],
+    matrix: &Matrix,
+    params: &ClusterParams,
+    threads: usize,
+) -> Result<Option<(usize, f64)>, ParasailError> {
+    if query.is_empty() {
+        return Ok(None);
+    }
+
+    let identities: Vec<Result<f64, ParasailError>> = parallel::map_chunks(candidates, threads, |chunk| {
+        let profile = match StatsProfile::new(query, matrix) {
+            Ok(profile) => profile,
+            Err(err) => return vec![Err(err)],
+        };
+        chunk
+            .iter()
+            .map(|&c| {
+                let reference = sequences[clusters[c].representative];
+                identity(&profile, reference, params.open_cost, params.gap_extend_cost)
+            })
+            .collect()
+    });
+
+    let mut best: Option<(usize, f64)> = None;
+    for (&c, identity) in candidates.iter().zip(identities) {
+        let identity = identity?;
+        match best {
+            Some((_, current)) if current >= identity => {}
+            _ => best = Some((c, identity)),
+        }
+    }
+    Ok(best)
+}
+
+/// The fraction of the query's residues matched exactly in a semi-global alignment against the reference.
+fn identity(
+    query_profile: &StatsProfile,
+    reference: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+) -> Result<f64, ParasailError> {
+    let stats = semi_global_alignment_stats_profile(query_profile, reference, open_cost, gap_extend_cost)?;
+    Ok(ratio(stats.num_matches as usize, stats.query_len))
+}
+
+fn kmer_counts(sequence: &[u8], k: usize) -> HashMap<&[u8], usize> {
+    let mut counts = HashMap::new();
+    for kmer in sequence.windows(k) {
+        *counts.entry(kmer).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// The number of k-mer occurrences the two sequences have in common, counting repeated k-mers as many times as they
+/// appear in both.
+fn shared_kmers(a: &HashMap<&[u8], usize>, b: &HashMap<&[u8], usize>) -> usize {
+    a.iter().map(|(kmer, &count)| count.min(b.get(kmer).copied().unwrap_or(0))).sum()
+}
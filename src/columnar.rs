@@ -0,0 +1,61 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Float64Array, Int64Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::errors::ParquetError;
+
+use crate::table::{ColumnValues, Tabular};
+
+/// Converts a batch of results into an Arrow `RecordBatch`, with a non-nullable column per field as laid out by
+/// `Tabular::columns`: `Int64` for signed values, `UInt64` for coordinates and counts, and `Float64` for fractions.
+/// Requires the `arrow` feature.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let hits = vec![ReferenceHit { score: 8, index: 2, mapq: 15 }];
+///
+/// let batch = to_record_batch(&hits).unwrap();
+/// assert_eq!(1, batch.num_rows());
+/// assert_eq!("mapq", batch.schema().field(2).name());
+/// ```
+pub fn to_record_batch<T: Tabular>(rows: &[T]) -> Result<RecordBatch, ArrowError> {
+    let columns = T::columns(rows);
+
+    let mut fields = Vec::with_capacity(columns.len());
+    let mut arrays: Vec<ArrayRef> = Vec::with_capacity(columns.len());
+    for column in columns {
+        let (data_type, array): (DataType, ArrayRef) = match column.values {
+            ColumnValues::Int(values) => (DataType::Int64, Arc::new(Int64Array::from(values))),
+            ColumnValues::UInt(values) => (DataType::UInt64, Arc::new(UInt64Array::from(values))),
+            ColumnValues::Float(values) => (DataType::Float64, Arc::new(Float64Array::from(values))),
+        };
+        fields.push(Field::new(column.name, data_type, false));
+        arrays.push(array);
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+}
+
+/// Writes a batch of results to `writer` as a Parquet file with a single row group, using the columns of
+/// `to_record_batch`. Requires the `arrow` feature.
+///
+/// To write several batches of the same result type into one file, convert each with `to_record_batch` and feed them
+/// to a `parquet::arrow::ArrowWriter` directly.
+pub fn write_parquet<T: Tabular, W: Write + Send>(rows: &[T], writer: W) -> Result<(), ParquetError> {
+    let batch = to_record_batch(rows)?;
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
@@ -0,0 +1,144 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::align::TracebackResults;
+
+/// A consensus sequence called from a stack of alignments, with per-base support.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Consensus {
+    /// The consensus sequence. Reference positions no read covers are reported as `N`.
+    pub sequence: Vec<u8>,
+    /// For each base of the consensus, the number of reads agreeing with it.
+    pub support: Vec<usize>,
+    /// For each base of the consensus, the number of reads covering its position.
+    pub depth: Vec<usize>,
+}
+
+/// Calls a plurality consensus over `reference[ref_begin..ref_end]` from reads aligned against that reference.
+///
+/// Each alignment is expected to come from one of the traceback functions, with the read as the query and the
+/// reference as the database sequence, so that `ref_end` locates the trace in the reference. Leading and trailing
+/// columns where a read has no bases (free end gaps) don't count as coverage. At each reference position the most
+/// common read base wins even without a majority, and the position is dropped from the consensus if a deletion is
+/// the most common entry. Insertions are stricter: one between two reference positions is kept only if more than
+/// half of the reads covering the position before it carry the same inserted bases.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let reference = b"AAAACCCCGGGGTTTT";
+/// let reads: Vec<&[u8]> = vec![b"AAAACCACGGGGTTTT", b"AAAACCACGGGGTTTT", b"AAAACCCCGGGGTTTT"];
+///
+/// let alignments: Vec<TracebackResults> = reads
+///     .iter()
+///     .map(|read| semi_global_traceback(read, reference, 1, 1, &matrix).unwrap())
+///     .collect();
+///
+/// let consensus = call_consensus(&alignments, 0, reference.len());
+/// assert_eq!(b"AAAACCACGGGGTTTT".to_vec(), consensus.sequence);
+/// assert_eq!(2, consensus.support[6]);
+/// assert_eq!(3, consensus.depth[6]);
+/// ```
+pub fn call_consensus(alignments: &[TracebackResults], ref_begin: usize, ref_end: usize) -> Consensus {
+    let len = ref_end.saturating_sub(ref_begin);
+
+    // read bases (or '-' for a deletion) observed at each reference position
+    let mut columns: Vec<Vec<(u8, usize)>> = vec![Vec::new(); len];
+    // bases inserted between each reference position and the next
+    let mut insertions: Vec<Vec<(Vec<u8>, usize)>> = vec![Vec::new(); len];
+    let mut depth = vec![0; len];
+
+    for alignment in alignments {
+        let query = alignment.query_trace.as_bytes();
+        let reference = alignment.ref_trace.as_bytes();
+
+        let first = match query.iter().position(|&c| c != b'-') {
+            Some(first) => first,
+            None => continue,
+        };
+        let last = query.iter().rposition(|&c| c != b'-').unwrap_or(first);
+
+        let ref_residues = reference.iter().filter(|&&c| c != b'-').count();
+        let skipped = reference[..first].iter().filter(|&&c| c != b'-').count();
+        let mut pos = alignment.ref_end - ref_residues + skipped;
+
+        let mut inserted = Vec::new();
+        let mut covered_any = false;
+        for k in first..=last {
+            if reference[k] == b'-' {
+                inserted.push(query[k]);
+                continue;
+            }
+
+            // an insertion only counts once the read has covered the reference base before it
+            if covered_any && !inserted.is_empty() && pos > ref_begin && pos - 1 < ref_end {
+                tally(&mut insertions[pos - 1 - ref_begin], inserted.clone());
+            }
+            inserted.clear();
+
+            if pos >= ref_begin && pos < ref_end {
+                tally(&mut columns[pos - ref_begin], query[k]);
+                depth[pos - ref_begin] += 1;
+            }
+            covered_any = true;
+            pos += 1;
+        }
+    }
+
+    let mut consensus = Consensus {
+        sequence: Vec::with_capacity(len),
+        support: Vec::with_capacity(len),
+        depth: Vec::with_capacity(len),
+    };
+
+    for i in 0..len {
+        match plurality(&columns[i]) {
+            None => {
+                consensus.sequence.push(b'N');
+                consensus.support.push(0);
+                consensus.depth.push(0);
+            }
+            Some((b'-', _)) => {}
+            Some((base, count)) => {
+                consensus.sequence.push(base);
+                consensus.support.push(count);
+                consensus.depth.push(depth[i]);
+            }
+        }
+
+        if let Some((bases, count)) = plurality(&insertions[i]) {
+            if count * 2 > depth[i] {
+                for base in bases {
+                    consensus.sequence.push(base);
+                    consensus.support.push(count);
+                    consensus.depth.push(depth[i]);
+                }
+            }
+        }
+    }
+
+    consensus
+}
+
+fn tally<T: PartialEq>(counts: &mut Vec<(T, usize)>, value: T) {
+    match counts.iter_mut().find(|(v, _)| *v == value) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((value, 1)),
+    }
+}
+
+/// Picks the most common value, breaking ties in favor of whichever was seen first.
+fn plurality<T: Clone>(counts: &[(T, usize)]) -> Option<(T, usize)> {
+    let mut best: Option<&(T, usize)> = None;
+    for entry in counts {
+        match best {
+            Some(current) if current.1 >= entry.1 => {}
+            _ => best = Some(entry),
+        }
+    }
+    best.cloned()
+}
@@ -0,0 +1,48 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use polars::prelude::{DataFrame, NamedFrom, PolarsResult, Series};
+
+use crate::table::{ColumnValues, Tabular};
+
+/// Collections of results which can be turned into a Polars `DataFrame`. Requires the `polars` feature.
+///
+/// Implemented for slices (and so vectors) of every `Tabular` result type, with the same columns as
+/// `Tabular::columns`: `i64` for signed values, `u64` for coordinates and counts, and `f64` for fractions.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let hits = vec![
+///     ReferenceHit { score: 8, index: 2, mapq: 15 },
+///     ReferenceHit { score: 6, index: 0, mapq: 0 },
+/// ];
+///
+/// let df = hits.to_dataframe().unwrap();
+/// assert_eq!((2, 3), df.shape());
+/// ```
+pub trait ToDataFrame {
+    /// Lays the results out as a data frame with a row per result.
+    fn to_dataframe(&self) -> PolarsResult<DataFrame>;
+}
+
+impl<T: Tabular> ToDataFrame for [T] {
+    fn to_dataframe(&self) -> PolarsResult<DataFrame> {
+        let columns = T::columns(self)
+            .into_iter()
+            .map(|column| {
+                let name = column.name.into();
+                match column.values {
+                    ColumnValues::Int(values) => Series::new(name, values),
+                    ColumnValues::UInt(values) => Series::new(name, values),
+                    ColumnValues::Float(values) => Series::new(name, values),
+                }
+                .into()
+            })
+            .collect();
+        DataFrame::new(columns)
+    }
+}
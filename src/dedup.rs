@@ -0,0 +1,132 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::collections::HashMap;
+
+use crate::distance::edit_distance_bounded;
+use crate::minimizer::minimizers;
+
+/// Controls how `dedup_reads` decides which reads are duplicates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupParams {
+    /// Reads within this many edits (substitutions, insertions, and deletions) of a representative are collapsed
+    /// into it.
+    pub max_edits: usize,
+    /// The k-mer length of the minimizer sketch used to bucket reads, between 1 and 32.
+    pub k: usize,
+    /// The minimizer window, in k-mers.
+    pub w: usize,
+}
+
+impl Default for DedupParams {
+    fn default() -> Self {
+        DedupParams {
+            max_edits: 1,
+            k: 12,
+            w: 8,
+        }
+    }
+}
+
+/// A representative read and the reads collapsed into it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// The index of the read representing the group.
+    pub representative: usize,
+    /// The indices of every read in the group, starting with the representative.
+    pub members: Vec<usize>,
+}
+
+impl DuplicateGroup {
+    /// The number of reads in the group, including the representative.
+    pub fn count(&self) -> usize {
+        self.members.len()
+    }
+}
+
+/// Collapses reads which are within `max_edits` edits of one another, returning each group's representative and
+/// members.
+///
+/// Identical reads are merged first, and distinct sequences are then visited from most to least abundant (ties in
+/// input order), so representatives are the most common version of each read. A sequence joins the first existing
+/// representative it's close enough to, as checked by `edit_distance_bounded`, and otherwise starts a new group. Only
+/// representatives sharing a minimizer with the sequence are checked, which keeps the work close to linear but means
+/// that reads so short or so damaged that they share no minimizer are never collapsed (reads with no minimizers at
+/// all are compared against each other directly). Groups are returned in the order they were created, i.e. by
+/// decreasing abundance of their representative.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let reads: Vec<&[u8]> = vec![
+///     b"ACGTTGCAAGGCTTACGGATCCATGCAGTCAAGTCGGATC",
+///     b"TTGACCAGTAGGCATTCAGGCATGCCGATACGTAGCTTAG",
+///     b"ACGTTGCAAGGCTTACGGATCCATGCAGTCAAGTCGGATC",
+///     b"ACGTTGCAAGGCTTACGGATGCATGCAGTCAAGTCGGATC",
+/// ];
+///
+/// let groups = dedup_reads(&reads, &DedupParams::default());
+/// assert_eq!(2, groups.len());
+/// assert_eq!(vec![0, 2, 3], groups[0].members);
+/// assert_eq!(1, groups[1].count());
+/// ```
+pub fn dedup_reads(reads: &[&[u8]], params: &DedupParams) -> Vec<DuplicateGroup> {
+    // exact duplicates, in order of first appearance
+    let mut distinct: Vec<Vec<usize>> = Vec::new();
+    let mut seen: HashMap<&[u8], usize> = HashMap::new();
+    for (i, &read) in reads.iter().enumerate() {
+        let slot = *seen.entry(read).or_insert_with(|| {
+            distinct.push(Vec::new());
+            distinct.len() - 1
+        });
+        distinct[slot].push(i);
+    }
+    // a stable sort keeps ties in input order
+    distinct.sort_by_key(|copies| std::cmp::Reverse(copies.len()));
+
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut unsketched: Vec<usize> = Vec::new();
+
+    for copies in distinct {
+        let read = reads[copies[0]];
+        let sketch: Vec<u64> = minimizers(read, params.k, params.w).iter().map(|m| m.hash).collect();
+
+        let mut candidates: Vec<usize> = if sketch.is_empty() {
+            unsketched.clone()
+        } else {
+            sketch.iter().filter_map(|hash| buckets.get(hash)).flatten().copied().collect()
+        };
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let found = candidates.into_iter().find(|&g| {
+            edit_distance_bounded(read, reads[groups[g].representative], params.max_edits).is_some()
+        });
+
+        match found {
+            Some(g) => groups[g].members.extend(copies),
+            None => {
+                let g = groups.len();
+                if sketch.is_empty() {
+                    unsketched.push(g);
+                }
+                for hash in sketch {
+                    let bucket = buckets.entry(hash).or_default();
+                    if bucket.last() != Some(&g) {
+                        bucket.push(g);
+                    }
+                }
+                groups.push(DuplicateGroup {
+                    representative: copies[0],
+                    members: copies,
+                });
+            }
+        }
+    }
+
+    groups
+}
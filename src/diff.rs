@@ -0,0 +1,138 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use crate::align::TracebackResults;
+use crate::ops::{ops_from_traces, EditOp};
+
+/// How many columns of each kind an alignment has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct OpCounts {
+    /// Columns where both sequences have the same character.
+    pub matches: usize,
+    /// Columns where the sequences have different characters.
+    pub substitutions: usize,
+    /// Query characters facing a gap.
+    pub insertions: usize,
+    /// Reference characters facing a gap.
+    pub deletions: usize,
+}
+
+impl OpCounts {
+    fn of(alignment: &TracebackResults) -> Self {
+        let mut counts = OpCounts::default();
+        for op in ops_from_traces(&alignment.query_trace, &alignment.ref_trace) {
+            match op.op {
+                EditOp::Match => counts.matches += op.len,
+                EditOp::Subst => counts.substitutions += op.len,
+                EditOp::Ins => counts.insertions += op.len,
+                EditOp::Del => counts.deletions += op.len,
+            }
+        }
+        counts
+    }
+}
+
+/// The differences between two alignments of the same query and reference, as made by `diff_alignments`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignmentDiff {
+    /// The second alignment's score minus the first's. Only meaningful if both were scored the same way.
+    pub score_delta: i64,
+    /// How many query positions both alignments cover.
+    pub query_overlap: usize,
+    /// How many reference positions both alignments cover.
+    pub ref_overlap: usize,
+    /// The columns of each kind in the first alignment, end gaps included.
+    pub first_ops: OpCounts,
+    /// The columns of each kind in the second alignment, end gaps included.
+    pub second_ops: OpCounts,
+    /// How many query characters both alignments pair with the same reference character.
+    pub shared_pairs: usize,
+    /// The stretches of the query which the two alignments place differently: every query position in them is paired
+    /// with a different reference position, or with a gap in one alignment and not the other. In query order.
+    pub discordant: Vec<Range<usize>>,
+}
+
+impl AlignmentDiff {
+    /// Whether the alignments pair every query character the same way.
+    pub fn is_concordant(&self) -> bool {
+        self.discordant.is_empty()
+    }
+
+    /// The fraction of query characters paired with a reference character in either alignment that both pair the same
+    /// way, or 1 if neither pairs anything.
+    pub fn pair_agreement(&self, first: &TracebackResults, second: &TracebackResults) -> f64 {
+        let paired = |alignment: &TracebackResults| {
+            alignment.aligned_pairs().filter(|pair| pair.0.is_some() && pair.1.is_some()).count()
+        };
+        let union = paired(first) + paired(second) - self.shared_pairs;
+        if union == 0 {
+            1.0
+        } else {
+            self.shared_pairs as f64 / union as f64
+        }
+    }
+}
+
+/// Compares two alignments of the same query against the same reference, e.g. from different gap costs in a grid
+/// search or from different backends: how their scores differ, how much of each sequence they both cover, how their
+/// columns break down, and where in the query they disagree.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let query = b"ACGTACGTTTTACGTACGT";
+/// let reference = b"ACGTACGTACGTACGT";
+///
+/// let cheap_gaps = semi_global_traceback(query, reference, 1, 1, &matrix).unwrap();
+/// let dear_gaps = semi_global_traceback(query, reference, 10, 1, &matrix).unwrap();
+/// let diff = diff_alignments(&cheap_gaps, &dear_gaps);
+///
+/// assert_eq!(dear_gaps.score - cheap_gaps.score, diff.score_delta);
+/// assert!(diff.first_ops.insertions >= 3);
+/// ```
+pub fn diff_alignments(first: &TracebackResults, second: &TracebackResults) -> AlignmentDiff {
+    let overlap = |a: Range<usize>, b: Range<usize>| a.end.min(b.end).saturating_sub(a.start.max(b.start));
+
+    // what each alignment pairs each query position with, `None` for a gap
+    let placements = |alignment: &TracebackResults| -> HashMap<usize, Option<usize>> {
+        alignment.aligned_pairs().filter_map(|(q, r)| q.map(|q| (q, r))).collect()
+    };
+    let (first_placed, second_placed) = (placements(first), placements(second));
+
+    let shared_pairs = first_placed
+        .iter()
+        .filter(|&(q, r)| r.is_some() && second_placed.get(q) == Some(r))
+        .count();
+
+    let covered: HashSet<usize> = first_placed.keys().chain(second_placed.keys()).copied().collect();
+    let mut differing: Vec<usize> = covered
+        .into_iter()
+        .filter(|q| first_placed.get(q) != second_placed.get(q))
+        .collect();
+    differing.sort_unstable();
+
+    let mut discordant: Vec<Range<usize>> = Vec::new();
+    for q in differing {
+        match discordant.last_mut() {
+            Some(range) if range.end == q => range.end += 1,
+            _ => discordant.push(q..q + 1),
+        }
+    }
+
+    AlignmentDiff {
+        score_delta: second.score - first.score,
+        query_overlap: overlap(first.query_begin()..first.query_end, second.query_begin()..second.query_end),
+        ref_overlap: overlap(first.ref_begin()..first.ref_end, second.ref_begin()..second.ref_end),
+        first_ops: OpCounts::of(first),
+        second_ops: OpCounts::of(second),
+        shared_pairs,
+        discordant,
+    }
+}
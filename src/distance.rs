@@ -0,0 +1,171 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::error::AlignError;
+use crate::myers;
+
+/// Counts the positions at which two equal-length sequences differ. Bytes are compared exactly, so case matters.
+///
+/// This is a single pass with no dynamic programming, which is all that's needed for substitution-only comparisons
+/// such as barcodes. Returns an error if the sequences have different lengths.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// assert_eq!(Ok(2), hamming_distance(b"ACGTACGT", b"ACCTACGA"));
+/// assert!(hamming_distance(b"ACGT", b"ACG").is_err());
+/// ```
+pub fn hamming_distance(a: &[u8], b: &[u8]) -> Result<usize, AlignError> {
+    if a.len() != b.len() {
+        return Err(AlignError::LengthMismatch {
+            left: a.len(),
+            right: b.len(),
+        });
+    }
+
+    // kept branch-free so the compiler can vectorize it
+    Ok(a.iter().zip(b).map(|(x, y)| (x != y) as usize).sum())
+}
+
+/// The Levenshtein distance between two sequences: the fewest substitutions, insertions, and deletions turning one
+/// into the other. Bytes are compared exactly, so case matters.
+///
+/// The distance is computed with Myers' bit-parallel algorithm, which fills 64 rows of the dynamic programming matrix
+/// with a few word operations, so no substitution matrix or parasail call is involved.
+///
+/// With `hamming_fast_path` set, equal-length sequences are first compared position by position, and the full
+/// dynamic programming is skipped when they differ in at most two places. That answer is exact: a single edit between
+/// equal-length sequences can only be a substitution, so two mismatches can't be explained by fewer than two edits.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// assert_eq!(3, edit_distance(b"kitten", b"sitting", false));
+/// assert_eq!(1, edit_distance(b"ACGTACGT", b"ACGAACGT", true));
+/// ```
+pub fn edit_distance(a: &[u8], b: &[u8], hamming_fast_path: bool) -> usize {
+    if hamming_fast_path {
+        if let Ok(mismatches) = hamming_distance(a, b) {
+            if mismatches <= 2 {
+                return mismatches;
+            }
+        }
+    }
+
+    myers::distance(a, b, None).unwrap()
+}
+
+/// The Levenshtein distance between two sequences if it's at most `k`, or `None` if it's larger.
+///
+/// Pairs whose lengths differ by more than `k` are rejected without aligning. Otherwise this runs the same
+/// bit-parallel computation as `edit_distance`, but only over the 64-row blocks covering the diagonal band of width
+/// `2k + 1` which can hold an alignment within `k` edits, so for small `k` it costs a few word operations per
+/// character of the longer sequence. It gives up as soon as every cell of the band exceeds `k`, so pairs which are
+/// very different are rejected early.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// assert_eq!(Some(3), edit_distance_bounded(b"kitten", b"sitting", 3));
+/// assert_eq!(None, edit_distance_bounded(b"kitten", b"sitting", 2));
+/// ```
+pub fn edit_distance_bounded(a: &[u8], b: &[u8], k: usize) -> Option<usize> {
+    let length_difference = if a.len() > b.len() { a.len() - b.len() } else { b.len() - a.len() };
+    if length_difference > k {
+        return None;
+    }
+
+    myers::distance(a, b, Some(k))
+}
+
+/// A stretch of text where a pattern occurs with a few edits, from `approximate_occurrences`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Occurrence {
+    /// The index (0-based) in the text where the occurrence begins.
+    pub start: usize,
+    /// The index (0-based, exclusive) in the text where the occurrence ends.
+    pub end: usize,
+    /// The Levenshtein distance between the pattern and `text[start..end]`.
+    pub edits: usize,
+}
+
+/// Finds where a pattern occurs in a text with at most `k` edits (substitutions, insertions, and deletions), as when
+/// scanning for a motif or a restriction site.
+///
+/// The text is scanned once with Myers' bit-parallel algorithm in search mode, which gives the fewest edits of any
+/// occurrence ending at each position. Consecutive positions within `k` edits are the same occurrence seen with a base
+/// more or less at its end, so each such run is reported once, at the position with the fewest edits (the first, on a
+/// tie). The start is then found by aligning the pattern against the stretch of text just before that end, preferring
+/// the shortest occurrence among those with the fewest edits. Occurrences are returned in text order. An empty pattern
+/// occurs nowhere.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let occurrences = approximate_occurrences(b"GAATTC", b"CCGAATTCAAAAGATTCAAAAGCCTTCA", 1);
+/// assert_eq!(2, occurrences.len());
+/// assert_eq!(Occurrence { start: 2, end: 8, edits: 0 }, occurrences[0]);
+/// assert_eq!(Occurrence { start: 12, end: 17, edits: 1 }, occurrences[1]);
+/// ```
+pub fn approximate_occurrences(pattern: &[u8], text: &[u8], k: usize) -> Vec<Occurrence> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+
+    let ends = myers::end_distances(pattern, text);
+    let mut occurrences = Vec::new();
+    let mut j = 0;
+    while j < ends.len() {
+        if ends[j] > k {
+            j += 1;
+            continue;
+        }
+
+        let mut best = j;
+        while j < ends.len() && ends[j] <= k {
+            if ends[j] < ends[best] {
+                best = j;
+            }
+            j += 1;
+        }
+
+        let end = best + 1;
+        let window_start = end.saturating_sub(pattern.len() + k);
+        let (start, edits) = best_start(pattern, &text[window_start..end]);
+        occurrences.push(Occurrence {
+            start: window_start + start,
+            end,
+            edits,
+        });
+    }
+    occurrences
+}
+
+/// The start within `window` of the substring ending at its end that the pattern matches with the fewest edits, the
+/// latest one on a tie, and the number of edits.
+fn best_start(pattern: &[u8], window: &[u8]) -> (usize, usize) {
+    // (edits, start) for the previous pattern row, matching up to each window position; a match may start anywhere
+    let mut row: Vec<(usize, usize)> = (0..=window.len()).map(|j| (0, j)).collect();
+    let better = |a: (usize, usize), b: (usize, usize)| if (a.0, b.1) < (b.0, a.1) { a } else { b };
+
+    for (i, &p) in pattern.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = (i + 1, 0);
+        for (j, &t) in window.iter().enumerate() {
+            let substitution = (diagonal.0 + (p != t) as usize, diagonal.1);
+            diagonal = row[j + 1];
+            let deletion = (row[j + 1].0 + 1, row[j + 1].1);
+            let insertion = (row[j].0 + 1, row[j].1);
+            row[j + 1] = better(better(substitution, deletion), insertion);
+        }
+    }
+
+    let (edits, start) = row[window.len()];
+    (start, edits)
+}
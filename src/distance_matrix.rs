@@ -0,0 +1,185 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::io::{self, Write};
+
+use crate::align::{global_alignment_stats, ratio, self_alignment_score};
+use crate::error::{AlignError, ParasailError};
+use crate::matrix::Matrix;
+use crate::parallel;
+
+/// How a pairwise global alignment is turned into a distance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DistanceKind {
+    /// One minus the fraction of alignment columns which are exact matches.
+    Identity,
+    /// How far the alignment score falls short of the two sequences' self-alignment scores,
+    /// `(self_score(a) + self_score(b)) / 2 - score(a, b)`.
+    Score,
+}
+
+/// Whether to write every entry of a PHYLIP matrix or only those below the diagonal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhylipLayout {
+    /// The full square matrix.
+    Square,
+    /// The lower triangle, without the diagonal.
+    LowerTriangular,
+}
+
+/// Symmetric pairwise distances between named sequences.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceMatrix {
+    /// The names of the sequences, in row order.
+    pub names: Vec<String>,
+    // row-major, size names.len() squared
+    values: Vec<f64>,
+}
+
+impl DistanceMatrix {
+    /// Creates a matrix of zero distances between the named sequences.
+    pub fn new(names: Vec<String>) -> Self {
+        let n = names.len();
+        DistanceMatrix {
+            names,
+            values: vec![0.0; n * n],
+        }
+    }
+
+    /// The number of sequences.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether there are no sequences.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The distance between sequences `i` and `j`.
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        self.values[i * self.len() + j]
+    }
+
+    /// Sets the distance between sequences `i` and `j`, in both directions.
+    pub fn set(&mut self, i: usize, j: usize, distance: f64) {
+        let n = self.len();
+        self.values[i * n + j] = distance;
+        self.values[j * n + i] = distance;
+    }
+
+    /// Writes the matrix in PHYLIP format: the number of sequences on the first line, then a row per sequence
+    /// starting with its name, truncated or padded to the 10 characters strict PHYLIP expects.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let mut distances = DistanceMatrix::new(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+    /// distances.set(0, 1, 0.25);
+    /// distances.set(0, 2, 0.5);
+    /// distances.set(1, 2, 0.75);
+    ///
+    /// let mut out = Vec::new();
+    /// distances.write_phylip(&mut out, PhylipLayout::LowerTriangular).unwrap();
+    /// assert_eq!("3\na         \nb          0.250000\nc          0.500000 0.750000\n", String::from_utf8(out).unwrap());
+    /// ```
+    pub fn write_phylip<W: Write>(&self, mut out: W, layout: PhylipLayout) -> io::Result<()> {
+        writeln!(out, "{}", self.len())?;
+        for (i, name) in self.names.iter().enumerate() {
+            let name: String = name.chars().take(10).collect();
+            write!(out, "{:<10}", name)?;
+            let columns = match layout {
+                PhylipLayout::Square => self.len(),
+                PhylipLayout::LowerTriangular => i,
+            };
+            for j in 0..columns {
+                write!(out, " {:.6}", self.get(i, j))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the matrix as tab separated values, with a header row and a first column of names.
+    pub fn write_tsv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for name in &self.names {
+            write!(out, "\t{}", name)?;
+        }
+        writeln!(out)?;
+
+        for (i, name) in self.names.iter().enumerate() {
+            write!(out, "{}", name)?;
+            for j in 0..self.len() {
+                write!(out, "\t{}", self.get(i, j))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Globally aligns every pair of sequences and collects their distances, on several threads.
+///
+/// `threads` of 0 uses the available parallelism. The result doesn't depend on how threads are scheduled. Returns an
+/// error if there isn't exactly one name per sequence.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let names = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+/// let sequences: Vec<&[u8]> = vec![b"ACGTACGTAC", b"ACGTACGTAC", b"ACGAACGTTC"];
+///
+/// let distances = pairwise_distances(&sequences, names, DistanceKind::Identity, 1, 1, &matrix, 2).unwrap();
+/// assert_eq!(0.0, distances.get(0, 1));
+/// assert!((distances.get(0, 2) - 0.2).abs() < 1e-9);
+///
+/// // every sequence needs a name
+/// assert!(pairwise_distances(&sequences, vec!["a".to_string()], DistanceKind::Identity, 1, 1, &matrix, 2).is_err());
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn pairwise_distances(
+    sequences: &[&[u8]],
+    names: Vec<String>,
+    kind: DistanceKind,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    matrix: &Matrix,
+    threads: usize,
+) -> Result<DistanceMatrix, ParasailError> {
+    if names.len() != sequences.len() {
+        return Err(AlignError::LengthMismatch {
+            left: sequences.len(),
+            right: names.len(),
+        }
+        .into());
+    }
+
+    let self_scores: Vec<i64> = sequences.iter().map(|s| self_alignment_score(s, matrix)).collect();
+    let pairs: Vec<(usize, usize)> = (0..sequences.len())
+        .flat_map(|i| (i + 1..sequences.len()).map(move |j| (i, j)))
+        .collect();
+
+    let distances: Vec<Result<f64, ParasailError>> = parallel::map_chunks(&pairs, threads, |chunk| {
+        chunk
+            .iter()
+            .map(|&(i, j)| {
+                let stats = global_alignment_stats(sequences[i], sequences[j], open_cost, gap_extend_cost, matrix)?;
+                Ok(match kind {
+                    DistanceKind::Identity => 1.0 - ratio(stats.num_matches as usize, stats.align_length),
+                    DistanceKind::Score => (self_scores[i] + self_scores[j]) as f64 / 2.0 - stats.score as f64,
+                })
+            })
+            .collect()
+    });
+
+    let mut matrix = DistanceMatrix::new(names);
+    for (&(i, j), distance) in pairs.iter().zip(distances) {
+        matrix.set(i, j, distance?);
+    }
+    Ok(matrix)
+}
@@ -0,0 +1,228 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::slice;
+
+use libc::c_int;
+use parasail_sys::{
+    parasail_nw_stats_table_striped_sat, parasail_result_get_length_table, parasail_result_get_matches_table,
+    parasail_result_get_score_table, parasail_result_get_similar_table, parasail_result_is_stats_table,
+    parasail_result_is_table, parasail_sg_dx_table_striped_sat, parasail_sg_stats_table_striped_sat,
+    parasail_sw_stats_table_striped_sat,
+};
+
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::result::ParasailResult;
+
+/// One of the dynamic programming tables of an alignment, with a row per query character and a column per reference
+/// character.
+///
+/// Cell `(i, j)` holds the value for the best alignment ending at query character `i` and reference character `j`,
+/// with parasail's usual conventions: for the stats tables, that's the alignment the score table's cell was reached
+/// by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DpTable {
+    rows: usize,
+    cols: usize,
+    cells: Vec<i32>,
+}
+
+impl DpTable {
+    /// The number of rows, the length of the query.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The number of columns, the length of the reference.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The value at query position `i` and reference position `j`, or `None` outside the table.
+    pub fn get(&self, i: usize, j: usize) -> Option<i32> {
+        if i < self.rows && j < self.cols {
+            Some(self.cells[i * self.cols + j])
+        } else {
+            None
+        }
+    }
+
+    /// The values for query position `i` along the whole reference.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is past the end of the query.
+    pub fn row(&self, i: usize) -> &[i32] {
+        &self.cells[i * self.cols..(i + 1) * self.cols]
+    }
+
+    /// Every value, row after row.
+    pub fn as_slice(&self) -> &[i32] {
+        &self.cells
+    }
+}
+
+/// The score, match, similarity, and length tables of an alignment, from one of parasail's `*_stats_table` kernels.
+///
+/// Comparing the match or length table with the score table shows where along the dynamic programming matrix the
+/// matches of the best paths concentrate. Each table takes `4 * query_len * ref_len` bytes (see `estimate_memory`
+/// with `ResultKind::StatsTable`), so these are for studying short sequences rather than for everyday alignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsTables {
+    /// The alignment score.
+    pub score: i32,
+    /// The index (0-based) just past the last query character of the alignment.
+    pub query_end: usize,
+    /// The index (0-based) just past the last reference character of the alignment.
+    pub ref_end: usize,
+    /// The best score of an alignment ending at each cell.
+    pub scores: DpTable,
+    /// The number of exactly matching characters in that alignment.
+    pub matches: DpTable,
+    /// The number of positively scoring substitutions in that alignment.
+    pub similar: DpTable,
+    /// The number of columns of that alignment.
+    pub lengths: DpTable,
+}
+
+/// Globally aligns the query and reference, keeping every cell of the score and stats tables.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let tables = global_stats_tables(b"ACGT", b"ACGT", 1, 1, &matrix).unwrap();
+///
+/// assert_eq!(4, tables.score);
+/// assert_eq!((4, 4), (tables.scores.rows(), tables.scores.cols()));
+/// assert_eq!(Some(4), tables.matches.get(3, 3));
+/// assert_eq!(Some(4), tables.lengths.get(3, 3));
+/// ```
+pub fn global_stats_tables(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<StatsTables, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_nw_stats_table_striped_sat(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    stats_tables(&result, query_sequence.len(), database_sequence.len())
+}
+
+/// Semi-globally aligns the query and reference, as `semi_global_alignment_stats` does, keeping every cell of the
+/// score and stats tables.
+pub fn semi_global_stats_tables(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<StatsTables, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sg_stats_table_striped_sat(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    stats_tables(&result, query_sequence.len(), database_sequence.len())
+}
+
+/// Locally aligns the query and reference, keeping every cell of the score and stats tables.
+pub fn local_stats_tables(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<StatsTables, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sw_stats_table_striped_sat(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    stats_tables(&result, query_sequence.len(), database_sequence.len())
+}
+
+/// The score table of a semi-global alignment with free gaps at both ends of the reference, as in
+/// `semi_global_dx_traceback`, so row `i` holds the best scores of the query up to and including `i` ending at each
+/// reference position.
+pub(crate) fn semi_global_dx_score_table(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<DpTable, ParasailError> {
+    let (rows, cols) = (query_sequence.len(), database_sequence.len());
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sg_dx_table_striped_sat(
+            query_sequence.as_ptr(),
+            rows as c_int,
+            database_sequence.as_ptr(),
+            cols as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    let raw = result.as_raw();
+    if unsafe { parasail_result_is_table(raw) } == 0 {
+        return Err(ParasailError::Kernel { what: "a score table" });
+    }
+
+    Ok(DpTable {
+        rows,
+        cols,
+        cells: unsafe { slice::from_raw_parts(parasail_result_get_score_table(raw), rows * cols) }.to_vec(),
+    })
+}
+
+/// Copies the tables out of a stats table kernel's result for a `rows` long query and `cols` long reference, before
+/// the result is freed.
+fn stats_tables(result: &ParasailResult, rows: usize, cols: usize) -> Result<StatsTables, ParasailError> {
+    let raw = result.as_raw();
+    assert!(unsafe { parasail_result_is_stats_table(raw) } != 0, "parasail didn't return stats tables");
+
+    // parasail lays the tables out row by row, a row per query character
+    let table = |cells: *mut c_int| DpTable {
+        rows,
+        cols,
+        cells: unsafe { slice::from_raw_parts(cells, rows * cols) }.to_vec(),
+    };
+    unsafe {
+        Ok(StatsTables {
+            score: result.score(),
+            query_end: result.query_end(),
+            ref_end: result.ref_end(),
+            scores: table(parasail_result_get_score_table(raw)),
+            matches: table(parasail_result_get_matches_table(raw)),
+            similar: table(parasail_result_get_similar_table(raw)),
+            lengths: table(parasail_result_get_length_table(raw)),
+        })
+    }
+}
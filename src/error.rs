@@ -0,0 +1,107 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::io;
+
+use thiserror::Error;
+
+use crate::matrix::ParseMatrixTypeError;
+use crate::ops::ParseCigarError;
+
+/// The reason a pair of sequences couldn't be compared.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AlignError {
+    /// The comparison needs sequences of the same length, but they differ.
+    #[error("sequences must have the same length, got {left} and {right}")]
+    LengthMismatch {
+        /// The length of the first sequence.
+        left: usize,
+        /// The length of the second sequence.
+        right: usize,
+    },
+    /// A sequence contains a character outside its alphabet.
+    #[error("invalid {alphabet} residue {:?} at position {pos}", *residue as char)]
+    InvalidResidue {
+        /// The index (0-based) of the offending character.
+        pos: usize,
+        /// The offending character.
+        residue: u8,
+        /// The name of the alphabet the sequence should be in.
+        alphabet: &'static str,
+    },
+    /// Text couldn't be parsed as a substitution matrix.
+    #[error("invalid matrix at line {line}: {reason}")]
+    InvalidMatrix {
+        /// The line (1-based) with the problem, or 0 if it isn't tied to one line.
+        line: usize,
+        /// What was wrong.
+        reason: &'static str,
+    },
+    /// A parameter is out of range or inconsistent with the others.
+    #[error("invalid parameter: {reason}")]
+    InvalidParameter {
+        /// What was wrong.
+        reason: &'static str,
+    },
+    /// A substitution matrix is meant for a different kind of sequence.
+    #[error("substitution matrix can't score {alphabet} sequences")]
+    IncompatibleMatrix {
+        /// The name of the alphabet the matrix was needed for.
+        alphabet: &'static str,
+    },
+    /// A sequence is longer than allowed.
+    #[error("sequence of length {len} is longer than the maximum of {max}")]
+    SequenceTooLong {
+        /// The length of the sequence.
+        len: usize,
+        /// The most residues allowed.
+        max: usize,
+    },
+    /// A sequence has no residues.
+    #[error("sequence is empty")]
+    EmptySequence,
+}
+
+/// Any error from this crate, for code which calls into several parts of it and wants a single error type to pass
+/// up with `?`. Each variant wraps the error of one kind of operation, which says what went wrong and where.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// fn load(name: &str, cigar: &str) -> Result<(Matrix, Vec<AlignmentOp>), ParasailError> {
+///     let matrix = Matrix::from_name(name)?;
+///     let ops = ops_from_cigar(cigar)?;
+///     Ok((matrix, ops))
+/// }
+///
+/// let err = load("blosum62", "3=2Q").unwrap_err();
+/// assert_eq!("invalid CIGAR string at offset 3: unsupported operation", err.to_string());
+/// ```
+#[derive(Debug, Error)]
+pub enum ParasailError {
+    /// A pair of sequences or the parameters for comparing them were invalid.
+    #[error(transparent)]
+    Align(#[from] AlignError),
+    /// A substitution matrix was asked for by a name that isn't known.
+    #[error(transparent)]
+    UnknownMatrix(#[from] ParseMatrixTypeError),
+    /// A CIGAR string couldn't be parsed.
+    #[error(transparent)]
+    Cigar(#[from] ParseCigarError),
+    /// Reading or writing a file (a matrix, an index) failed, or its contents were invalid.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// parasail returned null instead of a result, traceback, or profile, as it does when it runs out of memory or
+    /// rejects its arguments.
+    #[error("parasail couldn't create {what}: it ran out of memory or was given invalid arguments")]
+    Kernel {
+        /// What parasail was asked to create.
+        what: &'static str,
+    },
+    /// The aligned strings of a traceback aren't valid UTF-8, because a sequence had non-ASCII bytes.
+    #[error("parasail traceback isn't valid UTF-8")]
+    InvalidTraceback,
+}
@@ -0,0 +1,343 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::matrix::Matrix;
+
+/// Decides when an extension gives up on a path through the dynamic programming matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropOff {
+    /// Abandon any cell scoring more than this far below the best score seen so far (as in BLAST).
+    X(i32),
+    /// Like `X`, but the allowed drop grows by the gap extension cost for every base of difference between the
+    /// cell's diagonal and the best cell's diagonal (as in minimap2), so a long gap isn't mistaken for a dead end.
+    Z(i32),
+}
+
+/// The interval covered by an extension and the score it achieved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extension {
+    /// The score according to the substitution matrix and gap penalty scheme used.
+    pub score: i64,
+    /// The starting index (0-based) of the extended alignment in the query.
+    pub query_begin: usize,
+    /// The ending index (0-based, exclusive) of the extended alignment in the query.
+    pub query_end: usize,
+    /// The starting index (0-based) of the extended alignment in the reference.
+    pub ref_begin: usize,
+    /// The ending index (0-based, exclusive) of the extended alignment in the reference.
+    pub ref_end: usize,
+}
+
+/// Grows an alignment outwards from an anchor point in both sequences, stopping each direction once its score drops
+/// too far below the best score seen so far.
+///
+/// The anchor is the pair of positions `(query_anchor, ref_anchor)`, typically the start of a seed hit. The alignment
+/// is extended leftwards from the anchor over `query[..query_anchor]` and `reference[..ref_anchor]`, and rightwards
+/// over `query[query_anchor..]` and `reference[ref_anchor..]`; each side keeps its best-scoring prefix and the scores
+/// are summed. Only the cells of the dynamic programming matrix which survive the drop-off are computed, so this is
+/// much cheaper than a full alignment when the sequences diverge past the seed.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let query = b"TTTTGATTACAGGGG";
+/// let reference = b"CCCCCCGATTACACCCCCC";
+///
+/// // a seed hit on "ATTA"
+/// let extension = extend_alignment(query, reference, 5, 7, 1, 1, &matrix, DropOff::X(3));
+/// assert_eq!(7, extension.score);
+/// assert_eq!((4, 11), (extension.query_begin, extension.query_end));
+/// assert_eq!((6, 13), (extension.ref_begin, extension.ref_end));
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn extend_alignment(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    query_anchor: usize,
+    ref_anchor: usize,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+    drop_off: DropOff,
+) -> Extension {
+    let score = |a: u8, b: u8| substitution_matrix.score(a, b);
+
+    let (right_score, right_query, right_ref) = extend_forward(
+        query_sequence[query_anchor..].iter().cloned(),
+        &database_sequence[ref_anchor..],
+        &score,
+        open_cost,
+        gap_extend_cost,
+        drop_off,
+    );
+
+    let left_ref: Vec<u8> = database_sequence[..ref_anchor].iter().rev().cloned().collect();
+    let (left_score, left_query, left_ref) = extend_forward(
+        query_sequence[..query_anchor].iter().rev().cloned(),
+        &left_ref,
+        &score,
+        open_cost,
+        gap_extend_cost,
+        drop_off,
+    );
+
+    Extension {
+        score: (left_score + right_score) as i64,
+        query_begin: query_anchor - left_query,
+        query_end: query_anchor + right_query,
+        ref_begin: ref_anchor - left_ref,
+        ref_end: ref_anchor + right_ref,
+    }
+}
+
+/// Aligns the whole query to a stretch of the reference, computing only the cells within `band` of the diagonal
+/// running through a seed hit at `(query_anchor, ref_anchor)`.
+///
+/// This is the full alignment to follow a seed when the query lies somewhere inside a longer reference: gaps at
+/// either end of the reference are free, as in `semi_global_dx_traceback`, but the work is proportional to the
+/// query length times the band rather than to the size of the whole matrix. Unlike `global_alignment_score_banded`,
+/// which keeps to the main diagonal, the band is centred on the seed's diagonal, so the query can start anywhere in
+/// the reference. The score is exact as long as the best alignment strays no more than `band` cells from the seed's
+/// diagonal, so `band` should allow for the indels expected between the seed and the ends of the query.
+///
+/// Returns `None` if the band misses the reference altogether, for instance when the seed is past its end.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let query = b"GATTACAGATTACA";
+/// let reference = b"CCCCCCCCCCCCCCCCCCCCGATTACAGATTTACACCCCCCCCCCCCCCCC";
+///
+/// // a seed hit on "ACAG", one indel away from the end of the query
+/// let alignment = seeded_band_alignment(query, reference, 4, 24, 2, 1, 1, &matrix).unwrap();
+/// assert_eq!(13, alignment.score);
+/// assert_eq!((0, 14), (alignment.query_begin, alignment.query_end));
+/// assert_eq!((20, 35), (alignment.ref_begin, alignment.ref_end));
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn seeded_band_alignment(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    query_anchor: usize,
+    ref_anchor: usize,
+    band: usize,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Option<Extension> {
+    let diagonal = ref_anchor as i64 - query_anchor as i64;
+    let score = |a: u8, b: u8| substitution_matrix.score(a, b);
+    let (score, ref_begin, ref_end) = align_in_band(
+        query_sequence,
+        database_sequence,
+        diagonal,
+        band,
+        &score,
+        open_cost,
+        gap_extend_cost,
+    )?;
+
+    Some(Extension {
+        score: score as i64,
+        query_begin: 0,
+        query_end: query_sequence.len(),
+        ref_begin,
+        ref_end,
+    })
+}
+
+// low enough that no alignment can reach it, with enough headroom that subtracting gap costs can't overflow
+const DEAD: i32 = i32::MIN / 4;
+
+/// Extends an alignment from the start of both sequences, returning the best score along with how many query and
+/// reference characters the best-scoring alignment consumes.
+///
+/// Cells are computed a query row at a time, and each row only covers the columns which still have a live cell in
+/// the row above (or which a live horizontal gap reaches), so the work done is proportional to the area which
+/// survives the drop-off.
+fn extend_forward<Q, F>(
+    query: Q,
+    reference: &[u8],
+    score: &F,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    drop_off: DropOff,
+) -> (i32, usize, usize)
+where
+    Q: Iterator<Item = u8>,
+    F: Fn(u8, u8) -> i32,
+{
+    let m = reference.len();
+    let (mut best, mut best_i, mut best_j) = (0, 0, 0);
+
+    let threshold = |i: usize, j: usize, best: i32, best_i: usize, best_j: usize| match drop_off {
+        DropOff::X(x) => best - x,
+        DropOff::Z(z) => {
+            let diagonal_diff = (i as i64 - best_i as i64) - (j as i64 - best_j as i64);
+            best - z - gap_extend_cost * diagonal_diff.abs() as i32
+        }
+    };
+
+    // scores ending in a match/mismatch or any gap, and scores ending in a gap in the reference (consuming query)
+    let mut prev_h = vec![DEAD; m + 1];
+    let mut prev_f = vec![DEAD; m + 1];
+    let mut cur_h = vec![DEAD; m + 1];
+    let mut cur_f = vec![DEAD; m + 1];
+
+    // the first row is a run of gaps in the query
+    prev_h[0] = 0;
+    let mut lo = 0;
+    let mut hi = 0;
+    while hi < m {
+        let gap = -(open_cost + gap_extend_cost * hi as i32);
+        if gap < threshold(0, hi + 1, best, best_i, best_j) {
+            break;
+        }
+        hi += 1;
+        prev_h[hi] = gap;
+    }
+    // the range of columns last written in each buffer, so they can be reset cheaply before reuse
+    let mut prev_written = (0, hi);
+    let mut cur_written = (0, 0);
+
+    for (row, q) in query.enumerate() {
+        let i = row + 1;
+
+        for j in cur_written.0..=cur_written.1 {
+            cur_h[j] = DEAD;
+            cur_f[j] = DEAD;
+        }
+
+        let mut e = DEAD;
+        let mut new_lo = None;
+        let mut new_hi = lo;
+        let mut j = lo;
+        while j <= m && (j <= hi + 1 || e > DEAD) {
+            let diagonal = if j > 0 && prev_h[j - 1] > DEAD {
+                prev_h[j - 1] + score(q, reference[j - 1])
+            } else {
+                DEAD
+            };
+            let f = (prev_h[j] - open_cost).max(prev_f[j] - gap_extend_cost).max(DEAD);
+            let mut h = diagonal.max(e).max(f);
+
+            if h < threshold(i, j, best, best_i, best_j) {
+                h = DEAD;
+                cur_f[j] = DEAD;
+            } else {
+                cur_f[j] = f;
+                if new_lo.is_none() {
+                    new_lo = Some(j);
+                }
+                new_hi = j;
+                if h > best {
+                    best = h;
+                    best_i = i;
+                    best_j = j;
+                }
+            }
+            cur_h[j] = h;
+
+            e = (h - open_cost).max(e - gap_extend_cost).max(DEAD);
+            if e < threshold(i, j + 1, best, best_i, best_j) {
+                e = DEAD;
+            }
+            j += 1;
+        }
+        cur_written = (lo, j.saturating_sub(1).max(lo));
+
+        match new_lo {
+            Some(new_lo) => {
+                lo = new_lo;
+                hi = new_hi;
+            }
+            None => break,
+        }
+
+        std::mem::swap(&mut prev_h, &mut cur_h);
+        std::mem::swap(&mut prev_f, &mut cur_f);
+        std::mem::swap(&mut prev_written, &mut cur_written);
+    }
+
+    (best, best_i, best_j)
+}
+
+/// Aligns all of `query` to part of `reference` within `band` cells of the diagonal where the reference index minus
+/// the query index is `diagonal`, returning the best score with the reference interval it covers.
+///
+/// Rows only hold the band, so cell `k` of row `i` is column `i + diagonal - band + k`: the cell diagonally up and to
+/// the left is cell `k` of the row above, and the one directly above is cell `k + 1`. Each cell carries the column its
+/// alignment started at along with its score.
+fn align_in_band<F>(
+    query: &[u8],
+    reference: &[u8],
+    diagonal: i64,
+    band: usize,
+    score: &F,
+    open_cost: i32,
+    gap_extend_cost: i32,
+) -> Option<(i32, usize, usize)>
+where
+    F: Fn(u8, u8) -> i32,
+{
+    let width = 2 * band + 1;
+    let first_column = |i: usize| i as i64 + diagonal - band as i64;
+    let in_reference = |j: i64| 0 <= j && j <= reference.len() as i64;
+    let dead = (DEAD, 0);
+    let better = |a: (i32, usize), b: (i32, usize)| if b.0 > a.0 { b } else { a };
+    let gap = |from: (i32, usize), cost: i32| ((from.0 - cost).max(DEAD), from.1);
+
+    // scores ending in a match/mismatch or any gap, and scores ending in a gap in the reference (consuming query);
+    // the first row is free, since the alignment can start anywhere in the reference
+    let mut h: Vec<(i32, usize)> = (0..width)
+        .map(|k| first_column(0) + k as i64)
+        .map(|j| if in_reference(j) { (0, j as usize) } else { dead })
+        .collect();
+    let mut f = vec![dead; width];
+    let mut cur_h = vec![dead; width];
+    let mut cur_f = vec![dead; width];
+
+    for (row, &q) in query.iter().enumerate() {
+        let i = row + 1;
+        let mut e = dead;
+        for k in 0..width {
+            let j = first_column(i) + k as i64;
+            if !in_reference(j) {
+                cur_h[k] = dead;
+                cur_f[k] = dead;
+                e = dead;
+                continue;
+            }
+
+            cur_f[k] = match (h.get(k + 1), f.get(k + 1)) {
+                (Some(&up_h), Some(&up_f)) => better(gap(up_h, open_cost), gap(up_f, gap_extend_cost)),
+                _ => dead,
+            };
+            let matched = if h[k].0 > DEAD {
+                (h[k].0 + score(q, reference[j as usize - 1]), h[k].1)
+            } else {
+                dead
+            };
+            cur_h[k] = better(better(matched, cur_f[k]), e);
+            e = better(gap(cur_h[k], open_cost), gap(e, gap_extend_cost));
+        }
+        std::mem::swap(&mut h, &mut cur_h);
+        std::mem::swap(&mut f, &mut cur_f);
+    }
+
+    // the end of the reference is free too, so the best cell of the last row wins (the leftmost, on a tie)
+    let mut best: Option<(i32, usize, usize)> = None;
+    for (k, &(cell, start)) in h.iter().enumerate() {
+        let j = first_column(query.len()) + k as i64;
+        if in_reference(j) && cell > DEAD && best.map_or(true, |b| cell > b.0) {
+            best = Some((cell, start, j as usize));
+        }
+    }
+    best
+}
@@ -55,9 +55,9 @@
 //! # let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
 //! # let identity_matrix = Matrix::new(MatrixType::Identity);
 //! # let profile = Profile::new(query_sequence, &identity_matrix);
-//! assert_eq!(10, local_alignment_score(&profile, reference, 1, 1));
-//! assert_eq!(10, semi_global_alignment_score(&profile, reference, 1, 1));
-//! assert_eq!(-30, global_alignment_score(&profile, reference, 1, 1));
+//! assert_eq!(10, local_alignment_score(&profile, reference, 1, 1).unwrap());
+//! assert_eq!(10, semi_global_alignment_score(&profile, reference, 1, 1).unwrap());
+//! assert_eq!(-30, global_alignment_score(&profile, reference, 1, 1).unwrap());
 //! ```
 //!
 //! And a non-matching alignment:
@@ -68,9 +68,9 @@
 //! # let identity_matrix = Matrix::new(MatrixType::Identity);
 //! # let profile = Profile::new(query_sequence, &identity_matrix);
 //! let reference = b"CCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
-//! assert_eq!(0, local_alignment_score(&profile, reference, 1, 1));
-//! assert_eq!(0, semi_global_alignment_score(&profile, reference, 1, 1));
-//! assert_eq!(-30, global_alignment_score(&profile, reference, 1, 1));
+//! assert_eq!(0, local_alignment_score(&profile, reference, 1, 1).unwrap());
+//! assert_eq!(0, semi_global_alignment_score(&profile, reference, 1, 1).unwrap());
+//! assert_eq!(-30, global_alignment_score(&profile, reference, 1, 1).unwrap());
 //! ```
 //!
 //! Some more examples with differing query/reference relationships:
@@ -84,15 +84,15 @@
 //!
 //! // these should be exact matches, with score of 50
 //! let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
-//! assert_eq!(50, local_alignment_score(&profile, reference, 1, 1));
-//! assert_eq!(50, semi_global_alignment_score(&profile, reference, 1, 1));
-//! assert_eq!(50, global_alignment_score(&profile, reference, 1, 1));
+//! assert_eq!(50, local_alignment_score(&profile, reference, 1, 1).unwrap());
+//! assert_eq!(50, semi_global_alignment_score(&profile, reference, 1, 1).unwrap());
+//! assert_eq!(50, global_alignment_score(&profile, reference, 1, 1).unwrap());
 //!
 //! // these should be inexact matches with 2 edits, with score of 48
 //! let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTCCTTTTTTNNNNNNNNN";
-//! assert_eq!(48, local_alignment_score(&profile, reference, 1, 1));
-//! assert_eq!(48, semi_global_alignment_score(&profile, reference, 1, 1));
-//! assert_eq!(48, global_alignment_score(&profile, reference, 1, 1));
+//! assert_eq!(48, local_alignment_score(&profile, reference, 1, 1).unwrap());
+//! assert_eq!(48, semi_global_alignment_score(&profile, reference, 1, 1).unwrap());
+//! assert_eq!(48, global_alignment_score(&profile, reference, 1, 1).unwrap());
 //! ```
 //!
 //! Also, we can just do one-off alignment which will automatically create and
@@ -103,16 +103,134 @@
 //! # let identity_matrix = Matrix::new(MatrixType::Identity);
 //! let query = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
 //! let reference = b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN";
-//! assert_eq!(50, local_alignment_score_no_profile(reference, query, 1, 1, &identity_matrix));
+//! assert_eq!(50, local_alignment_score_no_profile(reference, query, 1, 1, &identity_matrix).unwrap());
 //! ```
+//!
+//! ## Errors
+//!
+//! Fallible functions return `AlignError` when they only check their inputs, and `ParasailError` when they also run
+//! parasail or touch files. `ParasailError` wraps `AlignError` along with kernel failures, I/O errors, and parse
+//! errors, so `?` works from one to the other.
+//!
+//! There are deliberately no separate matrix or profile error types. A bad matrix (`InvalidMatrix`), a matrix that
+//! doesn't suit the sequences (`IncompatibleMatrix`), and a bad profile parameter (`InvalidParameter`) are all
+//! `AlignError` variants, because most calls can hit several of them at once, and one enum to match on is easier to
+//! handle than a nested hierarchy. Failures inside parasail itself are `ParasailError::Kernel`.
 
 extern crate libc;
 // extern crate parasail_sys;
 
 mod align;
+mod aligner;
+mod auto;
+mod backend;
+mod batch;
+mod chain;
+mod checkpoint;
+mod chunked;
+mod cluster;
+#[cfg(feature = "arrow")]
+mod columnar;
+mod consensus;
+#[cfg(feature = "polars")]
+mod dataframe;
+mod dedup;
+mod diff;
+mod distance;
+mod distance_matrix;
+mod dp_table;
+mod error;
+mod extend;
+mod log_odds;
 mod matrix;
+mod memory;
+mod minimizer;
+mod myers;
+mod ops;
+mod overlap;
+mod overlap_graph;
+mod parallel;
+mod preset;
 mod profile;
+mod progress;
+mod reduced;
+mod repeat;
+mod result;
+mod sanitize;
+mod scalar;
+mod scheme;
+mod screen;
+mod search;
+mod sequence;
+mod session;
+mod significance;
+mod sink;
+mod sketch;
+mod split;
+mod suboptimal;
+mod table;
+mod top_hits;
+mod tree;
+mod variant;
+mod version;
+#[cfg(feature = "wfa")]
+mod wfa;
+
+pub mod matrices;
+pub mod seq;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 
 pub use align::*;
+pub use aligner::*;
+pub use auto::*;
+pub use backend::*;
+pub use batch::*;
+pub use chain::*;
+pub use checkpoint::*;
+pub use chunked::*;
+pub use cluster::*;
+#[cfg(feature = "arrow")]
+pub use columnar::*;
+pub use consensus::*;
+#[cfg(feature = "polars")]
+pub use dataframe::*;
+pub use dedup::*;
+pub use diff::*;
+pub use distance::*;
+pub use distance_matrix::*;
+pub use dp_table::*;
+pub use error::*;
+pub use extend::*;
+pub use log_odds::*;
 pub use matrix::*;
+pub use memory::*;
+pub use minimizer::*;
+pub use ops::*;
+pub use overlap::*;
+pub use overlap_graph::*;
+pub use preset::*;
 pub use profile::*;
+pub use progress::*;
+pub use reduced::*;
+pub use repeat::*;
+pub use result::*;
+pub use sanitize::*;
+pub use scalar::*;
+pub use scheme::*;
+pub use screen::*;
+pub use search::*;
+pub use sequence::*;
+pub use session::*;
+pub use significance::*;
+pub use sink::*;
+pub use sketch::*;
+pub use split::*;
+pub use suboptimal::*;
+pub use table::*;
+pub use top_hits::*;
+pub use tree::*;
+pub use variant::*;
+pub use version::*;
+#[cfg(feature = "wfa")]
+pub use wfa::*;
@@ -0,0 +1,160 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::error::AlignError;
+use crate::matrix::Matrix;
+
+/// Estimates a log-odds substitution matrix from aligned training data, the way BLOSUM matrices were built.
+///
+/// The score for letters `a` and `b` is `round(scale * log2(p(a, b) / (q(a) * q(b))))`, where `p` is the observed
+/// frequency of `a` aligned to `b` (counting each pair both ways round, so the matrix is symmetric) and `q` is the
+/// background frequency of each letter. Every pair starts with `pseudocount` observations so that unseen pairs get a
+/// finite score. Unless background frequencies are given, they're taken from the letters in the observed pairs.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let mut builder = LogOddsBuilder::new("ACGT");
+/// builder.add_alignment(b"ACGTACGTAC-GTTGCA", b"ACGTACCTACAGTTGCA");
+/// builder.add_pair_count(b'A', b'G', 3.0);
+///
+/// let matrix = builder.build().unwrap();
+/// assert!(matrix.score(b'A', b'A') > 0);
+/// assert!(matrix.score(b'A', b'C') < 0);
+/// assert_eq!(matrix.score(b'A', b'G'), matrix.score(b'G', b'A'));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogOddsBuilder {
+    alphabet: Vec<u8>,
+    counts: Vec<Vec<f64>>,
+    background: Option<Vec<f64>>,
+    pseudocount: f64,
+    scale: f64,
+}
+
+impl LogOddsBuilder {
+    /// Starts a matrix over the letters of `alphabet`, with no observations, a pseudocount of 1, and half-bit scores
+    /// (a scale of 2).
+    pub fn new(alphabet: &str) -> Self {
+        let alphabet: Vec<u8> = alphabet.bytes().collect();
+        let size = alphabet.len();
+        LogOddsBuilder {
+            alphabet,
+            counts: vec![vec![0.0; size]; size],
+            background: None,
+            pseudocount: 1.0,
+            scale: 2.0,
+        }
+    }
+
+    fn index(&self, letter: u8) -> Option<usize> {
+        self.alphabet.iter().position(|a| a.eq_ignore_ascii_case(&letter))
+    }
+
+    /// Records `count` observations of `a` aligned to `b`. Letters outside the alphabet are ignored.
+    pub fn add_pair_count(&mut self, a: u8, b: u8, count: f64) -> &mut Self {
+        if let (Some(i), Some(j)) = (self.index(a), self.index(b)) {
+            // half each way round keeps the table symmetric
+            self.counts[i][j] += count / 2.0;
+            self.counts[j][i] += count / 2.0;
+        }
+        self
+    }
+
+    /// Records one observation of `a` aligned to `b`.
+    pub fn add_pair(&mut self, a: u8, b: u8) -> &mut Self {
+        self.add_pair_count(a, b, 1.0)
+    }
+
+    /// Records every aligned pair of two rows of an alignment, such as the `query_trace` and `ref_trace` of a
+    /// traceback. Columns with a gap (`-`) or a letter outside the alphabet are skipped.
+    pub fn add_alignment(&mut self, first: &[u8], second: &[u8]) -> &mut Self {
+        for (&a, &b) in first.iter().zip(second) {
+            if a != b'-' && b != b'-' {
+                self.add_pair(a, b);
+            }
+        }
+        self
+    }
+
+    /// Uses the given background frequencies instead of those of the observed letters. They're normalized to sum to
+    /// 1, and every letter of the alphabet needs a positive frequency by the time the matrix is built.
+    pub fn background(&mut self, frequencies: &[(u8, f64)]) -> &mut Self {
+        let mut background = vec![0.0; self.alphabet.len()];
+        for &(letter, frequency) in frequencies {
+            if let Some(i) = self.index(letter) {
+                background[i] += frequency;
+            }
+        }
+        self.background = Some(background);
+        self
+    }
+
+    /// Sets the number of observations every pair starts with.
+    pub fn pseudocount(&mut self, pseudocount: f64) -> &mut Self {
+        self.pseudocount = pseudocount;
+        self
+    }
+
+    /// Sets the multiplier applied to the base 2 log-odds before rounding, e.g. 2 for half-bit or 3 for third-bit
+    /// scores.
+    pub fn scale(&mut self, scale: f64) -> &mut Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Computes the rounded log-odds matrix. Returns an error if the alphabet is empty, the pseudocount is negative or
+    /// the scale isn't positive, some pair has no observations even after the pseudocount, or a letter has no
+    /// background frequency.
+    pub fn build(&self) -> Result<Matrix, AlignError> {
+        let invalid = |reason| Err(AlignError::InvalidParameter { reason });
+        if self.alphabet.is_empty() {
+            return invalid("the alphabet is empty");
+        }
+        if !(self.pseudocount >= 0.0 && self.pseudocount.is_finite()) {
+            return invalid("the pseudocount must be finite and non-negative");
+        }
+        if !(self.scale > 0.0 && self.scale.is_finite()) {
+            return invalid("the scale must be finite and positive");
+        }
+
+        let pairs: Vec<Vec<f64>> = self
+            .counts
+            .iter()
+            .map(|row| row.iter().map(|count| count + self.pseudocount).collect())
+            .collect();
+        if pairs.iter().flatten().any(|&count| count <= 0.0) {
+            return invalid("a pair was never observed, so a positive pseudocount is needed");
+        }
+        let total: f64 = pairs.iter().flatten().sum();
+
+        let background = match self.background {
+            Some(ref background) => background.clone(),
+            None => pairs.iter().map(|row| row.iter().sum::<f64>()).collect(),
+        };
+        let background_total: f64 = background.iter().sum();
+        if background.iter().any(|&q| q.is_nan() || q <= 0.0) {
+            return invalid("every letter needs a positive background frequency");
+        }
+
+        let rows: Vec<Vec<i32>> = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(j, &count)| {
+                        let observed = count / total;
+                        let expected = (background[i] / background_total) * (background[j] / background_total);
+                        (self.scale * (observed / expected).log2()).round() as i32
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(Matrix::from_table(&self.alphabet, &rows))
+    }
+}
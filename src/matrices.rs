@@ -0,0 +1,129 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+//! Shared instances of the built-in substitution matrices.
+//!
+//! `Matrix::new` looks the matrix up through parasail (and builds the identity matrices from scratch) on every call.
+//! Each static here does that once, the first time it's used, and can then be borrowed from anywhere, including
+//! from other threads, for the rest of the program. Prefer these over `Matrix::new` in hot paths such as per-read
+//! closures.
+//!
+//! # Examples
+//!
+//! ```
+//! # use parasailors::*;
+//! let profile = Profile::new(b"ACGTACGT", &matrices::IDENTITY_WITH_PENALTY);
+//! assert_eq!(8, global_alignment_score(&profile, b"ACGTACGT", 1, 1).unwrap());
+//! assert_eq!(11, matrices::BLOSUM62.score(b'W', b'W'));
+//! ```
+
+use std::ops::Deref;
+use std::sync::OnceLock;
+
+use crate::matrix::{Matrix, MatrixType};
+
+/// A built-in matrix that is built on first use. Derefs to the `Matrix`.
+///
+/// This is `LazyLock<Matrix>` spelled out with `OnceLock`, which keeps the crate building on Rust 1.73.
+pub struct SharedMatrix {
+    matrix_type: MatrixType,
+    matrix: OnceLock<Matrix>,
+}
+
+impl SharedMatrix {
+    const fn new(matrix_type: MatrixType) -> SharedMatrix {
+        SharedMatrix { matrix_type, matrix: OnceLock::new() }
+    }
+}
+
+impl Deref for SharedMatrix {
+    type Target = Matrix;
+
+    fn deref(&self) -> &Matrix {
+        self.matrix.get_or_init(|| Matrix::new(self.matrix_type))
+    }
+}
+
+macro_rules! builtin_matrices {
+    ($($name:ident => $matrix_type:ident,)*) => {
+        $(
+            #[doc = concat!("The shared `MatrixType::", stringify!($matrix_type), "` matrix.")]
+            pub static $name: SharedMatrix = SharedMatrix::new(MatrixType::$matrix_type);
+        )*
+    };
+}
+
+builtin_matrices! {
+    IDENTITY => Identity,
+    IDENTITY_WITH_PENALTY => IdentityWithPenalty,
+    ADAPTOR_SEARCH => AdaptorSearch,
+    DNAFULL => DNAFull,
+    NUC44 => Nuc44,
+    BLOSUM100 => Blosum100,
+    BLOSUM30 => Blosum30,
+    BLOSUM35 => Blosum35,
+    BLOSUM40 => Blosum40,
+    BLOSUM45 => Blosum45,
+    BLOSUM50 => Blosum50,
+    BLOSUM55 => Blosum55,
+    BLOSUM60 => Blosum60,
+    BLOSUM62 => Blosum62,
+    BLOSUM65 => Blosum65,
+    BLOSUM70 => Blosum70,
+    BLOSUM75 => Blosum75,
+    BLOSUM80 => Blosum80,
+    BLOSUM85 => Blosum85,
+    BLOSUM90 => Blosum90,
+    PAM10 => Pam10,
+    PAM100 => Pam100,
+    PAM110 => Pam110,
+    PAM120 => Pam120,
+    PAM130 => Pam130,
+    PAM140 => Pam140,
+    PAM150 => Pam150,
+    PAM160 => Pam160,
+    PAM170 => Pam170,
+    PAM180 => Pam180,
+    PAM190 => Pam190,
+    PAM20 => Pam20,
+    PAM200 => Pam200,
+    PAM210 => Pam210,
+    PAM220 => Pam220,
+    PAM230 => Pam230,
+    PAM240 => Pam240,
+    PAM250 => Pam250,
+    PAM260 => Pam260,
+    PAM270 => Pam270,
+    PAM280 => Pam280,
+    PAM290 => Pam290,
+    PAM30 => Pam30,
+    PAM300 => Pam300,
+    PAM310 => Pam310,
+    PAM320 => Pam320,
+    PAM330 => Pam330,
+    PAM340 => Pam340,
+    PAM350 => Pam350,
+    PAM360 => Pam360,
+    PAM370 => Pam370,
+    PAM380 => Pam380,
+    PAM390 => Pam390,
+    PAM40 => Pam40,
+    PAM400 => Pam400,
+    PAM410 => Pam410,
+    PAM420 => Pam420,
+    PAM430 => Pam430,
+    PAM440 => Pam440,
+    PAM450 => Pam450,
+    PAM460 => Pam460,
+    PAM470 => Pam470,
+    PAM480 => Pam480,
+    PAM490 => Pam490,
+    PAM50 => Pam50,
+    PAM500 => Pam500,
+    PAM60 => Pam60,
+    PAM70 => Pam70,
+    PAM80 => Pam80,
+    PAM90 => Pam90,
+}
@@ -3,18 +3,49 @@
 // This software may be modified and distributed under the terms of the MIT license.  See the
 // LICENSE file for details.
 
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
 use std::ops::Deref;
+use std::path::Path;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::error::AlignError;
+use crate::result::non_null;
 
 // use libc::{c_int, c_char};
 
-use parasail_sys::{parasail_matrix, parasail_matrix_create, parasail_matrix_free,
-                   parasail_matrix_lookup, parasail_matrix_set_value};
+use parasail_sys::{parasail_matrix, parasail_matrix_copy, parasail_matrix_create, parasail_matrix_free,
+                   parasail_matrix_lookup, parasail_matrix_pssm_create, parasail_matrix_set_value};
+
+// parasail's `PARASAIL_MATRIX_TYPE_PSSM`, for matrices with a row per query position rather than per letter
+const MATRIX_TYPE_PSSM: i32 = 1;
+
+// parasail only fails to create or copy a matrix when it can't allocate one, which is treated like any other failed
+// allocation rather than as an error
+const OUT_OF_MEMORY: &str = "parasail couldn't allocate a matrix";
 
 /// A substitution matrix to use when aligning DNA or protein. Can be reused in many profiles.
 pub struct Matrix {
     matrix_type: MatrixType,
     internal_rep: *const parasail_matrix,
+    ownership: Ownership,
+    /// Whether the matrix came from `parasail_matrix_create`, which adds a `*` row and column after the alphabet it's
+    /// given for characters outside it. That wildcard is left out of `alphabet` and `score_table`.
+    wildcard: bool,
+}
+
+/// Whether a `Matrix` is responsible for freeing the parasail matrix it points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ownership {
+    /// Allocated for this struct, which may change it and must free it.
+    Owned,
+    /// One of parasail's statically allocated built-in matrices, which must never be changed or freed.
+    Static,
 }
 
 unsafe impl Send for Matrix {}
@@ -32,12 +63,6 @@ impl Matrix {
     /// let blosum62 = Matrix::new(MatrixType::Blosum62);
     /// let pam120 = Matrix::new(MatrixType::Pam120);
     /// ```
-    // pub fn from_file(filename: &str) -> Self {
-    //     unsafe {
-    //         c_name = CString::from(filename).unwrap().into_raw();
-    //         parasail_matrix_from_file(c_name)
-    //     }
-    // }
     pub fn new(matrix_type: MatrixType) -> Self {
         unsafe {
             // we can pass this pointer because it will outlive this unsafe block
@@ -69,6 +94,7 @@ impl Matrix {
                                                  com/dikaiosune/parasailors/issues with a sample \
                                                  of the code that caused this error.");
                     let matrix = parasail_matrix_create(alphabet.as_ptr(), 1, -2);
+                    let matrix = non_null(matrix, "a matrix").expect(OUT_OF_MEMORY);
                     for i in 0 .. 7 {
                         parasail_matrix_set_value(matrix, i, 6, 1);
                         parasail_matrix_set_value(matrix, 6, i, 1);
@@ -83,76 +109,7 @@ impl Matrix {
                     matrix
                 }
                 _ => {
-                    let lookup_name = match matrix_type {
-                        MatrixType::DNAFull => "dnafull",
-                        MatrixType::Nuc44 => "nuc44",
-                        MatrixType::Blosum100 => "blosum100",
-                        MatrixType::Blosum30 => "blosum30",
-                        MatrixType::Blosum35 => "blosum35",
-                        MatrixType::Blosum40 => "blosum40",
-                        MatrixType::Blosum45 => "blosum45",
-                        MatrixType::Blosum50 => "blosum50",
-                        MatrixType::Blosum55 => "blosum55",
-                        MatrixType::Blosum60 => "blosum60",
-                        MatrixType::Blosum62 => "blosum62",
-                        MatrixType::Blosum65 => "blosum65",
-                        MatrixType::Blosum70 => "blosum70",
-                        MatrixType::Blosum75 => "blosum75",
-                        MatrixType::Blosum80 => "blosum80",
-                        MatrixType::Blosum85 => "blosum85",
-                        MatrixType::Blosum90 => "blosum90",
-                        MatrixType::Pam10 => "pam10",
-                        MatrixType::Pam100 => "pam100",
-                        MatrixType::Pam110 => "pam110",
-                        MatrixType::Pam120 => "pam120",
-                        MatrixType::Pam130 => "pam130",
-                        MatrixType::Pam140 => "pam140",
-                        MatrixType::Pam150 => "pam150",
-                        MatrixType::Pam160 => "pam160",
-                        MatrixType::Pam170 => "pam170",
-                        MatrixType::Pam180 => "pam180",
-                        MatrixType::Pam190 => "pam190",
-                        MatrixType::Pam20 => "pam20",
-                        MatrixType::Pam200 => "pam200",
-                        MatrixType::Pam210 => "pam210",
-                        MatrixType::Pam220 => "pam220",
-                        MatrixType::Pam230 => "pam230",
-                        MatrixType::Pam240 => "pam240",
-                        MatrixType::Pam250 => "pam250",
-                        MatrixType::Pam260 => "pam260",
-                        MatrixType::Pam270 => "pam270",
-                        MatrixType::Pam280 => "pam280",
-                        MatrixType::Pam290 => "pam290",
-                        MatrixType::Pam30 => "pam30",
-                        MatrixType::Pam300 => "pam300",
-                        MatrixType::Pam310 => "pam310",
-                        MatrixType::Pam320 => "pam320",
-                        MatrixType::Pam330 => "pam330",
-                        MatrixType::Pam340 => "pam340",
-                        MatrixType::Pam350 => "pam350",
-                        MatrixType::Pam360 => "pam360",
-                        MatrixType::Pam370 => "pam370",
-                        MatrixType::Pam380 => "pam380",
-                        MatrixType::Pam390 => "pam390",
-                        MatrixType::Pam40 => "pam40",
-                        MatrixType::Pam400 => "pam400",
-                        MatrixType::Pam410 => "pam410",
-                        MatrixType::Pam420 => "pam420",
-                        MatrixType::Pam430 => "pam430",
-                        MatrixType::Pam440 => "pam440",
-                        MatrixType::Pam450 => "pam450",
-                        MatrixType::Pam460 => "pam460",
-                        MatrixType::Pam470 => "pam470",
-                        MatrixType::Pam480 => "pam480",
-                        MatrixType::Pam490 => "pam490",
-                        MatrixType::Pam50 => "pam50",
-                        MatrixType::Pam500 => "pam500",
-                        MatrixType::Pam60 => "pam60",
-                        MatrixType::Pam70 => "pam70",
-                        MatrixType::Pam80 => "pam80",
-                        MatrixType::Pam90 => "pam90",
-                        _ => "",
-                    };
+                    let lookup_name = matrix_type.name();
 
                     let lookup = &CString::new(lookup_name)
                                       .expect("An internal error has occurred (matrix lookup \
@@ -165,32 +122,689 @@ impl Matrix {
                     parasail_matrix_lookup(lookup.as_ptr())
                 }
             };
-            // it's OK to keep this pointer forever, it points to static const structs
+            // it's OK to keep a looked up pointer forever, it points to static const structs
+            let ownership = match matrix_type {
+                MatrixType::Identity | MatrixType::IdentityWithPenalty | MatrixType::AdaptorSearch => Ownership::Owned,
+                _ => Ownership::Static,
+            };
             Matrix {
-                internal_rep: matrix,
+                internal_rep: non_null(matrix as *mut parasail_matrix, "a matrix").expect(OUT_OF_MEMORY),
                 matrix_type: matrix_type,
+                ownership,
+                wildcard: ownership == Ownership::Owned,
+            }
+        }
+    }
+
+    /// Looks up a matrix by name, as `MatrixType::from_str` parses it (`"blosum62"`, `"PAM250"`, `"identity"`, ...).
+    ///
+    /// Names parasail knows but `MatrixType` doesn't, such as matrices added in a newer parasail, are still found by
+    /// asking parasail directly, and come back as an owned `Custom` copy. See `available_matrices` for the full list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let blosum62 = Matrix::from_name("blosum62").unwrap();
+    /// assert_eq!(11, blosum62.score(b'W', b'W'));
+    /// assert!(Matrix::from_name("blosum63").is_err());
+    /// ```
+    pub fn from_name(name: &str) -> Result<Self, ParseMatrixTypeError> {
+        let error = match name.parse::<MatrixType>() {
+            Ok(matrix_type) => return Ok(Matrix::new(matrix_type)),
+            Err(error) => error,
+        };
+
+        let lookup = CString::new(name.trim().to_ascii_lowercase()).map_err(|_| error.clone())?;
+        unsafe {
+            let found = parasail_matrix_lookup(lookup.as_ptr());
+            if found.is_null() {
+                return Err(error);
             }
+            // built-in matrices are static, so take a copy this struct can own and free
+            Ok(Matrix {
+                internal_rep: non_null(parasail_matrix_copy(found), "a matrix").expect(OUT_OF_MEMORY),
+                matrix_type: MatrixType::Custom,
+                ownership: Ownership::Owned,
+                wildcard: false,
+            })
+        }
+    }
+
+    /// Takes ownership of a matrix created directly through `parasail_sys`, such as with `parasail_matrix_create` or
+    /// `parasail_matrix_copy`, so it can be used with this crate's alignment functions and is freed on drop.
+    ///
+    /// # Safety
+    ///
+    /// `matrix` must be a valid, non-null matrix allocated by parasail that nothing else frees or uses after this.
+    /// Don't pass one of parasail's built-in matrices from `parasail_matrix_lookup`: those are static and must never
+    /// be freed. Use `Matrix::new` or `Matrix::from_name` for those instead.
+    pub unsafe fn from_raw(matrix: *const parasail_matrix) -> Self {
+        Matrix {
+            internal_rep: matrix,
+            matrix_type: MatrixType::Custom,
+            ownership: Ownership::Owned,
+            wildcard: false,
         }
     }
 
+    /// The underlying parasail matrix, for passing to `parasail_sys` functions directly.
+    ///
+    /// The matrix is still owned by this struct (or is one of parasail's static built-ins): don't free or change it,
+    /// and don't use the pointer after this struct is dropped.
+    pub fn as_raw(&self) -> *const parasail_matrix {
+        self.internal_rep
+    }
+
     /// Create a custom matrix
     pub fn create(alphabet_input: &str, match_score: i64, mismatch_penalty: i64) -> Self {
+        Matrix::create_bytes(alphabet_input.as_bytes(),
+                             match_score as ::std::os::raw::c_int,
+                             mismatch_penalty as ::std::os::raw::c_int)
+    }
+
+    /// Creates a matrix over an arbitrary alphabet of ASCII bytes, scoring `match_score` for identical symbols and
+    /// `mismatch_score` for any two different ones. The alphabet isn't limited to letters, so this can align token
+    /// IDs, OCR output, or any other encoding of up to 127 symbols that fits in 7 bits.
+    ///
+    /// Symbols are matched exactly, so `a` and `A` are different symbols if both are in the alphabet. A symbol that's
+    /// only in the alphabet in one case still matches the other case, as with parasail's own matrices. Returns an
+    /// error if the alphabet is empty, repeats a symbol, or contains a NUL byte or a byte above `0x7f`. Tracebacks
+    /// come back as strings, so symbols outside ASCII couldn't be traced back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::identity(&[1, 2, 3, 0x7f, b'a', b'A'], 2, -1).unwrap();
+    /// assert_eq!(2, matrix.score(0x7f, 0x7f));
+    /// assert_eq!(-1, matrix.score(1, 3));
+    /// assert_eq!(-1, matrix.score(b'a', b'A'));
+    /// assert!(Matrix::identity(&[1, 2, 1], 2, -1).is_err());
+    /// assert!(Matrix::identity(&[1, 2, 0xff], 2, -1).is_err());
+    /// ```
+    pub fn identity(alphabet: &[u8], match_score: i32, mismatch_score: i32) -> Result<Self, AlignError> {
+        check_alphabet(alphabet)?;
+        Ok(Matrix::create_bytes(alphabet, match_score, mismatch_score))
+    }
+
+    /// Creates a matrix over an arbitrary alphabet of bytes from a square table of scores, where `rows[i][j]` is the
+    /// score of aligning `alphabet[i]` in the query against `alphabet[j]` in the reference.
+    ///
+    /// The alphabet follows the same rules as in `Matrix::identity`. Returns an error if it breaks them, or if the
+    /// table doesn't have exactly one row and one column per symbol.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// // three tokens, where 10 and 11 are near-synonyms
+    /// let matrix = Matrix::from_scores(&[10, 11, 12], &[vec![3, 1, -2], vec![1, 3, -2], vec![-2, -2, 3]]).unwrap();
+    /// assert_eq!(1, matrix.score(10, 11));
+    /// assert_eq!(-2, matrix.score(11, 12));
+    /// assert_eq!(&[10, 11, 12], matrix.alphabet());
+    /// ```
+    pub fn from_scores(alphabet: &[u8], rows: &[Vec<i32>]) -> Result<Self, AlignError> {
+        check_alphabet(alphabet)?;
+        if rows.len() != alphabet.len() || rows.iter().any(|row| row.len() != alphabet.len()) {
+            return Err(AlignError::InvalidParameter {
+                reason: "score table must have one row and one column per symbol",
+            });
+        }
+        Ok(Matrix::from_table(alphabet, rows))
+    }
+
+    /// Creates a matrix with `parasail_matrix_create`, then maps each symbol of the alphabet to its own row exactly,
+    /// since parasail folds case when it builds the mapping and a symbol's other case may be in the alphabet too.
+    fn create_bytes(alphabet: &[u8],
+                    match_score: ::std::os::raw::c_int,
+                    mismatch_score: ::std::os::raw::c_int) -> Self {
         unsafe {
-            let alphabet = &CString::new(alphabet_input).expect("An internal error has occurred (creating \
+            let alphabet_c = &CString::new(alphabet).expect("An internal error has occurred (creating \
                 identity matrix). Please file an issue at \
                 https://github.\
                 com/dikaiosune/parasailors/issues with a sample \
                 of the code that caused this error.");
 
-            let matrix: *const parasail_matrix = parasail_matrix_create(alphabet.as_ptr(), match_score as ::std::os::raw::c_int, mismatch_penalty as ::std::os::raw::c_int);
+            let matrix = parasail_matrix_create(alphabet_c.as_ptr(), match_score, mismatch_score);
+            let matrix = non_null(matrix, "a matrix").expect(OUT_OF_MEMORY);
+            // the mapper was allocated along with the matrix, which nothing else has seen yet
+            let mapper = (*matrix).mapper as *mut ::std::os::raw::c_int;
+            for (i, &symbol) in alphabet.iter().enumerate() {
+                *mapper.offset(symbol as isize) = i as ::std::os::raw::c_int;
+            }
             Matrix {
                 internal_rep: matrix,
                 matrix_type: MatrixType::Custom,
+                ownership: Ownership::Owned,
+                wildcard: true,
+            }
+        }
+    }
+
+    /// Creates a nucleotide matrix over all 15 IUPAC codes (`ACGTRYSWKMBDHVN`) in which ambiguity codes score by how
+    /// likely they are to stand for the same base.
+    ///
+    /// Two codes standing for the base sets `X` and `Y` share a base with probability `p = |X ∩ Y| / (|X| |Y|)`,
+    /// treating each base a code allows as equally likely, and score `p * match_score + (1 - p) * mismatch_score`
+    /// rounded to the nearest integer. Plain bases score exactly `match_score` and `mismatch_score`, a code scores
+    /// partially against the bases it allows (`R` against `A` is the average of the two), and codes with no bases in
+    /// common always score `mismatch_score`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::iupac_dna(4, -4);
+    /// assert_eq!(4, matrix.score(b'A', b'A'));
+    /// assert_eq!(-4, matrix.score(b'A', b'C'));
+    /// assert_eq!(0, matrix.score(b'R', b'A'));
+    /// assert_eq!(-4, matrix.score(b'R', b'C'));
+    /// assert_eq!(-2, matrix.score(b'N', b'A'));
+    /// ```
+    pub fn iupac_dna(match_score: i32, mismatch_score: i32) -> Self {
+        // the bases each code stands for, as bits of A, C, G, T
+        const BASES: [u32; 15] = [
+            0b0001, 0b0010, 0b0100, 0b1000, 0b0101, 0b1010, 0b0110, 0b1001, 0b1100, 0b0011, 0b1110, 0b1101, 0b1011,
+            0b0111, 0b1111,
+        ];
+
+        let matrix = Matrix::create("ACGTRYSWKMBDHVN", match_score as i64, mismatch_score as i64);
+        for (i, &row) in BASES.iter().enumerate() {
+            for (j, &col) in BASES.iter().enumerate() {
+                let shared = (row & col).count_ones() as f64;
+                let p = shared / (row.count_ones() * col.count_ones()) as f64;
+                let score = (p * match_score as f64 + (1.0 - p) * mismatch_score as f64).round();
+                unsafe {
+                    parasail_matrix_set_value(matrix.internal_rep as *mut parasail_matrix,
+                                              i as ::std::os::raw::c_int,
+                                              j as ::std::os::raw::c_int,
+                                              score as ::std::os::raw::c_int);
+                }
+            }
+        }
+        matrix
+    }
+
+    /// Parses a matrix in the whitespace separated NCBI/BLAST text format: `#` comment lines, a header line of
+    /// column letters, and one line per row giving the row letter followed by its scores. Rows may come in any
+    /// order, but there must be exactly one for each column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let text = "# a tiny matrix\n   A  C\nA  2 -1\nC -1  3\n";
+    /// let matrix = Matrix::from_ncbi_str(text).unwrap();
+    /// assert_eq!(3, matrix.score(b'C', b'C'));
+    /// assert_eq!(-1, matrix.score(b'A', b'C'));
+    /// ```
+    pub fn from_ncbi_str(text: &str) -> Result<Self, AlignError> {
+        let invalid = |line: usize, reason: &'static str| AlignError::InvalidMatrix { line, reason };
+
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|&(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let (header_line, header) = lines.next().ok_or_else(|| invalid(0, "no column header"))?;
+        let mut columns = Vec::new();
+        for letter in header.split_whitespace() {
+            match letter.as_bytes() {
+                [c] if !columns.contains(c) => columns.push(*c),
+                [_] => return Err(invalid(header_line, "repeated column letter")),
+                _ => return Err(invalid(header_line, "column labels must be single characters")),
+            }
+        }
+        if columns.is_empty() {
+            return Err(invalid(header_line, "no column letters"));
+        }
+
+        let mut rows: Vec<Option<Vec<i32>>> = vec![None; columns.len()];
+        for (number, line) in lines {
+            let mut fields = line.split_whitespace();
+            let letter = fields.next().unwrap_or("");
+            let row = match letter.as_bytes() {
+                [c] => columns
+                    .iter()
+                    .position(|l| l == c)
+                    .ok_or_else(|| invalid(number, "row letter isn't a column"))?,
+                _ => return Err(invalid(number, "row labels must be single characters")),
+            };
+            if rows[row].is_some() {
+                return Err(invalid(number, "repeated row letter"));
+            }
+
+            let scores = fields
+                .map(|field| field.parse::<i32>().map_err(|_| invalid(number, "score isn't an integer")))
+                .collect::<Result<Vec<i32>, AlignError>>()?;
+            if scores.len() != columns.len() {
+                return Err(invalid(number, "row length doesn't match the header"));
+            }
+            rows[row] = Some(scores);
+        }
+
+        let rows = rows
+            .into_iter()
+            .collect::<Option<Vec<Vec<i32>>>>()
+            .ok_or_else(|| invalid(0, "a column letter has no row"))?;
+        Ok(Matrix::from_table(&columns, &rows))
+    }
+
+    /// Builds a custom matrix from a square table of scores, one row and column per letter of `alphabet`.
+    pub(crate) fn from_table(alphabet: &[u8], rows: &[Vec<i32>]) -> Self {
+        let matrix = Matrix::create_bytes(alphabet, 0, 0);
+        for (i, row) in rows.iter().enumerate() {
+            for (j, &score) in row.iter().enumerate() {
+                unsafe {
+                    parasail_matrix_set_value(matrix.internal_rep as *mut parasail_matrix,
+                                              i as ::std::os::raw::c_int,
+                                              j as ::std::os::raw::c_int,
+                                              score);
+                }
+            }
+        }
+        matrix
+    }
+
+    /// An owned, editable copy of the matrix, made with `parasail_matrix_copy`. Built-in matrices are shared and
+    /// read-only, so this is the way to start from one and change a few scores with `set_score`. Position-specific
+    /// matrices are copied too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let mut matrix = Matrix::new(MatrixType::Blosum62).to_owned_mut();
+    /// matrix.set_score(b'X', b'X', 1).unwrap();
+    /// matrix.set_score(b'*', b'*', -4).unwrap();
+    ///
+    /// assert_eq!(1, matrix.score(b'X', b'X'));
+    /// assert_eq!(11, matrix.score(b'W', b'W'));
+    /// ```
+    pub fn to_owned_mut(&self) -> Self {
+        unsafe {
+            Matrix {
+                internal_rep: non_null(parasail_matrix_copy(self.internal_rep), "a matrix").expect(OUT_OF_MEMORY),
+                matrix_type: MatrixType::Custom,
+                ownership: Ownership::Owned,
+                wildcard: self.wildcard,
+            }
+        }
+    }
+
+    /// Sets the score of aligning `a` (in the query) against `b` (in the reference), ignoring case for letters only in
+    /// the alphabet in one case. Only that one
+    /// entry changes, so set `b` against `a` as well to keep the matrix symmetric.
+    ///
+    /// Returns an error if either letter isn't in the matrix's alphabet, or if the matrix is one of parasail's
+    /// shared built-in matrices, which can't be changed; copy it with `to_owned_mut` first.
+    pub fn set_score(&mut self, a: u8, b: u8, score: i32) -> Result<(), AlignError> {
+        if !self.is_owned() {
+            return Err(AlignError::InvalidParameter {
+                reason: "built-in matrices are read-only, copy them with to_owned_mut first",
+            });
+        }
+
+        // an exact match wins, since alphabets may have both cases of a letter as different symbols
+        let index = |letter: u8| {
+            let alphabet = self.alphabet();
+            alphabet
+                .iter()
+                .position(|&l| l == letter)
+                .or_else(|| alphabet.iter().position(|l| l.eq_ignore_ascii_case(&letter)))
+        };
+        match (index(a), index(b)) {
+            (Some(row), Some(col)) => {
+                unsafe {
+                    parasail_matrix_set_value(self.internal_rep as *mut parasail_matrix,
+                                              row as ::std::os::raw::c_int,
+                                              col as ::std::os::raw::c_int,
+                                              score);
+                }
+                Ok(())
+            }
+            _ => Err(AlignError::InvalidParameter {
+                reason: "letter not in the matrix's alphabet",
+            }),
+        }
+    }
+
+    /// Whether this struct allocated the underlying parasail matrix, and so may change and must free it.
+    fn is_owned(&self) -> bool {
+        self.ownership == Ownership::Owned
+    }
+
+    /// Builds a custom matrix over the same alphabet with every score passed through `f`.
+    fn map_scores<F: Fn(i32) -> i32>(&self, f: F) -> Self {
+        let rows: Vec<Vec<i32>> = self
+            .score_table()
+            .into_iter()
+            .map(|row| row.into_iter().map(&f).collect())
+            .collect();
+        Matrix::from_table(self.alphabet(), &rows)
+    }
+
+    /// A copy of the matrix with every score multiplied by `factor` and rounded to the nearest integer.
+    ///
+    /// Scaling up before rounding keeps more resolution from fractional (e.g. half-bit) scores, and scaling down
+    /// converts between units. Gap costs must be scaled by the same factor to keep the same optimal alignments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let blosum62 = Matrix::new(MatrixType::Blosum62);
+    /// let doubled = blosum62.scaled(2.0);
+    /// assert_eq!(8, doubled.score(b'A', b'A'));
+    /// // so open 11 / extend 1 becomes open 22 / extend 2
+    /// ```
+    pub fn scaled(&self, factor: f64) -> Self {
+        self.map_scores(|score| (score as f64 * factor).round() as i32)
+    }
+
+    /// A copy of the matrix with `offset` added to every score, saturating at the limits of `i32`.
+    ///
+    /// Shifting makes every aligned pair worth `offset` more, which favors aligning over gapping. In a global
+    /// alignment each gap column takes the place of half an aligned pair, so lowering both the gap open and gap
+    /// extend costs by `offset / 2` keeps the same optimal global alignments. Local alignments have no such
+    /// correspondence, since shifting also changes how far they extend.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::create("ACGT", 5, -4);
+    /// let shifted = matrix.shifted(-2);
+    /// assert_eq!(3, shifted.score(b'A', b'A'));
+    /// assert_eq!(-6, shifted.score(b'A', b'C'));
+    /// ```
+    pub fn shifted(&self, offset: i32) -> Self {
+        self.map_scores(|score| score.saturating_add(offset))
+    }
+
+    /// A copy of the matrix which tells soft-masked (lowercase) reference letters apart from uppercase ones and
+    /// scores them `penalty` lower, so alignments are discouraged from running through repeat-masked sequence without
+    /// being forbidden from it.
+    ///
+    /// Every uppercase letter of the alphabet gains a lowercase counterpart, if it doesn't have one already. Lowercase
+    /// query letters score like uppercase ones, so only the reference's masking counts. Returns an error if the
+    /// grown alphabet breaks the rules in `Matrix::identity`, which allow at most 127 symbols.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::create("ACGT", 5, -4).soft_masked(3).unwrap();
+    /// assert_eq!(5, matrix.score(b'A', b'A'));
+    /// assert_eq!(2, matrix.score(b'A', b'a'));
+    /// assert_eq!(5, matrix.score(b'a', b'A'));
+    /// assert_eq!(-7, matrix.score(b'A', b'c'));
+    /// ```
+    pub fn soft_masked(&self, penalty: i32) -> Result<Self, AlignError> {
+        let original = self.alphabet().to_vec();
+        let mut alphabet = original.clone();
+        alphabet.extend(
+            original
+                .iter()
+                .filter(|l| l.is_ascii_uppercase() && !original.contains(&l.to_ascii_lowercase()))
+                .map(u8::to_ascii_lowercase),
+        );
+        check_alphabet(&alphabet)?;
+
+        // letters added here look up the score of their uppercase counterpart
+        let unmasked = |letter: u8| if original.contains(&letter) { letter } else { letter.to_ascii_uppercase() };
+        let rows: Vec<Vec<i32>> = alphabet
+            .iter()
+            .map(|&a| {
+                alphabet
+                    .iter()
+                    .map(|&b| {
+                        let score = self.score(unmasked(a), unmasked(b));
+                        if b.is_ascii_lowercase() { score - penalty } else { score }
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Matrix::from_table(&alphabet, &rows))
+    }
+
+    /// The full table of scores, with a row and a column for each letter of `alphabet()`, in the same order. A
+    /// position-specific matrix (see `Profile::with_qualities`) has a row for each query position instead.
+    pub fn score_table(&self) -> Vec<Vec<i32>> {
+        let alphabet = self.alphabet();
+        match self.positions() {
+            Some(positions) => (0..positions)
+                .map(|row| alphabet.iter().map(|&b| self.lookup(row, b)).collect())
+                .collect(),
+            None => alphabet
+                .iter()
+                .map(|&a| alphabet.iter().map(|&b| self.score(a, b)).collect())
+                .collect(),
+        }
+    }
+
+    /// The number of query positions a position-specific matrix has rows for, or `None` for an ordinary matrix with
+    /// a row per letter.
+    fn positions(&self) -> Option<usize> {
+        let matrix = unsafe { &*self.internal_rep };
+        if matrix.type_ == MATRIX_TYPE_PSSM {
+            Some(matrix.length as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The score in row `row` of parasail's table for the column of character `b`.
+    fn lookup(&self, row: usize, b: u8) -> i32 {
+        unsafe {
+            let matrix = &*self.internal_rep;
+            let col = *matrix.mapper.offset(b as isize);
+            *matrix.matrix.offset(row as isize * matrix.size as isize + col as isize)
+        }
+    }
+
+    /// Reads a matrix in the NCBI/BLAST text format from a file, as described for `from_ncbi_str`. A file which
+    /// can't be parsed gives an error of kind `InvalidData`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Matrix::from_ncbi_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Formats the matrix in the NCBI/BLAST text format, with right-aligned columns, so it can be saved and read back
+    /// with `from_ncbi_str` or by other tools.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::create("ACGT", 5, -4);
+    /// let text = matrix.to_ncbi_string();
+    /// assert!(text.contains("A  5 -4 -4 -4"));
+    /// ```
+    pub fn to_ncbi_string(&self) -> String {
+        let alphabet = self.alphabet();
+        let scores = self.score_table();
+        let width = scores.iter().flatten().map(|score| score.to_string().len()).max().unwrap_or(1) + 1;
+
+        let mut text = String::from("# written by parasailors\n ");
+        for &letter in alphabet {
+            let _ = write!(text, "{:>width$}", letter as char, width = width);
+        }
+        text.push('\n');
+
+        for (&letter, row) in alphabet.iter().zip(&scores) {
+            text.push(letter as char);
+            for score in row {
+                let _ = write!(text, "{:>width$}", score, width = width);
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Writes the matrix to a file in the NCBI/BLAST text format, as produced by `to_ncbi_string`.
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_ncbi_string())
+    }
+
+    /// The characters the matrix has scores for, in the order of its rows and columns. The `*` wildcard parasail adds
+    /// to matrices built from an alphabet (by `Matrix::create`, `Matrix::identity`, and the like) isn't included, so
+    /// this is the alphabet the matrix was built from. Characters outside it still score as the wildcard does.
+    pub fn alphabet(&self) -> &[u8] {
+        let alphabet = unsafe { CStr::from_ptr((*self.internal_rep).alphabet).to_bytes() };
+        if self.wildcard {
+            &alphabet[..alphabet.len() - 1]
+        } else {
+            alphabet
+        }
+    }
+
+    /// The recommended `(open, extend)` gap costs for this matrix, or `None` for custom matrices. See
+    /// `MatrixType::default_gaps`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let blosum62 = Matrix::new(MatrixType::Blosum62);
+    /// let (open, extend) = blosum62.default_gaps().unwrap();
+    /// assert_eq!((11, 1), (open, extend));
+    /// ```
+    pub fn default_gaps(&self) -> Option<(i32, i32)> {
+        self.matrix_type.default_gaps()
+    }
+
+    /// Which kind of matrix this is.
+    pub(crate) fn matrix_type(&self) -> &MatrixType {
+        &self.matrix_type
+    }
+
+    /// A hash of the matrix's alphabet and scores which stays the same across runs, platforms, and versions of this
+    /// crate, so archived results can record exactly which scores produced them and be checked against a matrix
+    /// later. Two matrices have the same fingerprint whenever they're equal, whatever their name or origin: a
+    /// BLOSUM62 read from a file matches the built-in one.
+    ///
+    /// This is the 64-bit FNV-1a hash of the alphabet's length and bytes followed by every score in `score_table`
+    /// order, as little-endian 32-bit integers. For a position-specific matrix, the alphabet is preceded by a `0xff`
+    /// byte, so it can't collide with an ordinary matrix whose table happens to have the same scores.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let blosum62 = Matrix::new(MatrixType::Blosum62);
+    /// let copy = Matrix::from_ncbi_str(&blosum62.to_ncbi_string()).unwrap();
+    /// assert_eq!(blosum62.fingerprint(), copy.fingerprint());
+    /// assert_eq!(blosum62, copy);
+    ///
+    /// let shifted = blosum62.shifted(1);
+    /// assert_ne!(blosum62.fingerprint(), shifted.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let alphabet = self.alphabet();
+        let scores = self.score_table().into_iter().flatten().flat_map(i32::to_le_bytes);
+        let length = (alphabet.len() as u32).to_le_bytes();
+        let marker = self.positions().map(|_| 0xff);
+        let bytes = marker.into_iter().chain(length.iter().chain(alphabet).copied()).chain(scores);
+        bytes.fold(FNV_OFFSET, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+    }
+
+    /// Looks up the score for substituting one character for another. Characters outside the matrix's alphabet
+    /// are scored the same way parasail scores them during alignment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let blosum62 = Matrix::new(MatrixType::Blosum62);
+    /// assert_eq!(4, blosum62.score(b'A', b'A'));
+    /// assert_eq!(-1, blosum62.score(b'A', b'R'));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics for a position-specific matrix (see `Profile::with_qualities`), whose rows are query positions rather
+    /// than letters. Its scores are in `score_table`.
+    pub fn score(&self, a: u8, b: u8) -> i32 {
+        assert!(self.positions().is_none(), "position-specific matrices don't score pairs of letters");
+        let row = unsafe { *(*self.internal_rep).mapper.offset(a as isize) };
+        self.lookup(row as usize, b)
+    }
+}
+
+impl Matrix {
+    /// Builds a position-specific scoring matrix for `query` from this matrix and Phred+33 base qualities.
+    ///
+    /// A base with quality `Q` is wrong with probability `p = 10^(-Q/10)`, in which case it's taken to be any other
+    /// letter of the alphabet with equal chance. Each position scores a reference letter with the expected
+    /// substitution score under that model (rounded to the nearest integer), so confident bases score as usual and
+    /// doubtful ones count for little either way.
+    pub(crate) fn quality_weighted(&self, query: &[u8], qualities: &[u8]) -> Matrix {
+        unsafe {
+            let matrix = &*self.internal_rep;
+            let alphabet = CStr::from_ptr(matrix.alphabet);
+            let letters = alphabet.to_bytes();
+
+            let mut values = Vec::with_capacity(query.len() * letters.len());
+            for (&base, &quality) in query.iter().zip(qualities) {
+                let error = 10f64.powf(-(quality.saturating_sub(33) as f64) / 10.0);
+                let others = letters.iter().filter(|&&l| !l.eq_ignore_ascii_case(&base)).count().max(1);
+
+                for &target in letters {
+                    let called = self.score(base, target) as f64;
+                    let miscalled: i32 = letters
+                        .iter()
+                        .filter(|&&l| !l.eq_ignore_ascii_case(&base))
+                        .map(|&l| self.score(l, target))
+                        .sum();
+                    let expected = (1.0 - error) * called + error * miscalled as f64 / others as f64;
+                    values.push(expected.round() as ::std::os::raw::c_int);
+                }
+            }
+
+            let pssm = parasail_matrix_pssm_create(alphabet.as_ptr(), values.as_ptr(), query.len() as ::std::os::raw::c_int);
+            Matrix {
+                internal_rep: non_null(pssm, "a position-specific matrix").expect(OUT_OF_MEMORY),
+                matrix_type: MatrixType::Custom,
+                ownership: Ownership::Owned,
+                wildcard: self.wildcard,
             }
         }
     }
 }
 
+impl fmt::Debug for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Matrix")
+            .field("matrix_type", &self.matrix_type)
+            .field("alphabet", &String::from_utf8_lossy(self.alphabet()))
+            .finish()
+    }
+}
+
+/// Matrices are equal when they have the same alphabet in the same order and the same scores, whatever their type.
+/// A position-specific matrix is only ever equal to another position-specific matrix.
+impl PartialEq for Matrix {
+    fn eq(&self, other: &Matrix) -> bool {
+        self.positions().is_some() == other.positions().is_some()
+            && self.alphabet() == other.alphabet()
+            && self.score_table() == other.score_table()
+    }
+}
+
+impl Eq for Matrix {}
+
+impl Hash for Matrix {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fingerprint().hash(state);
+    }
+}
+
 #[doc(hidden)]
 impl Deref for Matrix {
     type Target = *const parasail_matrix;
@@ -203,21 +817,14 @@ impl Deref for Matrix {
 #[doc(hidden)]
 impl Drop for Matrix {
     fn drop(&mut self) {
-        if let MatrixType::Identity = self.matrix_type {
-            unsafe { parasail_matrix_free(self.internal_rep as *mut parasail_matrix) }
-        }
-
-        if let MatrixType::IdentityWithPenalty = self.matrix_type {
-            unsafe { parasail_matrix_free(self.internal_rep as *mut parasail_matrix) }
-        }
-
-        if let MatrixType::Custom = self.matrix_type {
+        if self.is_owned() {
             unsafe { parasail_matrix_free(self.internal_rep as *mut parasail_matrix) }
         }
     }
 }
 
 /// Denotes the type of the substitution matrix. Use Identity for simple edit-distance calculations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MatrixType {
     /// The identity matrix awards 1 score for each direct match, and 0 score for each mismatch.
     Identity,
@@ -362,3 +969,282 @@ pub enum MatrixType {
     /// Custom matrix
     Custom
 }
+
+/// The name of each matrix type, in the order they're listed in errors and by `available_matrices`.
+const NAMES: [(MatrixType, &str); 70] = [
+    (MatrixType::Identity, "identity"),
+    (MatrixType::IdentityWithPenalty, "identity_with_penalty"),
+    (MatrixType::AdaptorSearch, "adaptor_search"),
+    (MatrixType::DNAFull, "dnafull"),
+    (MatrixType::Nuc44, "nuc44"),
+    (MatrixType::Blosum30, "blosum30"),
+    (MatrixType::Blosum35, "blosum35"),
+    (MatrixType::Blosum40, "blosum40"),
+    (MatrixType::Blosum45, "blosum45"),
+    (MatrixType::Blosum50, "blosum50"),
+    (MatrixType::Blosum55, "blosum55"),
+    (MatrixType::Blosum60, "blosum60"),
+    (MatrixType::Blosum62, "blosum62"),
+    (MatrixType::Blosum65, "blosum65"),
+    (MatrixType::Blosum70, "blosum70"),
+    (MatrixType::Blosum75, "blosum75"),
+    (MatrixType::Blosum80, "blosum80"),
+    (MatrixType::Blosum85, "blosum85"),
+    (MatrixType::Blosum90, "blosum90"),
+    (MatrixType::Blosum100, "blosum100"),
+    (MatrixType::Pam10, "pam10"),
+    (MatrixType::Pam20, "pam20"),
+    (MatrixType::Pam30, "pam30"),
+    (MatrixType::Pam40, "pam40"),
+    (MatrixType::Pam50, "pam50"),
+    (MatrixType::Pam60, "pam60"),
+    (MatrixType::Pam70, "pam70"),
+    (MatrixType::Pam80, "pam80"),
+    (MatrixType::Pam90, "pam90"),
+    (MatrixType::Pam100, "pam100"),
+    (MatrixType::Pam110, "pam110"),
+    (MatrixType::Pam120, "pam120"),
+    (MatrixType::Pam130, "pam130"),
+    (MatrixType::Pam140, "pam140"),
+    (MatrixType::Pam150, "pam150"),
+    (MatrixType::Pam160, "pam160"),
+    (MatrixType::Pam170, "pam170"),
+    (MatrixType::Pam180, "pam180"),
+    (MatrixType::Pam190, "pam190"),
+    (MatrixType::Pam200, "pam200"),
+    (MatrixType::Pam210, "pam210"),
+    (MatrixType::Pam220, "pam220"),
+    (MatrixType::Pam230, "pam230"),
+    (MatrixType::Pam240, "pam240"),
+    (MatrixType::Pam250, "pam250"),
+    (MatrixType::Pam260, "pam260"),
+    (MatrixType::Pam270, "pam270"),
+    (MatrixType::Pam280, "pam280"),
+    (MatrixType::Pam290, "pam290"),
+    (MatrixType::Pam300, "pam300"),
+    (MatrixType::Pam310, "pam310"),
+    (MatrixType::Pam320, "pam320"),
+    (MatrixType::Pam330, "pam330"),
+    (MatrixType::Pam340, "pam340"),
+    (MatrixType::Pam350, "pam350"),
+    (MatrixType::Pam360, "pam360"),
+    (MatrixType::Pam370, "pam370"),
+    (MatrixType::Pam380, "pam380"),
+    (MatrixType::Pam390, "pam390"),
+    (MatrixType::Pam400, "pam400"),
+    (MatrixType::Pam410, "pam410"),
+    (MatrixType::Pam420, "pam420"),
+    (MatrixType::Pam430, "pam430"),
+    (MatrixType::Pam440, "pam440"),
+    (MatrixType::Pam450, "pam450"),
+    (MatrixType::Pam460, "pam460"),
+    (MatrixType::Pam470, "pam470"),
+    (MatrixType::Pam480, "pam480"),
+    (MatrixType::Pam490, "pam490"),
+    (MatrixType::Pam500, "pam500"),
+];
+
+impl MatrixType {
+    /// The lowercase name of the matrix type, as parasail knows the built-in matrices (`"blosum62"`, `"pam250"`,
+    /// `"dnafull"`, ...). The identity and adaptor matrices, which parasailors builds itself, are `"identity"`,
+    /// `"identity_with_penalty"`, and `"adaptor_search"`, and `Custom` is `"custom"`.
+    pub fn name(&self) -> &'static str {
+        NAMES.iter().find(|&&(t, _)| t == *self).map(|&(_, name)| name).unwrap_or("custom")
+    }
+
+    /// The recommended `(open, extend)` gap costs to use with this matrix, or `None` for `Custom`.
+    ///
+    /// BLOSUM and PAM matrices get NCBI BLAST's defaults where BLAST supports the matrix (BLOSUM45 14/2, BLOSUM50
+    /// 13/2, BLOSUM62 11/1, BLOSUM80 and BLOSUM90 10/1, PAM30 9/1, PAM70 10/1, PAM250 14/2), and the value of the
+    /// nearest supported matrix in the same series otherwise. The 5/-4 nucleotide matrices get EMBOSS's 10 to open
+    /// with the extension rounded up to 1, the 1/-2 adaptor matrix gets BLASTN's 5/2, and the identity matrices get
+    /// the unit costs that make their scores behave like edit distances.
+    pub fn default_gaps(&self) -> Option<(i32, i32)> {
+        let gaps = match *self {
+            MatrixType::Identity | MatrixType::IdentityWithPenalty => (1, 1),
+            MatrixType::AdaptorSearch => (5, 2),
+            MatrixType::DNAFull | MatrixType::Nuc44 => (10, 1),
+            MatrixType::Blosum30 | MatrixType::Blosum35 | MatrixType::Blosum40 | MatrixType::Blosum45 => (14, 2),
+            MatrixType::Blosum50 | MatrixType::Blosum55 => (13, 2),
+            MatrixType::Blosum60 | MatrixType::Blosum62 | MatrixType::Blosum65 | MatrixType::Blosum70 => (11, 1),
+            MatrixType::Blosum75
+            | MatrixType::Blosum80
+            | MatrixType::Blosum85
+            | MatrixType::Blosum90
+            | MatrixType::Blosum100 => (10, 1),
+            MatrixType::Pam10 | MatrixType::Pam20 | MatrixType::Pam30 | MatrixType::Pam40 | MatrixType::Pam50 => (9, 1),
+            MatrixType::Pam60
+            | MatrixType::Pam70
+            | MatrixType::Pam80
+            | MatrixType::Pam90
+            | MatrixType::Pam100
+            | MatrixType::Pam110
+            | MatrixType::Pam120
+            | MatrixType::Pam130
+            | MatrixType::Pam140
+            | MatrixType::Pam150
+            | MatrixType::Pam160 => (10, 1),
+            MatrixType::Custom => return None,
+            // PAM170 and up are closest to PAM250
+            _ => (14, 2),
+        };
+        Some(gaps)
+    }
+}
+
+/// Checks that an alphabet can back a parasail matrix: at least one symbol, no repeats, and no NUL byte, since
+/// parasail takes the alphabet as a C string. Symbols must also be ASCII, since parasail's tracebacks are read as
+/// strings. That leaves room for 127 symbols.
+fn check_alphabet(alphabet: &[u8]) -> Result<(), AlignError> {
+    let invalid = |reason: &'static str| Err(AlignError::InvalidParameter { reason });
+
+    if alphabet.is_empty() {
+        return invalid("alphabet must have at least one symbol");
+    }
+    if alphabet.contains(&0) {
+        return invalid("alphabet can't contain a NUL byte");
+    }
+    if !alphabet.is_ascii() {
+        return invalid("alphabet symbols must be ASCII");
+    }
+    let mut seen = [false; 256];
+    for &symbol in alphabet {
+        if std::mem::replace(&mut seen[symbol as usize], true) {
+            return invalid("alphabet repeats a symbol");
+        }
+    }
+    Ok(())
+}
+
+/// Every matrix `Matrix::new` can build: the identity and adaptor matrices, then parasail's bundled nucleotide,
+/// BLOSUM, and PAM matrices.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let names: Vec<&str> = available_matrices().iter().map(|t| t.name()).collect();
+/// assert!(names.contains(&"dnafull"));
+/// assert!(names.contains(&"pam500"));
+/// ```
+pub fn available_matrices() -> Vec<MatrixType> {
+    NAMES.iter().map(|&(matrix_type, _)| matrix_type).collect()
+}
+
+impl fmt::Display for MatrixType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses the names `MatrixType::name` gives, ignoring case and treating `-` like `_`, so `"BLOSUM62"` and
+/// `"identity-with-penalty"` are accepted. `"custom"` isn't, since a custom matrix can't be built from its name.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix_type: MatrixType = "BLOSUM62".parse().unwrap();
+/// assert_eq!(MatrixType::Blosum62, matrix_type);
+/// assert_eq!("blosum62", matrix_type.to_string());
+///
+/// let error = "blosum63".parse::<MatrixType>().unwrap_err();
+/// assert!(error.to_string().contains("blosum62"));
+/// ```
+impl FromStr for MatrixType {
+    type Err = ParseMatrixTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_ascii_lowercase().replace('-', "_");
+        NAMES
+            .iter()
+            .find(|&&(_, name)| name == normalized)
+            .map(|&(matrix_type, _)| matrix_type)
+            .ok_or_else(|| ParseMatrixTypeError { name: s.to_string() })
+    }
+}
+
+/// The name given to `MatrixType::from_str` isn't a known matrix.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("unknown matrix '{name}', expected one of: {}", known_names())]
+pub struct ParseMatrixTypeError {
+    /// The name that couldn't be parsed.
+    pub name: String,
+}
+
+// the names `MatrixType::from_str` accepts, for error messages
+fn known_names() -> String {
+    NAMES.iter().map(|&(_, name)| name).collect::<Vec<_>>().join(", ")
+}
+
+#[test]
+fn test_ncbi_round_trip() {
+    let original = Matrix::iupac_dna(5, -4);
+    let path = std::env::temp_dir().join(format!("parasailors-matrix-{}.txt", std::process::id()));
+    original.write_to(&path).unwrap();
+    let loaded = Matrix::from_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(original.alphabet(), loaded.alphabet());
+    for &a in original.alphabet() {
+        for &b in original.alphabet() {
+            assert_eq!(original.score(a, b), loaded.score(a, b));
+        }
+    }
+    assert_eq!(original.to_ncbi_string(), loaded.to_ncbi_string());
+
+    let blosum62 = Matrix::new(MatrixType::Blosum62);
+    let reparsed = Matrix::from_ncbi_str(&blosum62.to_ncbi_string()).unwrap();
+    assert_eq!(blosum62.score(b'W', b'W'), reparsed.score(b'W', b'W'));
+    assert_eq!(blosum62.score(b'A', b'R'), reparsed.score(b'A', b'R'));
+
+    assert!(Matrix::from_ncbi_str("   A  C\nA  1\nC  0  1\n").is_err());
+}
+
+#[test]
+fn test_matrix_type_names_round_trip() {
+    for &(matrix_type, name) in NAMES.iter() {
+        assert_eq!(name, matrix_type.to_string());
+        assert_eq!(Ok(matrix_type), name.parse());
+        assert_eq!(Ok(matrix_type), name.to_uppercase().replace('_', "-").parse());
+    }
+    assert_eq!("custom", MatrixType::Custom.to_string());
+    assert!("custom".parse::<MatrixType>().is_err());
+}
+
+#[test]
+fn test_every_kind_of_matrix_drops_cleanly() {
+    // run under Miri or a sanitizer to catch double frees and leaks
+    for matrix_type in available_matrices() {
+        let matrix = Matrix::new(matrix_type);
+        let copy = matrix.to_owned_mut();
+        assert_eq!(matrix.alphabet(), copy.alphabet());
+        assert!(copy.is_owned());
+    }
+    assert!(Matrix::new(MatrixType::Identity).is_owned());
+    assert!(Matrix::new(MatrixType::AdaptorSearch).is_owned());
+    assert!(!Matrix::new(MatrixType::Blosum62).is_owned());
+
+    let _ = Matrix::create("ACGT", 2, -3);
+    let _ = Matrix::iupac_dna(5, -4);
+    let _ = Matrix::from_ncbi_str("   A  C\nA  1 -1\nC -1  1\n").unwrap();
+    let _ = Matrix::from_name("pam250").unwrap();
+    let _ = Matrix::new(MatrixType::Blosum62).scaled(2.0);
+    let _ = Matrix::new(MatrixType::DNAFull).quality_weighted(b"ACGT", b"II#I");
+}
+
+#[test]
+fn test_position_specific_score_table() {
+    let matrix = Matrix::create("ACGT", 5, -4);
+    let weighted = matrix.quality_weighted(b"ACG", b"III");
+    let table = weighted.score_table();
+
+    // a row per query position, each scoring like the matrix's row for the base there
+    assert_eq!(3, table.len());
+    assert_eq!(vec![5, -4, -4, -4], table[0][..4].to_vec());
+    assert_eq!(vec![-4, -4, 5, -4], table[2][..4].to_vec());
+
+    assert_eq!(weighted, matrix.quality_weighted(b"ACG", b"III"));
+    assert_ne!(weighted, matrix.quality_weighted(b"ACG", b"II#"));
+    assert_ne!(weighted.fingerprint(), matrix.fingerprint());
+}
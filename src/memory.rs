@@ -0,0 +1,111 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::batch::AlignmentMode;
+
+/// Which of parasail's result flavors an alignment produces, since they differ hugely in how much memory they need.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResultKind {
+    /// Only the score and end positions, as from the `*_score` functions.
+    Score,
+    /// The score plus match, similarity, and length counts, as from the `*_stats` functions.
+    Stats,
+    /// The score along with the whole dynamic programming table.
+    Table,
+    /// The statistics along with a whole table for each of them.
+    StatsTable,
+    /// The score along with the last row and column of the table.
+    RowCol,
+    /// The statistics along with the last row and column of each of their tables.
+    StatsRowCol,
+    /// The score along with a traceback table, as from the `*_traceback` functions.
+    Trace,
+}
+
+/// The details of an alignment call which affect its memory use, beyond the sequence lengths.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryOptions {
+    /// What the alignment produces.
+    pub result: ResultKind,
+    /// The size in bytes of each score in the vectorized kernels. The saturation-checked (`_sat`) functions start at
+    /// 1 and retry at 2 if the score overflows, so 2 covers both.
+    pub score_bytes: usize,
+    /// The width in bytes of a SIMD vector: 16 for SSE and NEON, 32 for AVX2.
+    pub vector_bytes: usize,
+    /// The number of letters in the substitution matrix's alphabet.
+    pub alphabet_size: usize,
+}
+
+impl Default for MemoryOptions {
+    /// A score-only alignment, sized for the worst case of the saturation-checked kernels on AVX2 with a protein
+    /// matrix.
+    fn default() -> Self {
+        MemoryOptions {
+            result: ResultKind::Score,
+            score_bytes: 2,
+            vector_bytes: 32,
+            alphabet_size: 24,
+        }
+    }
+}
+
+/// Estimates the peak number of bytes parasail allocates for one striped alignment of a `query_len` query against a
+/// `ref_len` reference, so that oversized requests can be turned away before calling into C.
+///
+/// The estimate adds up the query profile (one padded copy of the query per alphabet letter, four of them for
+/// statistics), the vectors of the dynamic programming columns, and whatever the result carries: `4 * query_len *
+/// ref_len` bytes per table for table results, 4 bytes per row and column for row/column results, and a byte per
+/// padded cell plus the traceback strings for trace results. Table and trace results grow with the product of the
+/// lengths and dominate everything else for long sequences. Allocator overhead isn't counted, and an estimate too
+/// large for a `usize` comes out as `usize::MAX`.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let options = MemoryOptions { result: ResultKind::Trace, ..MemoryOptions::default() };
+/// let bytes = estimate_memory(100_000, 100_000, AlignmentMode::Global, &options);
+/// assert!(bytes > 10_000_000_000);
+///
+/// let bytes = estimate_memory(100_000, 100_000, AlignmentMode::Global, &MemoryOptions::default());
+/// assert!(bytes < 100_000_000);
+///
+/// let options = MemoryOptions { result: ResultKind::StatsTable, ..MemoryOptions::default() };
+/// assert_eq!(usize::MAX, estimate_memory(usize::MAX / 2, 1 << 20, AlignmentMode::Global, &options));
+/// ```
+pub fn estimate_memory(query_len: usize, ref_len: usize, mode: AlignmentMode, options: &MemoryOptions) -> usize {
+    let score_bytes = options.score_bytes.max(1);
+    let lanes = (options.vector_bytes / score_bytes).max(1);
+    // striped kernels pad the query up to a whole number of vectors
+    let padded_query = query_len.div_ceil(lanes).saturating_mul(lanes);
+    // sizes past the address space can't be allocated anyway, so they all come out as usize::MAX
+    let product = |factors: &[usize]| factors.iter().fold(1usize, |total, &factor| total.saturating_mul(factor));
+
+    let with_stats = match options.result {
+        ResultKind::Stats | ResultKind::StatsTable | ResultKind::StatsRowCol => true,
+        ResultKind::Score | ResultKind::Table | ResultKind::RowCol | ResultKind::Trace => false,
+    };
+    let tracked_values = if with_stats { 4 } else { 1 };
+
+    let profile = product(&[padded_query, options.alphabet_size, score_bytes, tracked_values]);
+    // H, E, and the previous H for each tracked value, plus the running maximum
+    let columns = product(&[padded_query, score_bytes, 3 * tracked_values + 1]);
+    // semi-global and local alignments keep the best score seen along the reference
+    let ref_row = match mode {
+        AlignmentMode::Global => 0,
+        AlignmentMode::SemiGlobal | AlignmentMode::Local => product(&[ref_len, score_bytes]),
+    };
+
+    let result = match options.result {
+        ResultKind::Score | ResultKind::Stats => 0,
+        ResultKind::Table | ResultKind::StatsTable => product(&[4, query_len, ref_len, tracked_values]),
+        ResultKind::RowCol | ResultKind::StatsRowCol => product(&[4, query_len.saturating_add(ref_len), tracked_values]),
+        // a direction byte per padded cell, and the query, comparison, and reference strings
+        ResultKind::Trace => product(&[padded_query, ref_len])
+            .saturating_add(product(&[3, query_len.saturating_add(ref_len).saturating_add(1)])),
+    };
+
+    profile.saturating_add(columns).saturating_add(ref_row).saturating_add(result)
+}
@@ -0,0 +1,423 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::chain::Anchor;
+
+/// A k-mer chosen as the smallest (by hash) in its window of consecutive k-mers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Minimizer {
+    /// The hash of the k-mer.
+    pub hash: u64,
+    /// The index (0-based) of the first base of the k-mer.
+    pub pos: usize,
+}
+
+/// Computes the `(w, k)` minimizers of a DNA sequence: for every window of `w` consecutive k-mers, the one with the
+/// smallest hash (the leftmost, on ties), reported once even if it's the smallest in several windows.
+///
+/// Bases are read case-insensitively, and k-mers containing anything other than `A`, `C`, `G`, or `T` are skipped.
+///
+/// # Panics
+///
+/// Panics if `k` is 0 or more than 32, or if `w` is 0.
+pub fn minimizers(sequence: &[u8], k: usize, w: usize) -> Vec<Minimizer> {
+    assert!(k > 0 && k <= 32, "k must be between 1 and 32");
+    assert!(w > 0, "w must be at least 1");
+
+    let hashes = kmer_hashes(sequence, k);
+    let mut found: Vec<Minimizer> = Vec::new();
+    if hashes.is_empty() {
+        return found;
+    }
+
+    for start in 0..hashes.len().saturating_sub(w - 1).max(1) {
+        let end = (start + w).min(hashes.len());
+        let best = (start..end)
+            .filter_map(|pos| hashes[pos].map(|hash| Minimizer { hash, pos }))
+            .min_by_key(|m| (m.hash, m.pos));
+
+        if let Some(best) = best {
+            if found.last() != Some(&best) {
+                found.push(best);
+            }
+        }
+    }
+
+    found
+}
+
+/// The hash of each k-mer of the sequence, by starting position, or `None` for k-mers with ambiguous bases.
+fn kmer_hashes(sequence: &[u8], k: usize) -> Vec<Option<u64>> {
+    if sequence.len() < k {
+        return Vec::new();
+    }
+
+    let mask = if k == 32 { u64::MAX } else { (1 << (2 * k)) - 1 };
+    let mut hashes = Vec::with_capacity(sequence.len() - k + 1);
+    let (mut kmer, mut valid) = (0u64, 0);
+
+    for (i, &base) in sequence.iter().enumerate() {
+        let code = match base.to_ascii_uppercase() {
+            b'A' => 0,
+            b'C' => 1,
+            b'G' => 2,
+            b'T' => 3,
+            _ => {
+                valid = 0;
+                if i + 1 >= k {
+                    hashes.push(None);
+                }
+                continue;
+            }
+        };
+        kmer = ((kmer << 2) | code) & mask;
+        valid += 1;
+
+        if i + 1 >= k {
+            hashes.push(if valid >= k { Some(mix(kmer, mask)) } else { None });
+        }
+    }
+
+    hashes
+}
+
+/// Thomas Wang's invertible integer hash, as used by minimap2, so that minimizers aren't biased toward poly-A.
+pub(crate) fn mix(key: u64, mask: u64) -> u64 {
+    let mut key = (!key).wrapping_add(key << 21) & mask;
+    key ^= key >> 24;
+    key = (key.wrapping_add(key << 3)).wrapping_add(key << 8) & mask;
+    key ^= key >> 14;
+    key = (key.wrapping_add(key << 2)).wrapping_add(key << 4) & mask;
+    key ^= key >> 28;
+    key.wrapping_add(key << 31) & mask
+}
+
+/// A region of a reference sharing minimizers with a query, worth aligning the query against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// The index of the reference in the indexed database.
+    pub ref_id: usize,
+    /// The starting index (0-based) of the region in the reference.
+    pub ref_begin: usize,
+    /// The ending index (0-based, exclusive) of the region in the reference.
+    pub ref_end: usize,
+    /// The shared k-mers supporting this region, in reference order, each scored by its length. These can be handed
+    /// straight to `chain_anchors`.
+    pub seeds: Vec<Anchor>,
+}
+
+/// A minimizer index over a database of DNA references, for finding where a query is likely to align before running
+/// any dynamic programming.
+pub struct MinimizerIndex {
+    k: usize,
+    w: usize,
+    ref_lengths: Vec<usize>,
+    // (reference, position) of every occurrence of each minimizer
+    occurrences: HashMap<u64, Vec<(usize, usize)>>,
+}
+
+impl MinimizerIndex {
+    /// Indexes the `(w, k)` minimizers of every reference.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0 or more than 32, or if `w` is 0.
+    pub fn new(references: &[&[u8]], k: usize, w: usize) -> Self {
+        MinimizerIndex::build(references, k, w, false)
+    }
+
+    /// Indexes the `(w, k)` minimizers of every reference, skipping soft-masked regions: minimizers whose k-mer
+    /// contains a lowercase base aren't indexed, so queries can't seed in repeat-masked sequence, as with BLAST's
+    /// soft masking. Queries can still align across masked regions once seeded elsewhere.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0 or more than 32, or if `w` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let references: Vec<&[u8]> = vec![b"ttgaccatgagctaggcttacgatcgGATCCTAGCATCGACTAGCGGCATTACAG"];
+    /// let index = MinimizerIndex::new_soft_masked(&references, 7, 3);
+    ///
+    /// assert!(index.candidates(b"ttgaccatgagctaggcttacg", 1).is_empty());
+    /// assert_eq!(1, index.candidates(b"GATCCTAGCATCGACTAGCGG", 1).len());
+    /// ```
+    pub fn new_soft_masked(references: &[&[u8]], k: usize, w: usize) -> Self {
+        MinimizerIndex::build(references, k, w, true)
+    }
+
+    fn build(references: &[&[u8]], k: usize, w: usize, skip_masked: bool) -> Self {
+        let mut occurrences: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+        for (ref_id, reference) in references.iter().enumerate() {
+            for m in minimizers(reference, k, w) {
+                if skip_masked && reference[m.pos..m.pos + k].iter().any(u8::is_ascii_lowercase) {
+                    continue;
+                }
+                occurrences.entry(m.hash).or_default().push((ref_id, m.pos));
+            }
+        }
+
+        MinimizerIndex {
+            k,
+            w,
+            ref_lengths: references.iter().map(|r| r.len()).collect(),
+            occurrences,
+        }
+    }
+
+    /// The k-mer length of the index.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The window size (in k-mers) of the index.
+    pub fn w(&self) -> usize {
+        self.w
+    }
+
+    /// Finds regions of the references the query probably aligns to.
+    ///
+    /// Every minimizer shared by the query and a reference is a seed on some diagonal (reference position minus query
+    /// position). Seeds are grouped by reference and nearby diagonal, allowing drift of up to an eighth of the query
+    /// length (at least `w + k`) for indels, and groups with at least `min_seeds` seeds become candidates. Each
+    /// region covers the whole query as placed by its outermost diagonals, clipped to the reference. Candidates are
+    /// ordered by number of seeds, most first, and then by reference and position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let references: Vec<&[u8]> = vec![
+    ///     b"TTGACCATGAGCTAGGCTTACGATCGGATCCTAGCATCGACTAGCGGCATTACAG",
+    ///     b"GGCATCGATCAGCGACTTAGGCAATCTAGCGCGATATCGCTAGGACTCAGCAAT",
+    /// ];
+    /// let index = MinimizerIndex::new(&references, 7, 3);
+    ///
+    /// let candidates = index.candidates(b"GATCAGCGACTTAGGCAATCTAGC", 2);
+    /// assert_eq!(1, candidates[0].ref_id);
+    /// assert_eq!(6, candidates[0].ref_begin);
+    /// assert_eq!(30, candidates[0].ref_end);
+    /// ```
+    pub fn candidates(&self, query: &[u8], min_seeds: usize) -> Vec<Candidate> {
+        // (reference, diagonal, query position, reference position)
+        let mut hits: Vec<(usize, i64, usize, usize)> = Vec::new();
+        for m in minimizers(query, self.k, self.w) {
+            if let Some(occurrences) = self.occurrences.get(&m.hash) {
+                for &(ref_id, pos) in occurrences {
+                    hits.push((ref_id, pos as i64 - m.pos as i64, m.pos, pos));
+                }
+            }
+        }
+        hits.sort_unstable();
+
+        let drift = ((query.len() / 8).max(self.w + self.k)) as i64;
+        let mut candidates = Vec::new();
+        let mut group_start = 0;
+        for i in 1..=hits.len() {
+            let splits = i == hits.len() || hits[i].0 != hits[i - 1].0 || hits[i].1 - hits[i - 1].1 > drift;
+            if splits {
+                if i - group_start >= min_seeds.max(1) {
+                    candidates.push(self.candidate(&hits[group_start..i], query.len()));
+                }
+                group_start = i;
+            }
+        }
+
+        candidates.sort_by(|a, b| {
+            b.seeds
+                .len()
+                .cmp(&a.seeds.len())
+                .then(a.ref_id.cmp(&b.ref_id))
+                .then(a.ref_begin.cmp(&b.ref_begin))
+        });
+        candidates
+    }
+
+    /// Writes the index in a compact binary format, so it can be loaded with `read` instead of being built again.
+    ///
+    /// The format is little-endian and versioned, and lists minimizers in hash order, so the same index always gives
+    /// the same bytes. The reference sequences themselves aren't included. Query profiles aren't worth saving
+    /// alongside: they're quick to build from the query, and tied to the SIMD layout of the machine building them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let references: Vec<&[u8]> = vec![b"TTGACCATGAGCTAGGCTTACGATCGGATCCTAGCATCGACTAGCGGCATTACAG"];
+    /// let index = MinimizerIndex::new(&references, 7, 3);
+    ///
+    /// let mut bytes = Vec::new();
+    /// index.write(&mut bytes).unwrap();
+    /// let loaded = MinimizerIndex::read(&bytes[..]).unwrap();
+    ///
+    /// let query = b"GATCCTAGCATCGACTAGCGG";
+    /// assert_eq!(index.candidates(query, 1), loaded.candidates(query, 1));
+    /// ```
+    pub fn write<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(INDEX_MAGIC)?;
+        write_u64(&mut out, INDEX_VERSION)?;
+        write_u64(&mut out, self.k as u64)?;
+        write_u64(&mut out, self.w as u64)?;
+
+        write_u64(&mut out, self.ref_lengths.len() as u64)?;
+        for &len in &self.ref_lengths {
+            write_u64(&mut out, len as u64)?;
+        }
+
+        let mut hashes: Vec<&u64> = self.occurrences.keys().collect();
+        hashes.sort_unstable();
+        write_u64(&mut out, hashes.len() as u64)?;
+        for hash in hashes {
+            let occurrences = &self.occurrences[hash];
+            write_u64(&mut out, *hash)?;
+            write_u64(&mut out, occurrences.len() as u64)?;
+            for &(ref_id, pos) in occurrences {
+                write_u64(&mut out, ref_id as u64)?;
+                write_u64(&mut out, pos as u64)?;
+            }
+        }
+        out.flush()
+    }
+
+    /// Reads an index written by `write`. Anything else, including an index from an incompatible version of this
+    /// crate, gives an error of kind `InvalidData`.
+    pub fn read<R: Read>(mut input: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != INDEX_MAGIC {
+            return Err(invalid_index("not a minimizer index"));
+        }
+        if read_u64(&mut input)? != INDEX_VERSION {
+            return Err(invalid_index("unsupported minimizer index version"));
+        }
+        let k = read_usize(&mut input)?;
+        let w = read_usize(&mut input)?;
+        if k == 0 || k > 32 || w == 0 {
+            return Err(invalid_index("minimizer index has invalid k or w"));
+        }
+
+        // counts come from the file, so they only bound what's read, not what's allocated up front
+        let num_refs = read_usize(&mut input)?;
+        let mut ref_lengths = Vec::with_capacity(num_refs.min(1 << 16));
+        for _ in 0..num_refs {
+            ref_lengths.push(read_usize(&mut input)?);
+        }
+
+        let num_hashes = read_usize(&mut input)?;
+        let mut occurrences = HashMap::with_capacity(num_hashes.min(1 << 16));
+        for _ in 0..num_hashes {
+            let hash = read_u64(&mut input)?;
+            let count = read_usize(&mut input)?;
+            let mut positions = Vec::with_capacity(count.min(1 << 16));
+            for _ in 0..count {
+                let ref_id = read_usize(&mut input)?;
+                let pos = read_usize(&mut input)?;
+                if ref_id >= num_refs || pos.saturating_add(k) > ref_lengths[ref_id] {
+                    return Err(invalid_index("minimizer index has an occurrence outside its references"));
+                }
+                positions.push((ref_id, pos));
+            }
+            occurrences.insert(hash, positions);
+        }
+
+        Ok(MinimizerIndex {
+            k,
+            w,
+            ref_lengths,
+            occurrences,
+        })
+    }
+
+    /// Writes the index to a file, as `write` does.
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    /// Reads an index from a file written by `write_to`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        MinimizerIndex::read(BufReader::new(File::open(path)?))
+    }
+
+    fn candidate(&self, group: &[(usize, i64, usize, usize)], query_len: usize) -> Candidate {
+        let ref_id = group[0].0;
+        let ref_len = self.ref_lengths[ref_id] as i64;
+        let lowest = group.iter().map(|h| h.1).min().unwrap_or(0);
+        let highest = group.iter().map(|h| h.1).max().unwrap_or(0);
+
+        let mut seeds: Vec<Anchor> = group
+            .iter()
+            .map(|&(_, _, query_pos, ref_pos)| Anchor {
+                score: self.k as i64,
+                query_begin: query_pos,
+                query_end: query_pos + self.k,
+                ref_begin: ref_pos,
+                ref_end: ref_pos + self.k,
+            })
+            .collect();
+        seeds.sort_by_key(|s| (s.ref_begin, s.query_begin));
+
+        Candidate {
+            ref_id,
+            ref_begin: lowest.max(0).min(ref_len) as usize,
+            ref_end: (highest + query_len as i64).max(0).min(ref_len) as usize,
+            seeds,
+        }
+    }
+}
+
+const INDEX_MAGIC: &[u8; 4] = b"PMIX";
+const INDEX_VERSION: u64 = 1;
+
+fn write_u64<W: Write>(out: &mut W, value: u64) -> io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn read_u64<R: Read>(input: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_usize<R: Read>(input: &mut R) -> io::Result<usize> {
+    let value = read_u64(input)?;
+    usize::try_from(value).map_err(|_| invalid_index("minimizer index value too large for this platform"))
+}
+
+fn invalid_index(reason: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+#[test]
+fn test_index_round_trip() {
+    let references: Vec<&[u8]> = vec![
+        b"TTGACCATGAGCTAGGCTTACGATCGGATCCTAGCATCGACTAGCGGCATTACAG",
+        b"GGCATCGATCAGCGACTTAGGCAATCTAGCGCGATATCGCTAGGACTCAGCAAT",
+    ];
+    let index = MinimizerIndex::new(&references, 7, 3);
+    let mut bytes = Vec::new();
+    index.write(&mut bytes).unwrap();
+
+    let loaded = MinimizerIndex::read(&bytes[..]).unwrap();
+    assert_eq!((index.k(), index.w()), (loaded.k(), loaded.w()));
+    assert_eq!(index.ref_lengths, loaded.ref_lengths);
+    assert_eq!(index.occurrences, loaded.occurrences);
+
+    let mut again = Vec::new();
+    loaded.write(&mut again).unwrap();
+    assert_eq!(bytes, again);
+
+    let err = MinimizerIndex::read(&bytes[..bytes.len() - 1]).err().unwrap();
+    assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+    bytes[0] = b'X';
+    assert_eq!(io::ErrorKind::InvalidData, MinimizerIndex::read(&bytes[..]).err().unwrap().kind());
+}
@@ -0,0 +1,243 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+//! Myers' bit-parallel edit distance, in the blocked form used by Edlib (Šošić and Šikić, 2017).
+//!
+//! Each column of the dynamic programming matrix is kept as bit vectors of the vertical differences between
+//! neighbouring cells, 64 rows to a word, so a column costs a handful of word operations per 64 characters of the
+//! pattern instead of one cell update per character. Every operation only carries information from low bits to high
+//! bits (additions and left shifts), so the unused bits past the end of the pattern never affect its last row.
+
+const WORD_BITS: usize = 64;
+
+/// The Levenshtein distance between `a` and `b`, or `None` as soon as it's certain to be larger than `limit`.
+///
+/// An alignment within `limit` edits never strays more than `limit` diagonals from the main one, or from the one the
+/// last cell is on, so as in Edlib only the blocks covering that band of each column are computed. Rows above the band
+/// are taken to grow by one per column, and rows below it to grow by one per row, which can only overestimate cells
+/// outside the band, so every cell of a path within the limit is still exact.
+pub(crate) fn distance(a: &[u8], b: &[u8], limit: Option<usize>) -> Option<usize> {
+    // the pattern runs down the columns, so keep it the shorter of the two
+    let (text, pattern) = if a.len() < b.len() { (b, a) } else { (a, b) };
+    let (m, n) = (pattern.len(), text.len());
+    // no distance is larger than the text, so a band that wide covers everything
+    let k = limit.map_or(n, |limit| limit.min(n));
+    if n - m > k {
+        return None;
+    }
+    if pattern.is_empty() {
+        return Some(n);
+    }
+
+    // column j (1-based) needs rows j - k to j + k - (n - m), between the diagonals a path within k edits can use
+    let first_block = |j: usize| (j.saturating_sub(k).max(1) - 1) / WORD_BITS;
+    let last_block = |j: usize| ((j + k - (n - m)).min(m) - 1) / WORD_BITS;
+    let bottom_row = |block: usize| ((block + 1) * WORD_BITS).min(m);
+    let height = |block: usize| bottom_row(block) - block * WORD_BITS;
+
+    let mut columns = Columns::new(pattern);
+    // the value of each block's last row in the current column, where every row of column 0 is its own index
+    let mut bottoms: Vec<usize> = (0..columns.words).map(bottom_row).collect();
+    let mut last = last_block(1);
+
+    for (j, &c) in text.iter().enumerate() {
+        let (first, next_last) = (first_block(j + 1), last_block(j + 1));
+        // a block joining the band hasn't moved since column 0, so its rows grow by one from the block above
+        for block in last + 1..=next_last {
+            bottoms[block] = bottoms[block - 1] + height(block);
+        }
+        last = next_last;
+
+        columns.advance_band(c, first, last, &mut bottoms);
+
+        // a block's cells are within its height of its last row, and a path within k edits crosses the band
+        if (first..=last).all(|block| bottoms[block].saturating_sub(height(block) - 1) > k) {
+            return None;
+        }
+    }
+
+    Some(bottoms[columns.words - 1]).filter(|&d| d <= k)
+}
+
+/// The edit distance between the whole pattern and the best matching substring of the text ending at each position:
+/// `ends[j]` is the least number of edits turning the pattern into some `text[i..j + 1]`.
+///
+/// The pattern must not be empty.
+pub(crate) fn end_distances(pattern: &[u8], text: &[u8]) -> Vec<usize> {
+    let mut columns = Columns::new(pattern);
+    let mut score = pattern.len();
+
+    // the top row stays at zero, so a match can start anywhere in the text
+    text.iter()
+        .map(|&c| {
+            score = (score as isize + columns.advance(c, 0) as isize) as usize;
+            score
+        })
+        .collect()
+}
+
+/// The columns of the dynamic programming matrix for one pattern, as bit vectors, advanced one text character at a
+/// time.
+struct Columns {
+    words: usize,
+    last_bit: usize,
+    // which row of `peq` each character's match bits are in; characters not in the pattern share the empty row 0
+    index: [usize; 256],
+    peq: Vec<u64>,
+    pv: Vec<u64>,
+    mv: Vec<u64>,
+}
+
+impl Columns {
+    fn new(pattern: &[u8]) -> Self {
+        let words = pattern.len().div_ceil(WORD_BITS);
+        let mut index = [0usize; 256];
+        let mut peq = vec![0u64; words];
+        for (i, &c) in pattern.iter().enumerate() {
+            if index[c as usize] == 0 {
+                index[c as usize] = peq.len() / words;
+                peq.resize(peq.len() + words, 0);
+            }
+            peq[index[c as usize] * words + i / WORD_BITS] |= 1 << (i % WORD_BITS);
+        }
+
+        Columns {
+            words,
+            last_bit: (pattern.len() - 1) % WORD_BITS,
+            index,
+            peq,
+            pv: vec![!0u64; words],
+            mv: vec![0u64; words],
+        }
+    }
+
+    /// Moves to the next column over text character `c`, where the top row changes by `top` (1 for global alignment,
+    /// 0 for a search), returning how much the last row changed.
+    fn advance(&mut self, c: u8, top: i32) -> i32 {
+        let eq = &self.peq[self.index[c as usize] * self.words..][..self.words];
+        let mut hin = top;
+        let blocks = self.pv.iter_mut().zip(self.mv.iter_mut()).zip(eq);
+        for (w, ((pv, mv), &eq)) in blocks.enumerate() {
+            let (ph, mh) = advance_block(pv, mv, eq, hin);
+            let bit = if w + 1 == self.words { self.last_bit } else { WORD_BITS - 1 };
+            hin = ((ph >> bit) & 1) as i32 - ((mh >> bit) & 1) as i32;
+        }
+        hin
+    }
+
+    /// Moves blocks `first..=last` to the next column over text character `c`, with the row above `first` growing by
+    /// one as the top row does in global alignment, and adds the change in each block's last row to `bottoms`.
+    fn advance_band(&mut self, c: u8, first: usize, last: usize, bottoms: &mut [usize]) {
+        let eq = &self.peq[self.index[c as usize] * self.words..][..self.words];
+        let mut hin = 1;
+        for w in first..=last {
+            let (ph, mh) = advance_block(&mut self.pv[w], &mut self.mv[w], eq[w], hin);
+            let bit = if w + 1 == self.words { self.last_bit } else { WORD_BITS - 1 };
+            hin = ((ph >> bit) & 1) as i32 - ((mh >> bit) & 1) as i32;
+            bottoms[w] = (bottoms[w] as isize + hin as isize) as usize;
+        }
+    }
+}
+
+/// Moves one 64-row block of a column over a text character whose matches in the block are `eq`, given the
+/// horizontal difference `hin` coming in at the block's top. Returns the block's horizontal positive and negative
+/// difference bits, before they're shifted down a row.
+fn advance_block(pv: &mut u64, mv: &mut u64, mut eq: u64, hin: i32) -> (u64, u64) {
+    let hin_negative = (hin < 0) as u64;
+    let xv = eq | *mv;
+    eq |= hin_negative;
+    let xh = ((eq & *pv).wrapping_add(*pv) ^ *pv) | eq;
+    let ph = *mv | !(xh | *pv);
+    let mh = *pv & xh;
+
+    let shifted_ph = (ph << 1) | (hin > 0) as u64;
+    let shifted_mh = (mh << 1) | hin_negative;
+    *pv = shifted_mh | !(xv | shifted_ph);
+    *mv = shifted_ph & xv;
+    (ph, mh)
+}
+
+#[test]
+fn test_myers_matches_dynamic_programming() {
+    fn levenshtein(a: &[u8], b: &[u8]) -> usize {
+        let mut row: Vec<usize> = (0..=b.len()).collect();
+        for (i, &x) in a.iter().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            for (j, &y) in b.iter().enumerate() {
+                let substitution = diagonal + (x != y) as usize;
+                diagonal = row[j + 1];
+                row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+            }
+        }
+        row[b.len()]
+    }
+
+    // lengths around the word boundaries, with edits sprinkled through
+    let base: Vec<u8> = (0..200u32).map(|i| b"ACGT"[(i * 7 + i / 3) as usize % 4]).collect();
+    for &len in &[1, 5, 63, 64, 65, 127, 128, 129, 200] {
+        let a = &base[..len];
+        let mut b = a.to_vec();
+        for i in (0..b.len()).step_by(17) {
+            b[i] = b'N';
+        }
+        b.insert(len / 2, b'T');
+        b.truncate(b.len().saturating_sub(3));
+
+        let expected = levenshtein(a, &b);
+        assert_eq!(Some(expected), distance(a, &b, None));
+        assert_eq!(Some(expected), distance(&b, a, Some(expected)));
+        if expected > 0 {
+            assert_eq!(None, distance(a, &b, Some(expected - 1)));
+        }
+    }
+    assert_eq!(Some(4), distance(b"", b"ACGT", None));
+}
+
+#[test]
+fn test_end_distances_match_dynamic_programming() {
+    fn search(pattern: &[u8], text: &[u8]) -> Vec<usize> {
+        let mut row: Vec<usize> = (0..=pattern.len()).collect();
+        text.iter()
+            .map(|&t| {
+                let mut diagonal = row[0];
+                row[0] = 0;
+                for (i, &p) in pattern.iter().enumerate() {
+                    let substitution = diagonal + (p != t) as usize;
+                    diagonal = row[i + 1];
+                    row[i + 1] = substitution.min(row[i] + 1).min(diagonal + 1);
+                }
+                row[pattern.len()]
+            })
+            .collect()
+    }
+
+    let text: Vec<u8> = (0..300u32).map(|i| b"ACGT"[(i * 5 + i / 7) as usize % 4]).collect();
+    for &len in &[1, 7, 63, 64, 65, 130] {
+        let mut pattern = text[100..100 + len].to_vec();
+        for i in (0..pattern.len()).step_by(11) {
+            pattern[i] = b'T';
+        }
+        assert_eq!(search(&pattern, &text), end_distances(&pattern, &text), "pattern length {}", len);
+    }
+}
+
+#[test]
+fn test_banded_distance_across_blocks() {
+    // a few edits spread over several blocks, so the band slides down the pattern and leaves the top blocks behind
+    let a: Vec<u8> = (0..500u32).map(|i| b"ACGT"[(i * 3 + i / 5) as usize % 4]).collect();
+    let mut b = a.clone();
+    b[40] = b'N';
+    b.remove(200);
+    b.insert(310, b'G');
+    b.truncate(496);
+
+    let expected = distance(&a, &b, None).unwrap();
+    assert_eq!(Some(expected), distance(&a, &b, Some(expected)));
+    assert_eq!(Some(expected), distance(&b, &a, Some(expected + 1)));
+    assert_eq!(None, distance(&a, &b, Some(expected - 1)));
+    assert_eq!(None, distance(&a, &b, Some(3)));
+    assert_eq!(Some(0), distance(&a, &a, Some(0)));
+}
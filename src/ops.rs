@@ -0,0 +1,248 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use thiserror::Error;
+
+use crate::align::TracebackResults;
+
+/// A single kind of alignment column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EditOp {
+    /// Both sequences have the same character (`=` in a CIGAR string).
+    Match,
+    /// The sequences have different characters (`X` in a CIGAR string).
+    Subst,
+    /// The query has a character the reference doesn't (`I` in a CIGAR string).
+    Ins,
+    /// The reference has a character the query doesn't (`D` in a CIGAR string).
+    Del,
+}
+
+impl EditOp {
+    /// The extended CIGAR character for this operation.
+    pub fn cigar_char(self) -> char {
+        match self {
+            EditOp::Match => '=',
+            EditOp::Subst => 'X',
+            EditOp::Ins => 'I',
+            EditOp::Del => 'D',
+        }
+    }
+
+    /// Whether this operation consumes a query character.
+    pub fn consumes_query(self) -> bool {
+        self != EditOp::Del
+    }
+
+    /// Whether this operation consumes a reference character.
+    pub fn consumes_ref(self) -> bool {
+        self != EditOp::Ins
+    }
+}
+
+/// A run of identical alignment columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AlignmentOp {
+    /// The kind of column.
+    pub op: EditOp,
+    /// How many columns in a row have this kind.
+    pub len: usize,
+}
+
+/// The reason a CIGAR string couldn't be turned into alignment operations.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid CIGAR string at offset {offset}: {reason}")]
+pub struct ParseCigarError {
+    /// The byte offset into the CIGAR string where the problem was found.
+    pub offset: usize,
+    /// What was wrong at that offset.
+    pub reason: &'static str,
+}
+
+/// Appends `len` columns of `op`, merging with the previous run if it's the same kind.
+fn push_op(ops: &mut Vec<AlignmentOp>, op: EditOp, len: usize) {
+    if len == 0 {
+        return;
+    }
+    match ops.last_mut() {
+        Some(last) if last.op == op => last.len += len,
+        _ => ops.push(AlignmentOp { op, len }),
+    }
+}
+
+/// Collapses a pair of trace strings (as found in `TracebackResults`) into runs of alignment operations. Characters
+/// are compared case-insensitively.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let ops = ops_from_traces("GAT-ACA", "GATTACC");
+/// assert_eq!("3=1D2=1X", ops_to_cigar(&ops));
+/// ```
+pub fn ops_from_traces(query_trace: &str, ref_trace: &str) -> Vec<AlignmentOp> {
+    let mut ops = Vec::new();
+    for (q, r) in query_trace.bytes().zip(ref_trace.bytes()) {
+        let op = if q == b'-' {
+            EditOp::Del
+        } else if r == b'-' {
+            EditOp::Ins
+        } else if q.eq_ignore_ascii_case(&r) {
+            EditOp::Match
+        } else {
+            EditOp::Subst
+        };
+        push_op(&mut ops, op, 1);
+    }
+    ops
+}
+
+/// Expands alignment operations back into query, comparison, and reference trace strings, given the stretches of the
+/// query and reference the alignment covers.
+///
+/// The comparison string marks matches with `|`, substitutions with `:`, and gaps with a space. Returns `None` if the
+/// operations consume more of either sequence than was given.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let ops = ops_from_cigar("3=1D2=1X").unwrap();
+/// let (query, comp, reference) = ops_to_traces(&ops, b"GATACA", b"GATTACC").unwrap();
+/// assert_eq!("GAT-ACA", query);
+/// assert_eq!("||| ||:", comp);
+/// assert_eq!("GATTACC", reference);
+/// ```
+pub fn ops_to_traces(ops: &[AlignmentOp], query: &[u8], reference: &[u8]) -> Option<(String, String, String)> {
+    let columns = ops.iter().map(|o| o.len).sum();
+    let mut query_trace = String::with_capacity(columns);
+    let mut comp_trace = String::with_capacity(columns);
+    let mut ref_trace = String::with_capacity(columns);
+
+    let (mut q, mut r) = (0, 0);
+    for o in ops {
+        for _ in 0..o.len {
+            let query_char = if o.op.consumes_query() {
+                q += 1;
+                *query.get(q - 1)? as char
+            } else {
+                '-'
+            };
+            let ref_char = if o.op.consumes_ref() {
+                r += 1;
+                *reference.get(r - 1)? as char
+            } else {
+                '-'
+            };
+            query_trace.push(query_char);
+            ref_trace.push(ref_char);
+            comp_trace.push(match o.op {
+                EditOp::Match => '|',
+                EditOp::Subst => ':',
+                EditOp::Ins | EditOp::Del => ' ',
+            });
+        }
+    }
+
+    Some((query_trace, comp_trace, ref_trace))
+}
+
+/// Formats alignment operations as an extended CIGAR string (using `=` and `X` rather than `M`).
+pub fn ops_to_cigar(ops: &[AlignmentOp]) -> String {
+    ops.iter().map(|o| format!("{}{}", o.len, o.op.cigar_char())).collect()
+}
+
+/// Parses an extended CIGAR string using the `=`, `X`, `I`, and `D` operations.
+///
+/// `M` is rejected, since it doesn't say whether the aligned characters match, and clipping, skipping, and padding
+/// operations have no equivalent alignment column. So are lengths which overflow a `usize`.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let ops = ops_from_cigar("2=3=1I").unwrap();
+/// assert_eq!(AlignmentOp { op: EditOp::Match, len: 5 }, ops[0]);
+///
+/// assert!(ops_from_cigar("4M").is_err());
+/// assert!(ops_from_cigar("99999999999999999999999=").is_err());
+/// ```
+pub fn ops_from_cigar(cigar: &str) -> Result<Vec<AlignmentOp>, ParseCigarError> {
+    let mut ops: Vec<AlignmentOp> = Vec::new();
+    let mut len: Option<usize> = None;
+
+    let too_long = |offset| ParseCigarError {
+        offset,
+        reason: "length doesn't fit in a usize",
+    };
+
+    for (offset, c) in cigar.char_indices() {
+        if let Some(digit) = c.to_digit(10) {
+            let longer = len.unwrap_or(0).checked_mul(10).and_then(|len| len.checked_add(digit as usize));
+            len = Some(longer.ok_or_else(|| too_long(offset))?);
+            continue;
+        }
+
+        let op = match c {
+            '=' => EditOp::Match,
+            'X' => EditOp::Subst,
+            'I' => EditOp::Ins,
+            'D' => EditOp::Del,
+            'M' => {
+                return Err(ParseCigarError {
+                    offset,
+                    reason: "M doesn't distinguish matches from substitutions, use = and X",
+                })
+            }
+            _ => {
+                return Err(ParseCigarError {
+                    offset,
+                    reason: "unsupported operation",
+                })
+            }
+        };
+
+        match len.take() {
+            Some(len) => {
+                // runs of the same operation are merged, and together they mustn't overflow either
+                if let Some(last) = ops.last().filter(|last| last.op == op) {
+                    last.len.checked_add(len).ok_or_else(|| too_long(offset))?;
+                }
+                push_op(&mut ops, op, len)
+            }
+            None => {
+                return Err(ParseCigarError {
+                    offset,
+                    reason: "operation without a length",
+                })
+            }
+        }
+    }
+
+    if len.is_some() {
+        return Err(ParseCigarError {
+            offset: cigar.len(),
+            reason: "length without an operation",
+        });
+    }
+
+    Ok(ops)
+}
+
+impl TracebackResults {
+    /// The alignment as runs of operations, including any end gaps in the trace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// let alignment = semi_global_traceback(b"GATTACA", b"GATTACA", 1, 1, &matrix).unwrap();
+    /// assert_eq!(vec![AlignmentOp { op: EditOp::Match, len: 7 }], alignment.ops());
+    /// ```
+    pub fn ops(&self) -> Vec<AlignmentOp> {
+        ops_from_traces(&self.query_trace, &self.ref_trace)
+    }
+}
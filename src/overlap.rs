@@ -0,0 +1,102 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use libc::c_int;
+
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::result::ParasailResult;
+use parasail_sys::parasail_sg_qb_de_trace_striped_sat;
+
+/// A suffix-prefix overlap between two sequences, as found by `overlap_alignment`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlap {
+    /// The score of the overlapping region.
+    pub score: i64,
+    /// The index (0-based) in the first sequence where the overlap starts. The overlap runs to its end.
+    pub a_begin: usize,
+    /// The index (0-based, exclusive) in the second sequence where the overlap ends. The overlap runs from its start.
+    pub b_end: usize,
+    /// The number of alignment columns in the overlap.
+    pub length: usize,
+    /// The fraction of overlap columns which are exact matches.
+    pub identity: f64,
+}
+
+/// Finds the best overlap of a suffix of `a` with a prefix of `b`, as when joining reads or contigs end to end.
+///
+/// Whatever part of `a` comes before the overlap and whatever part of `b` comes after it are free, but the overlap
+/// itself has to reach the end of `a` and the start of `b`. This is semi-global alignment with free gaps at the
+/// beginning of `a` and the end of `b` (parasail's `sg_qb_de`). Returns `None` if no overlap scores above zero, or an
+/// error if parasail fails.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let a = b"TTTTTGGGGACGTACGTAC";
+/// let b = b"ACGTACGTACCCCCAAAAA";
+///
+/// let overlap = overlap_alignment(a, b, 1, 1, &matrix).unwrap().unwrap();
+/// assert_eq!(10, overlap.score);
+/// assert_eq!(9, overlap.a_begin);
+/// assert_eq!(10, overlap.b_end);
+/// assert_eq!(10, overlap.length);
+/// assert_eq!(1.0, overlap.identity);
+/// ```
+pub fn overlap_alignment(
+    a: &[u8],
+    b: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<Option<Overlap>, ParasailError> {
+    if a.is_empty() || b.is_empty() {
+        return Ok(None);
+    }
+
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sg_qb_de_trace_striped_sat(
+            a.as_ptr(),
+            a.len() as c_int,
+            b.as_ptr(),
+            b.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    let cigar = result.cigar(a, b, substitution_matrix)?;
+
+    let score = result.score() as i64;
+    let mut ops = cigar.ops;
+    let mut a_begin = cigar.query_begin;
+    let b_end = result.ref_end();
+
+    // the skipped start of a and the skipped end of b aren't part of the overlap. parasail's end position already
+    // stops before the skipped end of b.
+    if let Some(&(b'I', len)) = ops.first() {
+        a_begin += len;
+        ops.remove(0);
+    }
+    if let Some(&(b'D', _)) = ops.last() {
+        ops.pop();
+    }
+
+    let length: usize = ops.iter().map(|&(_, len)| len).sum();
+    let exact: usize = ops.iter().filter(|&&(op, _)| op == b'=').map(|&(_, len)| len).sum();
+    if score <= 0 || length == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(Overlap {
+        score,
+        a_begin,
+        b_end,
+        length,
+        identity: exact as f64 / length as f64,
+    }))
+}
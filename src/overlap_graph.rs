@@ -0,0 +1,205 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+use crate::error::{AlignError, ParasailError};
+use crate::matrix::Matrix;
+use crate::overlap::overlap_alignment;
+use crate::parallel;
+
+/// Controls which overlaps `build_overlap_graph` looks for and keeps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlapGraphParams {
+    /// Overlaps shorter than this many alignment columns are dropped.
+    pub min_overlap: usize,
+    /// Overlaps with a lower fraction of exact matches are dropped.
+    pub min_identity: f64,
+    /// The k-mer length of the prefilter.
+    pub k: usize,
+    /// Pairs of reads sharing fewer distinct k-mers than this aren't aligned at all.
+    pub min_shared_kmers: usize,
+    /// The gap open cost used when aligning.
+    pub open_cost: i32,
+    /// The gap extend cost used when aligning.
+    pub gap_extend_cost: i32,
+    /// The number of threads to align on, or 0 for the available parallelism.
+    pub threads: usize,
+}
+
+impl Default for OverlapGraphParams {
+    fn default() -> Self {
+        OverlapGraphParams {
+            min_overlap: 50,
+            min_identity: 0.9,
+            k: 15,
+            min_shared_kmers: 3,
+            open_cost: 2,
+            gap_extend_cost: 1,
+            threads: 0,
+        }
+    }
+}
+
+/// A suffix of one read overlapping a prefix of another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlapEdge {
+    /// The index of the read whose suffix overlaps.
+    pub from: usize,
+    /// The index of the read whose prefix overlaps.
+    pub to: usize,
+    /// The score of the overlap.
+    pub score: i64,
+    /// Where the overlap starts in `from`.
+    pub from_begin: usize,
+    /// Where the overlap ends (exclusive) in `to`.
+    pub to_end: usize,
+    /// The number of alignment columns in the overlap.
+    pub length: usize,
+    /// The fraction of overlap columns which are exact matches.
+    pub identity: f64,
+}
+
+/// The overlaps found among a set of reads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlapGraph {
+    /// The number of reads, i.e. nodes.
+    pub read_count: usize,
+    /// The overlaps, ordered by `from` and then `to`.
+    pub edges: Vec<OverlapEdge>,
+}
+
+impl OverlapGraph {
+    /// Formats the graph as GFA 1: a segment line for each read and a link line for each overlap, all on the forward
+    /// strand. Overlaps are written as a single `M` operation spanning the overlap's columns. Reads are named by
+    /// their index, or by `names`. Returns an error unless there's one read for each node and, if `names` is given,
+    /// one name for each read.
+    pub fn to_gfa(&self, reads: &[&[u8]], names: Option<&[&str]>) -> Result<String, AlignError> {
+        if reads.len() != self.read_count {
+            return Err(AlignError::LengthMismatch {
+                left: self.read_count,
+                right: reads.len(),
+            });
+        }
+        if let Some(names) = names.filter(|names| names.len() != reads.len()) {
+            return Err(AlignError::LengthMismatch {
+                left: reads.len(),
+                right: names.len(),
+            });
+        }
+
+        let name = |i: usize| match names {
+            Some(names) => names[i].to_string(),
+            None => i.to_string(),
+        };
+
+        let mut gfa = String::from("H\tVN:Z:1.0\n");
+        for (i, read) in reads.iter().enumerate() {
+            let _ = writeln!(gfa, "S\t{}\t{}", name(i), String::from_utf8_lossy(read));
+        }
+        for edge in &self.edges {
+            let _ = writeln!(gfa, "L\t{}\t+\t{}\t+\t{}M", name(edge.from), name(edge.to), edge.length);
+        }
+        Ok(gfa)
+    }
+}
+
+/// Finds all significant suffix-prefix overlaps among a set of reads (in both directions for each pair) and
+/// collects them into a graph.
+///
+/// Pairs are first filtered by the number of distinct k-mers they share, and the survivors are aligned with
+/// `overlap_alignment` on several threads. Edges come out in the same order regardless of thread scheduling. Reads
+/// are compared as given, so reads from the reverse strand won't overlap their forward strand neighbors. Returns the
+/// first error parasail reports, if any.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let reads: Vec<&[u8]> = vec![
+///     b"AGCTTAGCTAGGCTACGATCGGATCCTAGC",
+///     b"GCTACGATCGGATCCTAGCATCGACTAGCG",
+///     b"TTTTTTTTTTTTTTTTTTTTTTTTTTTTTT",
+/// ];
+/// let params = OverlapGraphParams { min_overlap: 15, k: 8, ..OverlapGraphParams::default() };
+///
+/// let graph = build_overlap_graph(&reads, &matrix, &params).unwrap();
+/// assert_eq!(1, graph.edges.len());
+/// assert_eq!((0, 1), (graph.edges[0].from, graph.edges[0].to));
+/// assert_eq!(19, graph.edges[0].length);
+///
+/// let gfa = graph.to_gfa(&reads, Some(&["a", "b", "c"][..])).unwrap();
+/// assert!(gfa.contains("L\ta\t+\tb\t+\t19M"));
+/// assert!(graph.to_gfa(&reads, Some(&["a", "b"][..])).is_err());
+/// ```
+pub fn build_overlap_graph(
+    reads: &[&[u8]],
+    matrix: &Matrix,
+    params: &OverlapGraphParams,
+) -> Result<OverlapGraph, ParasailError> {
+    let candidates = candidate_pairs(reads, params.k.max(1), params.min_shared_kmers);
+
+    let edges: Vec<Result<OverlapEdge, ParasailError>> = parallel::map_chunks(&candidates, params.threads, |chunk| {
+        chunk
+            .iter()
+            .map(|&(from, to)| {
+                let overlap =
+                    overlap_alignment(reads[from], reads[to], params.open_cost, params.gap_extend_cost, matrix)?;
+                let overlap = match overlap {
+                    Some(overlap) => overlap,
+                    None => return Ok(None),
+                };
+                if overlap.length < params.min_overlap || overlap.identity < params.min_identity {
+                    return Ok(None);
+                }
+                Ok(Some(OverlapEdge {
+                    from,
+                    to,
+                    score: overlap.score,
+                    from_begin: overlap.a_begin,
+                    to_end: overlap.b_end,
+                    length: overlap.length,
+                    identity: overlap.identity,
+                }))
+            })
+            .filter_map(Result::transpose)
+            .collect()
+    });
+
+    Ok(OverlapGraph {
+        read_count: reads.len(),
+        edges: edges.into_iter().collect::<Result<_, _>>()?,
+    })
+}
+
+/// Ordered pairs of distinct reads sharing at least `min_shared` distinct k-mers, sorted.
+fn candidate_pairs(reads: &[&[u8]], k: usize, min_shared: usize) -> Vec<(usize, usize)> {
+    let mut readers: HashMap<&[u8], Vec<usize>> = HashMap::new();
+    for (i, read) in reads.iter().enumerate() {
+        let distinct: HashSet<&[u8]> = read.windows(k).collect();
+        for kmer in distinct {
+            readers.entry(kmer).or_default().push(i);
+        }
+    }
+
+    let mut shared: HashMap<(usize, usize), usize> = HashMap::new();
+    for holders in readers.values() {
+        for (n, &i) in holders.iter().enumerate() {
+            for &j in &holders[n + 1..] {
+                *shared.entry((i, j)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = shared
+        .into_iter()
+        .filter(|&(_, count)| count >= min_shared.max(1))
+        .flat_map(|((i, j), _)| vec![(i, j), (j, i)])
+        .collect();
+    pairs.sort_unstable();
+    pairs
+}
@@ -0,0 +1,44 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+//! Splitting work over scoped threads, for the functions taking a `threads` count.
+
+use std::thread;
+
+/// The number of threads to run for a requested `threads`, where 0 means the available parallelism.
+pub(crate) fn thread_count(threads: usize) -> usize {
+    if threads == 0 {
+        thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    } else {
+        threads
+    }
+}
+
+/// Cuts `items` into a contiguous run per thread, maps each run with `f` on its own thread, and concatenates the
+/// results. `f` gets a whole run rather than one item at a time, so it can set up state (such as a profile) once per
+/// thread. The output is in input order however the threads are scheduled.
+///
+/// # Panics
+///
+/// Panics if `f` panics on any of the threads.
+pub(crate) fn map_chunks<T, U, F>(items: &[T], threads: usize, f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&[T]) -> Vec<U> + Sync,
+{
+    let chunk_size = items.len().div_ceil(thread_count(threads)).max(1);
+
+    thread::scope(|scope| {
+        let f = &f;
+        let handles: Vec<_> = items.chunks(chunk_size).map(|chunk| scope.spawn(move || f(chunk))).collect();
+
+        // joining in spawn order keeps the output in input order
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("worker thread panicked"))
+            .collect()
+    })
+}
@@ -0,0 +1,160 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::fmt;
+
+use crate::align::{global_alignment_score, global_alignment_score_banded, TracebackResults};
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::profile::Profile;
+use crate::variant::{call_variants, drop_homopolymer_indels, Variant};
+
+/// Scoring parameters tuned for a kind of sequencing read, named after the minimap2 presets they follow.
+///
+/// minimap2 charges `open + k * extend` for a gap of length `k`, while parasail charges `open + (k - 1) * extend`,
+/// so the open costs here include one extension.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let preset = Preset::MapOnt;
+/// let matrix = preset.matrix();
+/// let (open, extend) = preset.gaps();
+///
+/// let read = b"ACGTTTACGGACCA";
+/// let reference = b"ACGTTTTACGGACCA";
+/// let alignment = semi_global_traceback(read, reference, open, extend, &matrix).unwrap();
+///
+/// // the lost T in the homopolymer is taken for a sequencing error
+/// assert!(preset.call_variants(&alignment, reference).is_empty());
+/// assert_eq!(1, call_variants(&alignment, reference).len());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preset {
+    /// Oxford Nanopore reads (`map-ont`): matches score 2, mismatches -4, and gaps cost 4 to open and 2 per base.
+    /// Nanopore reads often get the length of homopolymers wrong, so single base indels in homopolymers aren't
+    /// reported as variants.
+    MapOnt,
+    /// PacBio HiFi reads (`map-hifi`): matches score 1, mismatches -4, and gaps cost 6 to open and 2 per base. HiFi
+    /// reads are over 99% accurate, so `global_score` aligns them within a narrow band.
+    MapHifi,
+}
+
+/// How `Preset::global_score` aligns a pair of sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlobalStrategy {
+    /// Fill the whole dynamic programming matrix with a striped kernel.
+    Full,
+    /// Fill only the cells within `band` of the main diagonal (see `global_alignment_score_banded`).
+    Banded {
+        /// How far from the main diagonal the alignment may stray.
+        band: usize,
+    },
+}
+
+impl Preset {
+    /// The minimap2 name of the preset.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Preset::MapOnt => "map-ont",
+            Preset::MapHifi => "map-hifi",
+        }
+    }
+
+    /// Creates the preset's substitution matrix. It covers all the IUPAC nucleotide codes (see `Matrix::iupac_dna`),
+    /// so ambiguous bases score partially.
+    pub fn matrix(&self) -> Matrix {
+        let (match_score, mismatch_score) = match *self {
+            Preset::MapOnt => (2, -4),
+            Preset::MapHifi => (1, -4),
+        };
+        Matrix::iupac_dna(match_score, mismatch_score)
+    }
+
+    /// The `(open, extend)` gap costs to align with, as parasail charges them.
+    pub fn gaps(&self) -> (i32, i32) {
+        match *self {
+            Preset::MapOnt => (6, 2),
+            Preset::MapHifi => (8, 2),
+        }
+    }
+
+    /// The shortest reference homopolymer in which single base indels are taken for sequencing errors, or `None` if
+    /// every indel is reported.
+    pub fn homopolymer_min_run(&self) -> Option<usize> {
+        match *self {
+            Preset::MapOnt => Some(3),
+            Preset::MapHifi => None,
+        }
+    }
+
+    /// The typical identity of the preset's reads to their reference.
+    pub fn expected_identity(&self) -> f64 {
+        match *self {
+            Preset::MapOnt => 0.9,
+            Preset::MapHifi => 0.99,
+        }
+    }
+
+    /// Picks how to globally align sequences of the given lengths. When reads are expected to be at least 99%
+    /// identical, the alignment should stay within a few edits of the diagonal: the band covers the difference in
+    /// length plus twice the edits expected at that identity, with some slack. If that band would cover most of the
+    /// matrix anyway, or reads aren't that accurate, the full matrix is filled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// assert_eq!(GlobalStrategy::Banded { band: 220 }, Preset::MapHifi.strategy(10_000, 10_010));
+    /// assert_eq!(GlobalStrategy::Full, Preset::MapHifi.strategy(30, 40));
+    /// assert_eq!(GlobalStrategy::Full, Preset::MapOnt.strategy(10_000, 10_010));
+    /// ```
+    pub fn strategy(&self, query_len: usize, ref_len: usize) -> GlobalStrategy {
+        const SLACK: usize = 8;
+
+        let identity = self.expected_identity();
+        if identity < 0.99 {
+            return GlobalStrategy::Full;
+        }
+
+        let edits = (query_len.max(ref_len) as f64 * (1.0 - identity)).ceil() as usize;
+        let band = query_len.abs_diff(ref_len) + 2 * edits + SLACK;
+        if 2 * band + 1 >= query_len.min(ref_len) {
+            GlobalStrategy::Full
+        } else {
+            GlobalStrategy::Banded { band }
+        }
+    }
+
+    /// The global alignment score of `query` against `reference` with the preset's gap costs, aligned as `strategy`
+    /// picks. `matrix` should be the preset's `matrix`, made once and reused. A banded score is exact unless the
+    /// alignment strays further from the diagonal than the preset's reads are expected to.
+    pub fn global_score(&self, query: &[u8], reference: &[u8], matrix: &Matrix) -> Result<i32, ParasailError> {
+        let (open, extend) = self.gaps();
+        match self.strategy(query.len(), reference.len()) {
+            GlobalStrategy::Banded { band } => {
+                global_alignment_score_banded(query, reference, open, extend, band, matrix)
+            }
+            GlobalStrategy::Full => global_alignment_score(&Profile::try_new(query, matrix)?, reference, open, extend),
+        }
+    }
+
+    /// Calls variants as `call_variants` does, then drops the indels this preset's reads are known to get wrong (see
+    /// `homopolymer_min_run`).
+    pub fn call_variants(&self, alignment: &TracebackResults, reference: &[u8]) -> Vec<Variant> {
+        let variants = call_variants(alignment, reference);
+        match self.homopolymer_min_run() {
+            Some(min_run) => drop_homopolymer_indels(&variants, reference, min_run),
+            None => variants,
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
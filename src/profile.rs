@@ -3,11 +3,52 @@
 // This software may be modified and distributed under the terms of the MIT license.  See the
 // LICENSE file for details.
 
-use std::marker::PhantomData;
+use std::convert::TryFrom;
+use std::fmt;
 use std::ops::Deref;
+use std::slice;
 
-use parasail_sys::{parasail_profile, parasail_profile_create_sat, parasail_profile_free};
+use parasail_sys::{
+    parasail_profile, parasail_profile_create_16, parasail_profile_create_32, parasail_profile_create_64,
+    parasail_profile_create_8, parasail_profile_create_sat, parasail_profile_create_stats_16,
+    parasail_profile_create_stats_32, parasail_profile_create_stats_64, parasail_profile_create_stats_8,
+    parasail_profile_create_stats_sat, parasail_profile_free,
+};
+use crate::error::{AlignError, ParasailError};
 use crate::matrix::Matrix;
+use crate::parallel;
+use crate::result::non_null;
+
+/// The integer width of the scores a profile's kernels compute with.
+///
+/// Narrower scores fit more cells in each SIMD register and so align faster, but saturate sooner: 8 bit scores top out
+/// at 127, and 16 bit scores at 32767. A saturated score is silently wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ScoreWidth {
+    /// 8 bit scores.
+    Bits8,
+    /// 16 bit scores.
+    Bits16,
+    /// 32 bit scores.
+    Bits32,
+    /// 64 bit scores.
+    Bits64,
+    /// 8 bit scores, retried with 16 bit scores if they saturate. The profile holds both.
+    #[default]
+    Saturating,
+}
+
+/// Which kinds of kernels a profile is built for.
+///
+/// The default matches `Profile::new`: saturating widths, without stats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ProfileCaps {
+    /// Whether the profile carries the extra tables parasail's stats kernels need to count matches, similarities, and
+    /// alignment length. Stats profiles work with the plain kernels too, but take longer to build.
+    pub stats: bool,
+    /// The width of the scores.
+    pub width: ScoreWidth,
+}
 
 /// A container for a parasail query profile. Can be reused to re-align the same sequence against multiple references.
 ///
@@ -24,10 +65,19 @@ use crate::matrix::Matrix;
 /// let profile = Profile::new(query_sequence, &identity_matrix);
 /// ```
 pub struct Profile<'a> {
-    sequence_lifetime: PhantomData<&'a [u8]>,
+    query: &'a [u8],
+    matrix: Option<&'a Matrix>,
     internal_rep: *mut parasail_profile,
+    // the position-specific matrix the profile points at, if it built its own
+    quality_matrix: Option<Matrix>,
+    caps: ProfileCaps,
 }
 
+// parasail only reads a profile once it's built, and the query and matrix it points into are shared references, so
+// profiles can be moved to and aligned against from other threads
+unsafe impl<'a> Send for Profile<'a> {}
+unsafe impl<'a> Sync for Profile<'a> {}
+
 #[doc(hidden)]
 impl<'a> Drop for Profile<'a> {
     fn drop(&mut self) {
@@ -37,6 +87,16 @@ impl<'a> Drop for Profile<'a> {
     }
 }
 
+impl<'a> fmt::Debug for Profile<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Profile")
+            .field("query", &String::from_utf8_lossy(self.query))
+            .field("matrix", self.matrix())
+            .field("caps", &self.caps)
+            .finish()
+    }
+}
+
 #[doc(hidden)]
 impl<'a> Deref for Profile<'a> {
     type Target = *mut parasail_profile;
@@ -46,8 +106,30 @@ impl<'a> Deref for Profile<'a> {
     }
 }
 
+/// Builds a fresh parasail profile of the same query, matrix, and capabilities, so the clone can be moved to another
+/// thread or outlive the original. Profiles from `with_qualities` get their own copy of the position-specific matrix.
+impl<'a> Clone for Profile<'a> {
+    fn clone(&self) -> Self {
+        let quality_matrix = self.quality_matrix.as_ref().map(Matrix::to_owned_mut);
+        let matrix = quality_matrix.as_ref().or(self.matrix).expect("profiles always have a matrix");
+
+        Profile {
+            query: self.query,
+            matrix: self.matrix,
+            // this profile was built once already, so only running out of memory can stop it being built again
+            internal_rep: create_profile(self.query, matrix, self.caps).expect("parasail couldn't allocate a profile"),
+            quality_matrix,
+            caps: self.caps,
+        }
+    }
+}
+
 impl<'a> Profile<'a> {
     /// Creates a new profile container and ties its lifetime to the query sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if parasail can't build the profile, as happens for an empty query. See `try_new` for an error instead.
     pub fn new(query_seq: &'a [u8], matrix: &'a Matrix) -> Self {
         unsafe {
             // this struct now owns this pointer, and will free on drop
@@ -59,9 +141,384 @@ impl<'a> Profile<'a> {
             // we'll also store a lifetime'd reference to the query to make sure we don't
             // deref the query after it's been dropped
             Profile {
-                sequence_lifetime: PhantomData,
-                internal_rep: profile_ptr,
+                query: query_seq,
+                matrix: Some(matrix),
+                internal_rep: non_null(profile_ptr, "a profile").unwrap_or_else(|err| panic!("{}", err)),
+                quality_matrix: None,
+                caps: ProfileCaps::default(),
             }
         }
     }
+
+    /// Creates a profile as `new` does, but returns an error rather than panicking for a query parasail can't profile,
+    /// so untrusted input can't take down a service.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// assert!(Profile::try_new(b"ACGT", &matrix).is_ok());
+    /// assert!(matches!(Profile::try_new(b"", &matrix), Err(ParasailError::Align(AlignError::EmptySequence))));
+    /// ```
+    pub fn try_new(query_seq: &'a [u8], matrix: &'a Matrix) -> Result<Self, ParasailError> {
+        if query_seq.is_empty() {
+            return Err(AlignError::EmptySequence.into());
+        }
+        if query_seq.len() > i32::MAX as usize {
+            return Err(AlignError::SequenceTooLong {
+                len: query_seq.len(),
+                max: i32::MAX as usize,
+            }
+            .into());
+        }
+        Profile::new_with(query_seq, matrix, ProfileCaps::default())
+    }
+
+    /// Creates a profile built for the kernels `caps` describes, so it's known up front whether it can be used with
+    /// stats functions and which score width it computes with. Returns an error if parasail can't build the profile.
+    ///
+    /// The alignment functions taking a profile run the kernels matching its width.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// let caps = ProfileCaps { width: ScoreWidth::Bits16, ..ProfileCaps::default() };
+    /// let profile = Profile::new_with(b"ACGTACGT", &matrix, caps).unwrap();
+    /// assert_eq!(8, global_alignment_score(&profile, b"ACGTACGT", 1, 1).unwrap());
+    /// ```
+    pub fn new_with(query_seq: &'a [u8], matrix: &'a Matrix, caps: ProfileCaps) -> Result<Self, ParasailError> {
+        Ok(Profile {
+            query: query_seq,
+            matrix: Some(matrix),
+            internal_rep: create_profile(query_seq, matrix, caps)?,
+            quality_matrix: None,
+            caps,
+        })
+    }
+
+    /// Takes ownership of a profile created directly through `parasail_sys`, so it can be used with this crate's
+    /// profile-based alignment functions and is freed on drop.
+    ///
+    /// # Safety
+    ///
+    /// `profile` must be a valid, non-null profile of `query_seq` built with `matrix`, that nothing else frees or
+    /// uses after this. It must have been created by the `parasail_profile_create*` function matching `caps`: the
+    /// alignment functions pick which kernels to run from `caps`, and running a kernel against a profile without the
+    /// tables it needs is undefined behavior.
+    pub unsafe fn from_raw(
+        profile: *mut parasail_profile,
+        query_seq: &'a [u8],
+        matrix: &'a Matrix,
+        caps: ProfileCaps,
+    ) -> Self {
+        Profile {
+            query: query_seq,
+            matrix: Some(matrix),
+            internal_rep: profile,
+            quality_matrix: None,
+            caps,
+        }
+    }
+
+    /// The underlying parasail profile, for passing to `parasail_sys` kernels directly.
+    ///
+    /// The profile is still owned by this struct: don't free it, and don't use the pointer after this struct is
+    /// dropped.
+    pub fn as_raw(&self) -> *mut parasail_profile {
+        self.internal_rep
+    }
+
+    /// Creates a profile of each query, as `Profile::new` does, building them on as many threads as are available.
+    /// The profiles come back in the same order as the queries, and all share the one matrix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// let queries: Vec<&[u8]> = vec![b"ACGT", b"TTTTTT", b"GGC"];
+    ///
+    /// let profiles = Profile::new_many(&queries, &matrix);
+    /// assert_eq!(6, profiles[1].query_len());
+    /// ```
+    pub fn new_many(queries: &[&'a [u8]], matrix: &'a Matrix) -> Vec<Self> {
+        parallel::map_chunks(queries, 0, |chunk| chunk.iter().map(|query| Profile::new(query, matrix)).collect())
+    }
+
+    /// The kinds of kernels the profile was built for.
+    pub fn caps(&self) -> ProfileCaps {
+        self.caps
+    }
+
+    /// The width of the scores the profile's kernels compute with.
+    pub fn width(&self) -> ScoreWidth {
+        self.caps.width
+    }
+
+    /// The query sequence the profile was built from.
+    pub fn query(&self) -> &'a [u8] {
+        self.query
+    }
+
+    /// The length of the query sequence.
+    pub fn query_len(&self) -> usize {
+        self.query.len()
+    }
+
+    /// The matrix the profile scores with. For a profile built by `with_qualities`, this is the position-specific
+    /// matrix derived from the qualities, with one row per query position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    /// let profile = Profile::new(b"ACGT", &matrix);
+    /// assert_eq!(4, profile.query_len());
+    /// assert_eq!(matrix.alphabet(), profile.matrix().alphabet());
+    /// assert_eq!(ScoreWidth::Saturating, profile.width());
+    /// ```
+    pub fn matrix(&self) -> &Matrix {
+        match (&self.quality_matrix, self.matrix) {
+            (Some(quality_matrix), _) => quality_matrix,
+            (None, Some(matrix)) => matrix,
+            (None, None) => unreachable!("profiles always have a matrix"),
+        }
+    }
+
+    /// The characters the profile's matrix has scores for.
+    pub fn alphabet(&self) -> &[u8] {
+        self.matrix().alphabet()
+    }
+
+    /// Creates a profile which scores the query using its FASTQ base qualities (Phred+33), so that low-confidence
+    /// bases contribute less to the alignment score, whether they match or not.
+    ///
+    /// Each query position gets its own row of scores: the expected score from `matrix` if the base is wrong with
+    /// the probability its quality implies, and otherwise equally likely to be any other letter of the matrix's
+    /// alphabet. The resulting profile works with all of the profile-based alignment functions. Returns an error if
+    /// there isn't exactly one quality per query base.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = Matrix::create("ACGT", 5, -4);
+    /// let read = b"ACGTACGTAC";
+    /// let confident = Profile::with_qualities(read, b"IIIIIIIIII", &matrix).unwrap();
+    /// let doubtful = Profile::with_qualities(read, b"IIIII#IIII", &matrix).unwrap();
+    ///
+    /// // a mismatch at a low quality base costs less
+    /// let reference = b"ACGTAGGTAC";
+    /// assert_eq!(9 * 5 - 4, global_alignment_score(&confident, reference, 10, 1).unwrap());
+    /// assert_eq!(9 * 5 - 2, global_alignment_score(&doubtful, reference, 10, 1).unwrap());
+    /// ```
+    pub fn with_qualities(query_seq: &'a [u8], qualities: &[u8], matrix: &Matrix) -> Result<Self, ParasailError> {
+        if query_seq.len() != qualities.len() {
+            return Err(AlignError::LengthMismatch {
+                left: query_seq.len(),
+                right: qualities.len(),
+            }
+            .into());
+        }
+
+        let quality_matrix = matrix.quality_weighted(query_seq, qualities);
+        unsafe {
+            let profile_ptr = parasail_profile_create_sat(query_seq.as_ptr(),
+                                                          query_seq.len() as i32,
+                                                          *quality_matrix);
+
+            // the profile is freed in drop before the matrix it points at
+            Ok(Profile {
+                query: query_seq,
+                matrix: None,
+                internal_rep: non_null(profile_ptr, "a profile")?,
+                quality_matrix: Some(quality_matrix),
+                caps: ProfileCaps::default(),
+            })
+        }
+    }
 }
+
+/// A profile known to carry the tables parasail's stats kernels need, so the functions counting matches and alignment
+/// length from a profile (such as `semi_global_alignment_stats_profile`) can only be handed one that has them.
+///
+/// Passing a profile without stats tables to a stats kernel makes parasail read memory that isn't there, so this is
+/// checked once, when the `StatsProfile` is made, rather than every time it's used. It dereferences to the `Profile`
+/// inside, so it works with the plain profile functions as well.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// use std::convert::TryFrom;
+///
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let profile = StatsProfile::new(b"ACGTACGT", &matrix).unwrap();
+///
+/// let stats = semi_global_alignment_stats_profile(&profile, b"TTACGTACGTTT", 1, 1).unwrap();
+/// assert_eq!(8, stats.num_matches);
+/// assert_eq!(8, semi_global_alignment_score(&profile, b"TTACGTACGTTT", 1, 1).unwrap());
+///
+/// // a profile built without stats has to be converted, which checks it
+/// assert!(StatsProfile::try_from(Profile::new(b"ACGTACGT", &matrix)).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct StatsProfile<'a> {
+    profile: Profile<'a>,
+}
+
+impl<'a> StatsProfile<'a> {
+    /// Creates a stats profile with saturating score widths. Returns an error if parasail can't build it, as for an
+    /// empty query.
+    pub fn new(query_seq: &'a [u8], matrix: &'a Matrix) -> Result<Self, ParasailError> {
+        StatsProfile::with_width(query_seq, matrix, ScoreWidth::Saturating)
+    }
+
+    /// Creates a stats profile computing with the given score width. Returns an error if parasail can't build it.
+    pub fn with_width(query_seq: &'a [u8], matrix: &'a Matrix, width: ScoreWidth) -> Result<Self, ParasailError> {
+        let caps = ProfileCaps { stats: true, width };
+        Ok(StatsProfile {
+            profile: Profile::new_with(query_seq, matrix, caps)?,
+        })
+    }
+
+    /// The profile inside.
+    pub fn into_inner(self) -> Profile<'a> {
+        self.profile
+    }
+}
+
+/// Checks the profile was built with `ProfileCaps::stats`, and returns an error if it wasn't.
+impl<'a> TryFrom<Profile<'a>> for StatsProfile<'a> {
+    type Error = AlignError;
+
+    fn try_from(profile: Profile<'a>) -> Result<Self, AlignError> {
+        if !profile.caps().stats {
+            return Err(AlignError::InvalidParameter {
+                reason: "the profile was built without stats tables",
+            });
+        }
+        Ok(StatsProfile { profile })
+    }
+}
+
+impl<'a> Deref for StatsProfile<'a> {
+    type Target = Profile<'a>;
+
+    fn deref(&self) -> &Profile<'a> {
+        &self.profile
+    }
+}
+
+/// Profiles of many queries built with one matrix, for scoring every query against many references (see
+/// `align_profile_set`) without building any profile twice.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let queries: Vec<&[u8]> = vec![b"ACGTACGT", b"TTTTTTTT"];
+/// let profiles = ProfileSet::new(&queries, &matrix);
+///
+/// assert_eq!(2, profiles.len());
+/// assert_eq!(b"TTTTTTTT", profiles.get(1).unwrap().query());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProfileSet<'a> {
+    profiles: Vec<Profile<'a>>,
+}
+
+impl<'a> ProfileSet<'a> {
+    /// Builds a profile of each query in parallel, as `Profile::new_many` does.
+    pub fn new(queries: &[&'a [u8]], matrix: &'a Matrix) -> Self {
+        ProfileSet {
+            profiles: Profile::new_many(queries, matrix),
+        }
+    }
+
+    /// The number of profiles.
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    /// Whether there are no profiles.
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    /// The profile of the `i`th query, if there is one.
+    pub fn get(&self, i: usize) -> Option<&Profile<'a>> {
+        self.profiles.get(i)
+    }
+
+    /// The profiles, in query order.
+    pub fn iter(&self) -> slice::Iter<'_, Profile<'a>> {
+        self.profiles.iter()
+    }
+
+    /// The profiles as a slice, in query order.
+    pub fn as_slice(&self) -> &[Profile<'a>] {
+        &self.profiles
+    }
+}
+
+impl<'a> From<Vec<Profile<'a>>> for ProfileSet<'a> {
+    fn from(profiles: Vec<Profile<'a>>) -> Self {
+        ProfileSet { profiles }
+    }
+}
+
+impl<'s, 'a> IntoIterator for &'s ProfileSet<'a> {
+    type Item = &'s Profile<'a>;
+    type IntoIter = slice::Iter<'s, Profile<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.profiles.iter()
+    }
+}
+
+/// Creates the parasail profile matching `caps`, or returns an error if parasail can't. The caller owns the returned
+/// pointer.
+fn create_profile(
+    query_seq: &[u8],
+    matrix: &Matrix,
+    caps: ProfileCaps,
+) -> Result<*mut parasail_profile, ParasailError> {
+    let (query, len) = (query_seq.as_ptr(), query_seq.len() as i32);
+    let profile = unsafe {
+        match (caps.stats, caps.width) {
+            (false, ScoreWidth::Bits8) => parasail_profile_create_8(query, len, **matrix),
+            (false, ScoreWidth::Bits16) => parasail_profile_create_16(query, len, **matrix),
+            (false, ScoreWidth::Bits32) => parasail_profile_create_32(query, len, **matrix),
+            (false, ScoreWidth::Bits64) => parasail_profile_create_64(query, len, **matrix),
+            (false, ScoreWidth::Saturating) => parasail_profile_create_sat(query, len, **matrix),
+            (true, ScoreWidth::Bits8) => parasail_profile_create_stats_8(query, len, **matrix),
+            (true, ScoreWidth::Bits16) => parasail_profile_create_stats_16(query, len, **matrix),
+            (true, ScoreWidth::Bits32) => parasail_profile_create_stats_32(query, len, **matrix),
+            (true, ScoreWidth::Bits64) => parasail_profile_create_stats_64(query, len, **matrix),
+            (true, ScoreWidth::Saturating) => parasail_profile_create_stats_sat(query, len, **matrix),
+        }
+    };
+    non_null(profile, "a profile")
+}
+
+/// Calls whichever width of a profile kernel matches the profile's `ScoreWidth`, given the kernels in the order 8, 16,
+/// 32, 64, and saturating, followed by the arguments after the profile.
+macro_rules! profile_kernel {
+    ($profile:expr, [$k8:ident, $k16:ident, $k32:ident, $k64:ident, $sat:ident], $($arg:expr),* $(,)?) => {
+        match $profile.caps().width {
+            $crate::profile::ScoreWidth::Bits8 => $k8(**$profile, $($arg),*),
+            $crate::profile::ScoreWidth::Bits16 => $k16(**$profile, $($arg),*),
+            $crate::profile::ScoreWidth::Bits32 => $k32(**$profile, $($arg),*),
+            $crate::profile::ScoreWidth::Bits64 => $k64(**$profile, $($arg),*),
+            $crate::profile::ScoreWidth::Saturating => $sat(**$profile, $($arg),*),
+        }
+    };
+}
+
+pub(crate) use profile_kernel;
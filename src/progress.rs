@@ -0,0 +1,66 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+/// Receives progress reports from long-running batch work, such as `align_batch_with_progress`, to show how far
+/// along it is.
+///
+/// Reports come from every worker thread at once, so implementations need to be cheap and thread safe. With the
+/// `indicatif` feature, an `indicatif::ProgressBar` can be passed straight in, and draws the count, rate, and ETA with
+/// whatever style it was given. `()` ignores all reports.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// use std::sync::atomic::{AtomicU64, Ordering};
+///
+/// struct Counter(AtomicU64);
+///
+/// impl Progress for Counter {
+///     fn set_total(&self, _total: u64) {}
+///
+///     fn advance(&self, done: u64) {
+///         self.0.fetch_add(done, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let references: Vec<&[u8]> = vec![b"ACGTACGT", b"TTTTTTTT", b"ACGTTCGT"];
+/// let counter = Counter(AtomicU64::new(0));
+///
+/// align_batch_with_progress(b"ACGTACGT", &references, AlignmentMode::Global, 1, 1, &matrix, 2, &counter);
+/// assert_eq!(3, counter.0.load(Ordering::Relaxed));
+/// ```
+pub trait Progress: Sync {
+    /// Says how many items the work covers, before any are done.
+    fn set_total(&self, total: u64);
+
+    /// Says that `done` more items are finished.
+    fn advance(&self, done: u64);
+
+    /// Says that the work is over, whether or not every item was done.
+    fn finish(&self) {}
+}
+
+impl Progress for () {
+    fn set_total(&self, _total: u64) {}
+
+    fn advance(&self, _done: u64) {}
+}
+
+#[cfg(feature = "indicatif")]
+impl Progress for indicatif::ProgressBar {
+    fn set_total(&self, total: u64) {
+        self.set_length(total);
+    }
+
+    fn advance(&self, done: u64) {
+        self.inc(done);
+    }
+
+    fn finish(&self) {
+        indicatif::ProgressBar::finish(self);
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::fmt;
+
+use crate::matrix::Matrix;
+
+/// A reduced amino acid alphabet, which merges residues with similar properties into groups so that remote homologs
+/// share more exact k-mers and align with a smaller matrix.
+///
+/// Each group is written as its first residue in the listing below. Recode sequences with `recode` and score them with
+/// `matrix`, built from a full protein matrix.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let alphabet = ReducedAlphabet::Murphy10;
+/// assert_eq!(b"LLSSEKF".to_vec(), alphabet.recode(b"IVSTDRw"));
+///
+/// let matrix = alphabet.matrix(&Matrix::new(MatrixType::Blosum62));
+/// assert_eq!(b"LCAGSPFEKHX", matrix.alphabet());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReducedAlphabet {
+    /// Murphy et al.'s 15 groups: `LVIM C A G S T P FY W E D N Q KR H`.
+    Murphy15,
+    /// Murphy et al.'s 10 groups: `LVIM C A G ST P FYW EDNQ KR H`.
+    Murphy10,
+    /// Murphy et al.'s 8 groups: `LVIMC AG ST P FYW EDNQ KR H`.
+    Murphy8,
+    /// Murphy et al.'s 4 groups: `LVIMC AGSTP FYW EDNQKRH`.
+    Murphy4,
+    /// Dayhoff's 6 groups: `AGPST C DENQ HKR ILMV FWY`.
+    Dayhoff6,
+}
+
+impl ReducedAlphabet {
+    /// The groups of residues, each written first by the residue it's recoded as.
+    pub fn groups(&self) -> &'static [&'static [u8]] {
+        match *self {
+            ReducedAlphabet::Murphy15 => &[
+                b"LVIM", b"C", b"A", b"G", b"S", b"T", b"P", b"FY", b"W", b"E", b"D", b"N", b"Q", b"KR", b"H",
+            ],
+            ReducedAlphabet::Murphy10 => &[b"LVIM", b"C", b"A", b"G", b"ST", b"P", b"FYW", b"EDNQ", b"KR", b"H"],
+            ReducedAlphabet::Murphy8 => &[b"LVIMC", b"AG", b"ST", b"P", b"FYW", b"EDNQ", b"KR", b"H"],
+            ReducedAlphabet::Murphy4 => &[b"LVIMC", b"AGSTP", b"FYW", b"EDNQKRH"],
+            ReducedAlphabet::Dayhoff6 => &[b"AGPST", b"C", b"DENQ", b"HKR", b"ILMV", b"FWY"],
+        }
+    }
+
+    /// The name the alphabet usually goes by, e.g. `murphy10`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ReducedAlphabet::Murphy15 => "murphy15",
+            ReducedAlphabet::Murphy10 => "murphy10",
+            ReducedAlphabet::Murphy8 => "murphy8",
+            ReducedAlphabet::Murphy4 => "murphy4",
+            ReducedAlphabet::Dayhoff6 => "dayhoff6",
+        }
+    }
+
+    /// The letter a residue is recoded as, ignoring case. The ambiguity codes `B` (`D` or `N`), `Z` (`E` or `Q`), and
+    /// `J` (`I` or `L`) recode to their group when both residues share one. Anything else becomes `X`.
+    pub fn recode_residue(&self, residue: u8) -> u8 {
+        let residue = residue.to_ascii_uppercase();
+        let group_of = |r: u8| self.groups().iter().find(|group| group.contains(&r)).map(|group| group[0]);
+
+        let choices: &[u8] = match residue {
+            b'B' => b"DN",
+            b'Z' => b"EQ",
+            b'J' => b"IL",
+            _ => return group_of(residue).unwrap_or(b'X'),
+        };
+        match (group_of(choices[0]), group_of(choices[1])) {
+            (Some(a), Some(b)) if a == b => a,
+            _ => b'X',
+        }
+    }
+
+    /// Recodes a protein sequence into the reduced alphabet (see `recode_residue`).
+    pub fn recode(&self, sequence: &[u8]) -> Vec<u8> {
+        sequence.iter().map(|&residue| self.recode_residue(residue)).collect()
+    }
+
+    /// Builds a substitution matrix over the reduced alphabet (each group's letter, plus `X`) from a full protein
+    /// matrix such as BLOSUM62. Two groups score the mean of `full`'s scores over every pair of their residues,
+    /// rounded to the nearest integer, and `X` scores the mean of `full`'s `X` scores against the group's residues.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let blosum62 = Matrix::new(MatrixType::Blosum62);
+    /// let matrix = ReducedAlphabet::Dayhoff6.matrix(&blosum62);
+    ///
+    /// assert_eq!(blosum62.score(b'C', b'C'), matrix.score(b'C', b'C'));
+    /// assert_eq!(b"ACDHIFX", matrix.alphabet());
+    /// ```
+    pub fn matrix(&self, full: &Matrix) -> Matrix {
+        let mut groups: Vec<&[u8]> = self.groups().to_vec();
+        groups.push(b"X");
+
+        let mean_score = |a: &[u8], b: &[u8]| {
+            let total: i64 = a.iter().flat_map(|&x| b.iter().map(move |&y| full.score(x, y) as i64)).sum();
+            (total as f64 / (a.len() * b.len()) as f64).round() as i32
+        };
+        let rows: Vec<Vec<i32>> = groups
+            .iter()
+            .map(|row| groups.iter().map(|col| mean_score(row, col)).collect())
+            .collect();
+        let alphabet: Vec<u8> = groups.iter().map(|group| group[0]).collect();
+
+        Matrix::from_scores(&alphabet, &rows).expect("reduced alphabets have distinct letters")
+    }
+}
+
+impl fmt::Display for ReducedAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,550 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::borrow::Cow;
+use std::cmp::Reverse;
+
+use crate::align::{global_alignment_stats, ratio};
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::seq::reverse_complement;
+use crate::suboptimal::suboptimal_local_alignments;
+
+/// Controls how `find_repeats` looks for repeated regions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepeatParams {
+    /// The lowest local alignment score between two copies worth reporting.
+    pub min_score: i64,
+    /// The most pairs of copies reported from each comparison of one stretch of the sequence with the next.
+    pub max_alignments: usize,
+    /// Pieces of a repeat found separately are joined if they're at most this far apart in both copies.
+    pub merge_distance: usize,
+}
+
+impl Default for RepeatParams {
+    /// Copies scoring at least 30, at most 100 pairs per comparison, joined across gaps of up to 10.
+    fn default() -> Self {
+        RepeatParams {
+            min_score: 30,
+            max_alignments: 100,
+            merge_distance: 10,
+        }
+    }
+}
+
+/// Two copies of a repeated region of a sequence, the first before the second.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repeat {
+    /// The starting index (0-based) of the first copy.
+    pub first_begin: usize,
+    /// The ending index (0-based, exclusive) of the first copy.
+    pub first_end: usize,
+    /// The starting index (0-based) of the second copy.
+    pub second_begin: usize,
+    /// The ending index (0-based, exclusive) of the second copy.
+    pub second_end: usize,
+    /// The score of a global alignment of the two copies.
+    pub score: i64,
+    /// The fraction of the columns of that alignment which are exact matches.
+    pub identity: f64,
+}
+
+/// Finds regions repeated within a sequence, such as the copies of an insertion sequence in a plasmid, by aligning
+/// the sequence against itself.
+///
+/// Aligning a sequence against all of itself would only find the trivial alignment along the main diagonal, so the
+/// sequence is halved, the first half is aligned against the second with `suboptimal_local_alignments`, which masks
+/// out each hit of the second half in turn to report every copy scoring at least `min_score`, and both halves are
+/// searched the same way. A repeat whose first copy straddles a halving point is found in pieces, which are joined
+/// back together. Each reported pair of copies is then aligned globally for its score and identity. Repeats are
+/// sorted by where their first copy begins. Returns an error if parasail fails on any of the alignments.
+///
+/// Only direct repeats are found; see `find_inverted_repeats` for copies on the opposite strand.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let element = b"TCAATTGATACCAACACGCTTCCAGCCTGGCTCGATCCTG";
+/// let sequence = [
+///     &b"CCGTAATGCCTTTCCCTAACAGAGTTTTTC"[..],
+///     element,
+///     b"GAACTCGTGTTGTCGAGCGACGGAATTAGA",
+///     element,
+///     b"TCAGTTAAATGGCAGAAAACTGGCAGGGCT",
+/// ]
+/// .concat();
+///
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let repeats = find_repeats(&sequence, 3, 1, &matrix, &RepeatParams::default()).unwrap();
+///
+/// assert_eq!(1, repeats.len());
+/// assert!(repeats[0].first_begin <= 30 && repeats[0].first_end >= 70);
+/// assert!(repeats[0].second_begin <= 100 && repeats[0].second_end >= 140);
+/// assert!(repeats[0].identity > 0.9);
+/// ```
+pub fn find_repeats(
+    sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+    params: &RepeatParams,
+) -> Result<Vec<Repeat>, ParasailError> {
+    let pieces = halving_pieces(sequence, false, open_cost, gap_extend_cost, substitution_matrix, params)?;
+    merge_pieces(pieces, false, params.merge_distance)
+        .into_iter()
+        .map(|(first_begin, first_end, second_begin, second_end)| {
+            let (first, second) = (&sequence[first_begin..first_end], &sequence[second_begin..second_end]);
+            let stats = global_alignment_stats(first, second, open_cost, gap_extend_cost, substitution_matrix)?;
+            Ok(Repeat {
+                first_begin,
+                first_end,
+                second_begin,
+                second_end,
+                score: stats.score,
+                identity: ratio(stats.num_matches as usize, stats.align_length),
+            })
+        })
+        .collect()
+}
+
+/// Two arms of an inverted repeat, where the second arm is the reverse complement of the first, as in the stem of a
+/// hairpin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvertedRepeat {
+    /// The starting index (0-based) of the left arm.
+    pub left_begin: usize,
+    /// The ending index (0-based, exclusive) of the left arm.
+    pub left_end: usize,
+    /// The starting index (0-based) of the right arm.
+    pub right_begin: usize,
+    /// The ending index (0-based, exclusive) of the right arm.
+    pub right_end: usize,
+    /// The score of a global alignment of the left arm against the reverse complement of the right.
+    pub score: i64,
+    /// The fraction of the columns of that alignment which are exact matches.
+    pub identity: f64,
+}
+
+impl InvertedRepeat {
+    /// The number of residues between the arms, which would form the loop of a hairpin.
+    pub fn loop_len(&self) -> usize {
+        self.right_begin - self.left_end
+    }
+}
+
+/// Finds inverted repeats in a DNA sequence, such as the arms of hairpins or of transposon ends, by aligning the
+/// sequence against its own reverse complement.
+///
+/// The sequence is searched as in `find_repeats`, halving it and aligning the first half against the reverse
+/// complement of the second, so an arm is never aligned against itself. A perfect palindrome like `GAATTC` is an
+/// inverted repeat with no loop. Each pair of arms is aligned globally for its score and identity, and repeats are
+/// sorted by where their left arm begins. Returns an error if parasail fails on any of the alignments.
+///
+/// # Examples
+///
+/// ```
+/// use parasailors::seq::reverse_complement;
+/// use parasailors::*;
+///
+/// let arm = b"AGTAGGTGTTTCTACCCAGACGCTAATGTAGG";
+/// let hairpin = [&arm[..], b"TTTTTT", &reverse_complement(arm)].concat();
+/// let sequence = [&b"TTTCCTCATGCAATTCAAAACCAT"[..], &hairpin, b"GTCCGTAATGTAGGCGAAATAGTA"].concat();
+///
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let repeats = find_inverted_repeats(&sequence, 3, 1, &matrix, &RepeatParams::default()).unwrap();
+///
+/// assert_eq!(1, repeats.len());
+/// assert!(repeats[0].left_begin <= 24 && repeats[0].left_end >= 56);
+/// assert!(repeats[0].right_begin <= 62 && repeats[0].right_end >= 94);
+/// assert!(repeats[0].loop_len() <= 6);
+/// ```
+pub fn find_inverted_repeats(
+    sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+    params: &RepeatParams,
+) -> Result<Vec<InvertedRepeat>, ParasailError> {
+    let pieces = halving_pieces(sequence, true, open_cost, gap_extend_cost, substitution_matrix, params)?;
+    merge_pieces(pieces, true, params.merge_distance)
+        .into_iter()
+        .map(|(left_begin, left_end, right_begin, right_end)| {
+            let right = reverse_complement(&sequence[right_begin..right_end]);
+            let left = &sequence[left_begin..left_end];
+            let stats = global_alignment_stats(left, &right, open_cost, gap_extend_cost, substitution_matrix)?;
+            Ok(InvertedRepeat {
+                left_begin,
+                left_end,
+                right_begin,
+                right_end,
+                score: stats.score,
+                identity: ratio(stats.num_matches as usize, stats.align_length),
+            })
+        })
+        .collect()
+}
+
+/// Halves the sequence over and over, aligning the first half of each stretch against the second (or against its
+/// reverse complement, if `inverted`) to find pairs of copies `(first_begin, first_end, second_begin, second_end)`
+/// with the first before the second.
+fn halving_pieces(
+    sequence: &[u8],
+    inverted: bool,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+    params: &RepeatParams,
+) -> Result<Vec<(usize, usize, usize, usize)>, ParasailError> {
+    // a copy needs at least this many residues to reach the minimum score, so shorter halves aren't worth aligning
+    let best_score = substitution_matrix.score_table().into_iter().flatten().max().unwrap_or(0).max(1) as i64;
+    let min_len = (params.min_score / best_score).max(1) as usize;
+
+    let mut pieces = Vec::new();
+    let mut stretches = vec![(0, sequence.len())];
+    while let Some((start, end)) = stretches.pop() {
+        if end - start < 2 * min_len {
+            continue;
+        }
+        let middle = start + (end - start) / 2;
+        let second = if inverted {
+            Cow::Owned(reverse_complement(&sequence[middle..end]))
+        } else {
+            Cow::Borrowed(&sequence[middle..end])
+        };
+        let hits = suboptimal_local_alignments(
+            &sequence[start..middle],
+            &second,
+            open_cost,
+            gap_extend_cost,
+            substitution_matrix,
+            params.max_alignments,
+            params.min_score,
+        )?;
+        pieces.extend(hits.into_iter().map(|hit| {
+            // a hit on the reverse complement counts back from the end of the stretch
+            let (second_begin, second_end) = if inverted {
+                (end - hit.ref_end, end - hit.ref_begin)
+            } else {
+                (middle + hit.ref_begin, middle + hit.ref_end)
+            };
+            (start + hit.query_begin, start + hit.query_end, second_begin, second_end)
+        }));
+        stretches.push((start, middle));
+        stretches.push((middle, end));
+    }
+    Ok(pieces)
+}
+
+/// Controls how `find_tandem_repeats` looks for tandem repeats.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TandemParams {
+    /// The longest repeat unit looked for.
+    pub max_period: usize,
+    /// How many diagonals either side of the period the alignment may wander to, so a repeat can have indels.
+    pub band: usize,
+    /// The lowest score of the alignment of a repeat against itself shifted by one unit worth reporting.
+    pub min_score: i64,
+    /// How far the alignment's score may fall below the best so far before the repeat is taken to have ended.
+    pub x_drop: i32,
+}
+
+impl Default for TandemParams {
+    /// Units of up to 100 residues, 2 diagonals either side, scores of at least 20, and an X-drop of 10.
+    fn default() -> Self {
+        TandemParams {
+            max_period: 100,
+            band: 2,
+            min_score: 20,
+            x_drop: 10,
+        }
+    }
+}
+
+/// A run of copies of a short unit, one after the other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TandemRepeat {
+    /// The starting index (0-based) of the run.
+    pub begin: usize,
+    /// The ending index (0-based, exclusive) of the run.
+    pub end: usize,
+    /// The length of the repeated unit.
+    pub period: usize,
+    /// The number of copies of the unit, which needn't be whole.
+    pub copies: f64,
+    /// The fraction of the columns of the alignment of the run against itself, shifted by one unit, which are exact
+    /// matches.
+    pub purity: f64,
+    /// The score of that alignment.
+    pub score: i64,
+}
+
+/// Finds tandem repeats, such as microsatellites, with units of up to `max_period` residues.
+///
+/// For each period, the sequence is locally aligned against itself within `band` diagonals of the one `period`
+/// residues off the main diagonal, so each copy of a unit is aligned against the next while indels are still
+/// allowed. A stretch ends once its score drops `x_drop` below its best, and the next can start right after. Since a
+/// repeat also turns up at multiples of its period (with lower scores, being a unit shorter), overlapping stretches
+/// are resolved in favor of the best scoring, and a stretch is dropped if more than half of it is covered by a better
+/// one. Repeats are sorted by where they begin.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let before = b"CTTGTCTCCAAGTACCCATTTAGTAGACAAATCGTTCCAT";
+/// let after = b"CACCAATTCGCTGGTTGTTGAACTATACGACCGGGGCACA";
+/// let sequence = [&before[..], &b"ACG".repeat(10), after].concat();
+///
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let repeats = find_tandem_repeats(&sequence, 3, 1, &matrix, &TandemParams::default());
+///
+/// assert_eq!(1, repeats.len());
+/// assert_eq!(3, repeats[0].period);
+/// assert_eq!((40, 70), (repeats[0].begin, repeats[0].end));
+/// assert_eq!(10.0, repeats[0].copies);
+/// assert_eq!(1.0, repeats[0].purity);
+/// ```
+pub fn find_tandem_repeats(
+    sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+    params: &TandemParams,
+) -> Vec<TandemRepeat> {
+    let score = |a: u8, b: u8| substitution_matrix.score(a, b);
+    tandem_repeats(sequence, &score, open_cost, gap_extend_cost, params)
+}
+
+/// Finds tandem repeats as `find_tandem_repeats` does, scoring pairs of residues with `score`.
+fn tandem_repeats<F>(
+    sequence: &[u8],
+    score: &F,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    params: &TandemParams,
+) -> Vec<TandemRepeat>
+where
+    F: Fn(u8, u8) -> i32,
+{
+    let mut candidates = Vec::new();
+    for period in 1..=params.max_period.min(sequence.len() / 2) {
+        candidates.extend(tandem_stretches(sequence, period, score, open_cost, gap_extend_cost, params));
+    }
+
+    // best first, and the shorter period on a tie, since the same run scores a little lower at each multiple
+    candidates.sort_by(|a, b| b.score.cmp(&a.score).then(a.period.cmp(&b.period)).then(a.begin.cmp(&b.begin)));
+    let mut repeats: Vec<TandemRepeat> = Vec::new();
+    for candidate in candidates {
+        let covered = |r: &TandemRepeat| candidate.end.min(r.end).saturating_sub(candidate.begin.max(r.begin));
+        if repeats.iter().all(|r| 2 * covered(r) <= candidate.end - candidate.begin) {
+            repeats.push(candidate);
+        }
+    }
+    repeats.sort_by_key(|r| (r.begin, r.period));
+    repeats
+}
+
+/// The best alignment ending in a cell of `tandem_stretches`: its score, where it started in the sequence, and how
+/// many matches and columns it has.
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    score: i32,
+    start: usize,
+    matches: usize,
+    length: usize,
+}
+
+const EMPTY: Cell = Cell {
+    score: 0,
+    start: 0,
+    matches: 0,
+    length: 0,
+};
+
+const DEAD: Cell = Cell {
+    score: i32::MIN / 4,
+    ..EMPTY
+};
+
+/// Locally aligns the sequence against itself near the diagonal `period` off the main one, returning every stretch
+/// reaching the minimum score. A stretch's period is whichever diagonal in the band has the most identical pairs of
+/// residues within it, which is `period` unless the unit is really a little longer or shorter.
+///
+/// Rows only hold the band, so cell `k` of row `i` is column `i + low + k`, where `low` is the lowest diagonal in the
+/// band: the cell diagonally up and to the left is cell `k` of the row above, and the one directly above is cell
+/// `k + 1`. Diagonals never reach the main one, so no residue is aligned against itself.
+fn tandem_stretches<F>(
+    sequence: &[u8],
+    period: usize,
+    score: &F,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    params: &TandemParams,
+) -> Vec<TandemRepeat>
+where
+    F: Fn(u8, u8) -> i32,
+{
+    let n = sequence.len();
+    let low = period.saturating_sub(params.band).max(1);
+    let width = period + params.band - low + 1;
+    let better = |a: Cell, b: Cell| if b.score > a.score { b } else { a };
+    let gap = |from: Cell, cost: i32| Cell {
+        score: (from.score - cost).max(DEAD.score),
+        length: from.length + 1,
+        ..from
+    };
+
+    let mut stretches = Vec::new();
+    let mut emit = |best: (Cell, usize)| {
+        let (cell, end) = best;
+        if cell.score as i64 >= params.min_score {
+            let (begin, len) = (cell.start, end - cell.start);
+            let identical = |d: usize| (begin..end - d).filter(|&i| sequence[i] == sequence[i + d]).count();
+            let period = (low..low + width)
+                .filter(|&d| d < len)
+                .max_by_key(|&d| (identical(d), Reverse(d)))
+                .unwrap_or(low);
+            stretches.push(TandemRepeat {
+                begin: cell.start,
+                end,
+                period,
+                copies: (end - cell.start) as f64 / period as f64,
+                purity: ratio(cell.matches, cell.length),
+                score: cell.score as i64,
+            });
+        }
+    };
+
+    let (mut h, mut f) = (vec![EMPTY; width], vec![DEAD; width]);
+    let (mut cur_h, mut cur_f) = (vec![EMPTY; width], vec![DEAD; width]);
+    // the best cell of the current stretch, with the column it ends at
+    let mut best: Option<(Cell, usize)> = None;
+    for i in 1..=n {
+        let mut e = DEAD;
+        let mut row_best: Option<(Cell, usize)> = None;
+        for k in 0..width {
+            let j = i + low + k;
+            if j > n {
+                cur_h[k] = EMPTY;
+                cur_f[k] = DEAD;
+                continue;
+            }
+
+            let (a, b) = (sequence[i - 1], sequence[j - 1]);
+            let matched = if h[k].score > 0 {
+                Cell {
+                    score: h[k].score + score(a, b),
+                    matches: h[k].matches + (a == b) as usize,
+                    length: h[k].length + 1,
+                    ..h[k]
+                }
+            } else {
+                Cell {
+                    score: score(a, b),
+                    start: i - 1,
+                    matches: (a == b) as usize,
+                    length: 1,
+                }
+            };
+            cur_f[k] = match (h.get(k + 1), f.get(k + 1)) {
+                (Some(&up_h), Some(&up_f)) => better(gap(up_h, open_cost), gap(up_f, gap_extend_cost)),
+                _ => DEAD,
+            };
+            let cell = better(better(matched, cur_f[k]), e);
+            cur_h[k] = if cell.score > 0 { cell } else { EMPTY };
+            e = better(gap(cur_h[k], open_cost), gap(e, gap_extend_cost));
+
+            if cur_h[k].score > row_best.map_or(0, |(c, _)| c.score) {
+                row_best = Some((cur_h[k], j));
+            }
+        }
+
+        let row_score = row_best.map_or(0, |(cell, _)| cell.score);
+        if row_score > best.map_or(0, |(cell, _)| cell.score) {
+            best = row_best;
+        }
+        if let Some(stretch) = best {
+            if row_score == 0 || stretch.0.score - row_score > params.x_drop {
+                emit(stretch);
+                best = None;
+                // start afresh, so the next stretch doesn't carry on from this one
+                cur_h.iter_mut().for_each(|cell| *cell = EMPTY);
+                cur_f.iter_mut().for_each(|cell| *cell = DEAD);
+            }
+        }
+        std::mem::swap(&mut h, &mut cur_h);
+        std::mem::swap(&mut f, &mut cur_f);
+    }
+    if let Some(stretch) = best {
+        emit(stretch);
+    }
+    stretches
+}
+
+/// Joins pieces `(first_begin, first_end, second_begin, second_end)` of the same repeat which follow on from each
+/// other in both copies, as long as the joined copies don't overlap. Returns them sorted.
+///
+/// The second copy of an inverted repeat runs backwards, so a piece carrying on from another ends where the other's
+/// second copy begins, rather than beginning where it ends.
+fn merge_pieces(
+    mut pieces: Vec<(usize, usize, usize, usize)>,
+    inverted: bool,
+    distance: usize,
+) -> Vec<(usize, usize, usize, usize)> {
+    pieces.sort_unstable();
+    let near = |a: usize, b: usize| a.abs_diff(b) <= distance;
+    let follows = |repeat: &(usize, usize, usize, usize), piece: &(usize, usize, usize, usize)| {
+        let continues = if inverted { near(piece.3, repeat.2) } else { near(repeat.3, piece.2) };
+        near(repeat.1, piece.0) && continues && piece.1.max(repeat.1) <= repeat.2.min(piece.2)
+    };
+
+    let mut merged: Vec<(usize, usize, usize, usize)> = Vec::with_capacity(pieces.len());
+    for piece in pieces {
+        match merged.iter_mut().find(|repeat| follows(repeat, &piece)) {
+            Some(repeat) => {
+                repeat.1 = repeat.1.max(piece.1);
+                repeat.2 = repeat.2.min(piece.2);
+                repeat.3 = repeat.3.max(piece.3);
+            }
+            None => merged.push(piece),
+        }
+    }
+    merged.sort_unstable();
+    merged
+}
+
+#[test]
+fn test_merge_pieces() {
+    // a repeat split where its first copy crosses a halving point, plus an unrelated one
+    let pieces = vec![(150, 190, 400, 440), (100, 150, 350, 400), (10, 20, 30, 40)];
+    assert_eq!(vec![(10, 20, 30, 40), (100, 190, 350, 440)], merge_pieces(pieces, false, 5));
+
+    // joining these would make the copies overlap
+    let pieces = vec![(0, 10, 12, 22), (12, 22, 24, 34)];
+    assert_eq!(2, merge_pieces(pieces, false, 5).len());
+
+    // the arms of a hairpin, split where the left arm crosses a halving point
+    let pieces = vec![(120, 150, 200, 230), (100, 120, 230, 250)];
+    assert_eq!(vec![(100, 150, 200, 250)], merge_pieces(pieces, true, 5));
+}
+
+#[test]
+fn test_tandem_repeats() {
+    let score = |a: u8, b: u8| if a == b { 1 } else { -1 };
+    let unit = b"GATTACA";
+    let mut sequence = b"CGCGTTGACCTGAGCTTCAGGTACCATGCAGATCC".to_vec();
+    sequence.extend(unit.repeat(2));
+    // a copy with one residue missing
+    sequence.extend(b"GATACA");
+    sequence.extend(unit.repeat(2));
+    sequence.extend(b"TCGGCTATTCGCAGGCTCAAGCCGTCGTTACGGCT");
+
+    let repeats = tandem_repeats(&sequence, &score, 3, 1, &TandemParams::default());
+    assert_eq!(1, repeats.len());
+    assert_eq!(7, repeats[0].period);
+    assert!(repeats[0].begin <= 35 && repeats[0].end >= 69);
+    assert!(repeats[0].purity > 0.9 && repeats[0].purity < 1.0);
+}
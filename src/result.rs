@@ -0,0 +1,358 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::ffi::CStr;
+
+use libc::{c_char, c_int};
+use parasail_sys::{
+    parasail_cigar_decode_len, parasail_cigar_decode_op, parasail_cigar_free, parasail_result,
+    parasail_result_free, parasail_result_get_cigar, parasail_result_get_length, parasail_result_get_matches,
+    parasail_result_get_similar, parasail_result_get_traceback, parasail_result_is_diag, parasail_result_is_nw,
+    parasail_result_is_saturated, parasail_result_is_scan, parasail_result_is_sg, parasail_result_is_stats,
+    parasail_result_is_striped, parasail_result_is_sw, parasail_result_is_trace, parasail_traceback,
+    parasail_traceback_free,
+};
+
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::profile::ScoreWidth;
+
+// the bits of a result's flag recording the integer width its kernel ran at, from parasail.h (after the bits for
+// stats, tables, rows and columns, and traces)
+const FLAG_BITS_8: i32 = 1 << 20;
+const FLAG_BITS_16: i32 = 1 << 21;
+const FLAG_BITS_32: i32 = 1 << 22;
+const FLAG_BITS_64: i32 = 1 << 23;
+
+/// An alignment result from one of parasail's kernels, freed when dropped.
+///
+/// Every alignment function in this crate runs a kernel, reads what it needs from one of these, and lets it go, so
+/// the result is freed exactly once even if reading it panics.
+pub struct ParasailResult {
+    internal_rep: *mut parasail_result,
+}
+
+impl Drop for ParasailResult {
+    fn drop(&mut self) {
+        unsafe { parasail_result_free(self.internal_rep) }
+    }
+}
+
+impl ParasailResult {
+    /// Takes ownership of a result returned by a parasail kernel called directly through `parasail_sys`, so it can be
+    /// read with these methods and is freed on drop.
+    ///
+    /// # Safety
+    ///
+    /// `result` must be a valid result from a parasail kernel that nothing else frees or uses after this.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParasailError::Kernel` if `result` is null, which is how parasail reports running out of memory or
+    /// rejecting its arguments (such as an empty sequence).
+    pub unsafe fn from_raw(result: *mut parasail_result) -> Result<Self, ParasailError> {
+        Ok(ParasailResult {
+            internal_rep: non_null(result, "an alignment result")?,
+        })
+    }
+
+    /// The underlying parasail result, for passing to `parasail_sys` functions this struct doesn't wrap.
+    ///
+    /// The result is still owned by this struct: don't free it, and don't use the pointer after this struct is
+    /// dropped.
+    pub fn as_raw(&self) -> *mut parasail_result {
+        self.internal_rep
+    }
+
+    /// The alignment score.
+    pub fn score(&self) -> i32 {
+        unsafe { (*self.internal_rep).score }
+    }
+
+    /// The index (0-based) just past the last query character of the alignment.
+    pub fn query_end(&self) -> usize {
+        unsafe { ((*self.internal_rep).end_query + 1) as usize }
+    }
+
+    /// The index (0-based) just past the last reference character of the alignment.
+    pub fn ref_end(&self) -> usize {
+        unsafe { ((*self.internal_rep).end_ref + 1) as usize }
+    }
+
+    /// parasail's raw bit flags describing the kernel which produced the result.
+    pub fn flag(&self) -> i32 {
+        unsafe { (*self.internal_rep).flag }
+    }
+
+    /// Whether the result came from a global (Needleman-Wunsch) kernel.
+    pub fn is_nw(&self) -> bool {
+        unsafe { parasail_result_is_nw(self.internal_rep) != 0 }
+    }
+
+    /// Whether the result came from a semi-global kernel, including those with only some end gaps free.
+    pub fn is_sg(&self) -> bool {
+        unsafe { parasail_result_is_sg(self.internal_rep) != 0 }
+    }
+
+    /// Whether the result came from a local (Smith-Waterman) kernel.
+    pub fn is_sw(&self) -> bool {
+        unsafe { parasail_result_is_sw(self.internal_rep) != 0 }
+    }
+
+    /// Whether the result came from a trace kernel, and so can be walked for a traceback or CIGAR.
+    pub fn is_trace(&self) -> bool {
+        unsafe { parasail_result_is_trace(self.internal_rep) != 0 }
+    }
+
+    /// Whether the result came from a striped vectorized kernel.
+    pub fn is_striped(&self) -> bool {
+        unsafe { parasail_result_is_striped(self.internal_rep) != 0 }
+    }
+
+    /// Whether the result came from a prefix scan vectorized kernel.
+    pub fn is_scan(&self) -> bool {
+        unsafe { parasail_result_is_scan(self.internal_rep) != 0 }
+    }
+
+    /// Whether the result came from an anti-diagonal vectorized kernel.
+    pub fn is_diag(&self) -> bool {
+        unsafe { parasail_result_is_diag(self.internal_rep) != 0 }
+    }
+
+    /// The integer width the kernel actually ran at. Saturating kernels report 8 bits unless the score overflowed
+    /// and they retried wider, so this is never `ScoreWidth::Saturating`. `None` if parasail didn't record a width.
+    pub fn score_width(&self) -> Option<ScoreWidth> {
+        let flag = self.flag();
+        if flag & FLAG_BITS_8 != 0 {
+            Some(ScoreWidth::Bits8)
+        } else if flag & FLAG_BITS_16 != 0 {
+            Some(ScoreWidth::Bits16)
+        } else if flag & FLAG_BITS_32 != 0 {
+            Some(ScoreWidth::Bits32)
+        } else if flag & FLAG_BITS_64 != 0 {
+            Some(ScoreWidth::Bits64)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the score overflowed the kernel's integer width, in which case the score and positions are wrong.
+    /// Saturating kernels retry at a wider width on overflow, so only fixed width kernels report this.
+    pub fn is_saturated(&self) -> bool {
+        unsafe { parasail_result_is_saturated(self.internal_rep) != 0 }
+    }
+
+    /// Whether the result came from a stats kernel, and so has `matches`, `similar`, and `length`.
+    pub fn is_stats(&self) -> bool {
+        unsafe { parasail_result_is_stats(self.internal_rep) != 0 }
+    }
+
+    /// The number of exactly matching aligned characters, if the result came from a stats kernel.
+    pub fn matches(&self) -> Option<usize> {
+        self.is_stats().then(|| unsafe { parasail_result_get_matches(self.internal_rep) as usize })
+    }
+
+    /// The number of positively scoring aligned characters, if the result came from a stats kernel.
+    pub fn similar(&self) -> Option<usize> {
+        self.is_stats().then(|| unsafe { parasail_result_get_similar(self.internal_rep) as usize })
+    }
+
+    /// The number of alignment columns, if the result came from a stats kernel.
+    pub fn length(&self) -> Option<usize> {
+        self.is_stats().then(|| unsafe { parasail_result_get_length(self.internal_rep) as usize })
+    }
+
+    /// Has parasail walk the traceback of a trace kernel's result and write out the aligned strings.
+    /// `query_sequence` and `database_sequence` must be the sequences the kernel aligned. Returns an error if parasail
+    /// couldn't build the traceback, or if the aligned strings aren't valid UTF-8 (from non-ASCII sequences).
+    pub(crate) fn traceback(
+        &self,
+        query_sequence: &[u8],
+        database_sequence: &[u8],
+        substitution_matrix: &Matrix,
+    ) -> Result<Traceback, ParasailError> {
+        unsafe {
+            // parasail reads exactly the given lengths, so the sequences don't need a terminating NUL
+            let traceback = parasail_result_get_traceback(
+                self.internal_rep,
+                query_sequence.as_ptr() as *const c_char,
+                query_sequence.len() as c_int,
+                database_sequence.as_ptr() as *const c_char,
+                database_sequence.len() as c_int,
+                **substitution_matrix,
+                b'|' as c_char,
+                b'|' as c_char,
+                b':' as c_char,
+            );
+            // owned from here, so it's freed even if the strings are rejected
+            let traceback = Traceback {
+                internal_rep: non_null(traceback, "a traceback")?,
+            };
+            let strings = &*traceback.internal_rep;
+            for &string in &[strings.query, strings.comp, strings.ref_] {
+                if CStr::from_ptr(string).to_str().is_err() {
+                    return Err(ParasailError::InvalidTraceback);
+                }
+            }
+            Ok(traceback)
+        }
+    }
+
+    /// Has parasail walk the traceback of a trace kernel's result and run-length encode it. `query_sequence` and
+    /// `database_sequence` must be the sequences the kernel aligned. Returns an error if parasail couldn't build it.
+    pub(crate) fn cigar(
+        &self,
+        query_sequence: &[u8],
+        database_sequence: &[u8],
+        substitution_matrix: &Matrix,
+    ) -> Result<Cigar, ParasailError> {
+        unsafe {
+            let cigar = non_null(
+                parasail_result_get_cigar(
+                    self.internal_rep,
+                    query_sequence.as_ptr(),
+                    query_sequence.len() as c_int,
+                    database_sequence.as_ptr(),
+                    database_sequence.len() as c_int,
+                    **substitution_matrix,
+                ),
+                "a CIGAR string",
+            )?;
+
+            let mut ops = Vec::with_capacity((*cigar).len as usize);
+            for i in 0..(*cigar).len as isize {
+                let packed = *(*cigar).seq.offset(i);
+                ops.push((
+                    parasail_cigar_decode_op(packed) as u8,
+                    parasail_cigar_decode_len(packed) as usize,
+                ));
+            }
+            let decoded = Cigar {
+                ops,
+                query_begin: (*cigar).beg_query as usize,
+                ref_begin: (*cigar).beg_ref as usize,
+            };
+
+            parasail_cigar_free(cigar);
+            Ok(decoded)
+        }
+    }
+}
+
+/// Hands back a pointer parasail just returned, or an error if it's null. parasail returns null when it runs out of
+/// memory or rejects its arguments (such as an empty sequence), and this makes that an error rather than a null
+/// dereference.
+pub(crate) fn non_null<T>(ptr: *mut T, what: &'static str) -> Result<*mut T, ParasailError> {
+    if ptr.is_null() {
+        Err(ParasailError::Kernel { what })
+    } else {
+        Ok(ptr)
+    }
+}
+
+/// The aligned query, comparison, and reference strings parasail builds from a trace kernel's result, freed when
+/// dropped.
+///
+/// The comparison string marks matches and positively scoring substitutions with `|`, other substitutions with `:`,
+/// and gaps with a space.
+pub struct Traceback {
+    internal_rep: *mut parasail_traceback,
+}
+
+impl Drop for Traceback {
+    fn drop(&mut self) {
+        unsafe { parasail_traceback_free(self.internal_rep) }
+    }
+}
+
+impl Traceback {
+    /// The query with `-` for gaps.
+    pub fn query(&self) -> &str {
+        unsafe { trace_str((*self.internal_rep).query) }
+    }
+
+    /// The comparison between the query and the reference.
+    pub fn comp(&self) -> &str {
+        unsafe { trace_str((*self.internal_rep).comp) }
+    }
+
+    /// The reference with `-` for gaps.
+    pub fn reference(&self) -> &str {
+        unsafe { trace_str((*self.internal_rep).ref_) }
+    }
+}
+
+unsafe fn trace_str<'a>(trace: *const c_char) -> &'a str {
+    // `ParasailResult::traceback` only hands out tracebacks whose strings are valid UTF-8
+    std::str::from_utf8_unchecked(CStr::from_ptr(trace).to_bytes())
+}
+
+/// A run-length encoded traceback, as parasail's CIGAR operations (`=`, `X`, `I`, and `D`) and their lengths.
+pub(crate) struct Cigar {
+    pub(crate) ops: Vec<(u8, usize)>,
+    /// The index (0-based) of the first query character the operations cover.
+    pub(crate) query_begin: usize,
+    /// The index (0-based) of the first reference character the operations cover.
+    pub(crate) ref_begin: usize,
+}
+
+impl Cigar {
+    /// Formats the operations as a CIGAR string, as `parasail_cigar_decode` would.
+    pub(crate) fn to_cigar_string(&self) -> String {
+        self.ops.iter().map(|&(op, len)| format!("{}{}", len, op as char)).collect()
+    }
+
+    /// Drops the gaps at the start of the operations, advancing the begin positions past them. parasail walks a local
+    /// or semi-global trace back through any leading gaps to the start of a sequence, but they aren't part of the
+    /// aligned region.
+    pub(crate) fn strip_leading_gaps(&mut self) {
+        let mut leading = 0;
+        for &(op, len) in &self.ops {
+            match op {
+                b'D' => self.ref_begin += len,
+                b'I' => self.query_begin += len,
+                _ => break,
+            }
+            leading += 1;
+        }
+        self.ops.drain(..leading);
+    }
+}
+
+#[test]
+fn test_score_width_from_flag() {
+    use crate::matrix::{Matrix, MatrixType};
+    use parasail_sys::{parasail_nw_striped_16, parasail_nw_trace_striped_32};
+
+    let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    let (query, reference) = (b"ACGTACGT", b"ACGTTCGT");
+    let (striped, traced) = unsafe {
+        let striped = ParasailResult::from_raw(parasail_nw_striped_16(
+            query.as_ptr(),
+            query.len() as c_int,
+            reference.as_ptr(),
+            reference.len() as c_int,
+            1,
+            1,
+            *matrix,
+        ))
+        .unwrap();
+        let traced = ParasailResult::from_raw(parasail_nw_trace_striped_32(
+            query.as_ptr(),
+            query.len() as c_int,
+            reference.as_ptr(),
+            reference.len() as c_int,
+            1,
+            1,
+            *matrix,
+        ))
+        .unwrap();
+        (striped, traced)
+    };
+    assert_eq!(Some(ScoreWidth::Bits16), striped.score_width());
+    assert!(!striped.is_trace());
+    assert_eq!(Some(ScoreWidth::Bits32), traced.score_width());
+    assert!(traced.is_trace());
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::marker::PhantomData;
+
+use crate::error::AlignError;
+use crate::sequence::{Alphabet, Seq};
+
+/// Cleans up and checks sequences from an untrusted source, such as the body of a web request, before they get
+/// anywhere near parasail.
+///
+/// Whitespace (including line breaks from pasted FASTA) and NUL bytes are dropped, and everything else must be a
+/// residue of the alphabet, or of a narrower whitelist given with `with_allowed`. Sequences that are empty or longer
+/// than the cap once cleaned up are rejected, so a single request can't tie up an aligner with a huge input. Errors
+/// point at positions in the input as given, not as cleaned up.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let sanitizer = Sanitizer::<Dna>::new(1000).with_allowed(b"ACGTN").with_uppercase();
+///
+/// let sequence = sanitizer.sanitize(b"acgt acgt\r\nNNAC\0").unwrap();
+/// assert_eq!(b"ACGTACGTNNAC", sequence.as_bytes());
+///
+/// assert_eq!(
+///     Err(AlignError::InvalidResidue { pos: 2, residue: b'R', alphabet: "DNA" }),
+///     sanitizer.sanitize(b"ACRT")
+/// );
+/// assert_eq!(Err(AlignError::EmptySequence), sanitizer.sanitize(b" \n "));
+/// assert!(Sanitizer::<Dna>::new(4).sanitize(b"ACGTA").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sanitizer<A> {
+    max_len: usize,
+    allowed: Option<&'static [u8]>,
+    uppercase: bool,
+    alphabet: PhantomData<A>,
+}
+
+impl<A: Alphabet> Sanitizer<A> {
+    /// Creates a sanitizer accepting any residue of the alphabet, in sequences of at most `max_len` residues.
+    pub fn new(max_len: usize) -> Self {
+        Sanitizer {
+            max_len,
+            allowed: None,
+            uppercase: false,
+            alphabet: PhantomData,
+        }
+    }
+
+    /// Only accepts the given residues (in either case), e.g. `b"ACGT"` to turn away ambiguity codes. Residues outside
+    /// the alphabet are still rejected even if listed.
+    pub fn with_allowed(mut self, allowed: &'static [u8]) -> Self {
+        self.allowed = Some(allowed);
+        self
+    }
+
+    /// Upper-cases the cleaned up sequence, since substitution matrices are usually defined over upper case letters.
+    pub fn with_uppercase(mut self) -> Self {
+        self.uppercase = true;
+        self
+    }
+
+    /// The most residues a sequence may have.
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    /// Cleans up and checks one sequence, returning it tagged with the alphabet.
+    pub fn sanitize(&self, input: &[u8]) -> Result<Seq<A>, AlignError> {
+        let mut residues = Vec::with_capacity(input.len().min(self.max_len));
+        for (pos, &c) in input.iter().enumerate() {
+            if c == 0 || c.is_ascii_whitespace() {
+                continue;
+            }
+            if !self.accepts(c) {
+                return Err(AlignError::InvalidResidue {
+                    pos,
+                    residue: c,
+                    alphabet: A::NAME,
+                });
+            }
+            if residues.len() == self.max_len {
+                // count the rest without keeping it, so the error says how long the input really was
+                let rest = input[pos..].iter().filter(|&&c| c != 0 && !c.is_ascii_whitespace()).count();
+                return Err(AlignError::SequenceTooLong {
+                    len: residues.len() + rest,
+                    max: self.max_len,
+                });
+            }
+            residues.push(if self.uppercase { c.to_ascii_uppercase() } else { c });
+        }
+
+        if residues.is_empty() {
+            return Err(AlignError::EmptySequence);
+        }
+        Seq::new(residues)
+    }
+
+    /// Cleans up and checks a query and a reference. If both are bad, the error is the query's.
+    pub fn sanitize_pair(&self, query: &[u8], reference: &[u8]) -> Result<(Seq<A>, Seq<A>), AlignError> {
+        Ok((self.sanitize(query)?, self.sanitize(reference)?))
+    }
+
+    fn accepts(&self, c: u8) -> bool {
+        A::contains(c) && self.allowed.map_or(true, |allowed| allowed.iter().any(|a| a.eq_ignore_ascii_case(&c)))
+    }
+}
@@ -0,0 +1,408 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::batch::AlignmentMode;
+use crate::matrix::Matrix;
+
+/// The cost of one kind of gap, in parasail's convention: a gap of length `L` costs `open + (L - 1) * extend`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GapCosts {
+    /// The cost of the first gap column.
+    pub open: i32,
+    /// The cost of each further gap column.
+    pub extend: i32,
+}
+
+/// Separate gap costs for the two sides of an alignment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AsymmetricGaps {
+    /// The cost of query characters with no reference counterpart (`I` in a CIGAR string).
+    pub insertion: GapCosts,
+    /// The cost of reference characters with no query counterpart (`D` in a CIGAR string).
+    pub deletion: GapCosts,
+}
+
+impl AsymmetricGaps {
+    /// The same costs for both kinds of gap.
+    pub fn symmetric(open: i32, extend: i32) -> Self {
+        let costs = GapCosts { open, extend };
+        AsymmetricGaps {
+            insertion: costs,
+            deletion: costs,
+        }
+    }
+}
+
+/// The score and end positions of an alignment from the scalar backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScalarAlignment {
+    /// The score according to the substitution matrix and gap costs used.
+    pub score: i64,
+    /// The ending index (0-based, exclusive) of the alignment in the query.
+    pub query_end: usize,
+    /// The ending index (0-based, exclusive) of the alignment in the reference.
+    pub ref_end: usize,
+}
+
+/// Far enough below any real score to never win, and far enough above `i64::MIN` to never overflow.
+const NEG_INF: i64 = i64::MIN / 4;
+
+/// Scores an alignment with different costs for insertions and deletions, which parasail's kernels can't express.
+///
+/// This runs a plain (non-vectorized) Gotoh dynamic program in pure Rust, so it's much slower than the parasail
+/// kernels and only worth using when the two sides really need different costs, e.g. an error-prone long read
+/// against a polished reference. The modes match the parasail functions: `SemiGlobal` leaves gaps at either end of
+/// either sequence free, as `semi_global_alignment_score` does. Ties between end positions go to the one found first
+/// scanning the query, then the reference, from the start.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let cheap_insertions = AsymmetricGaps {
+///     insertion: GapCosts { open: 1, extend: 1 },
+///     deletion: GapCosts { open: 6, extend: 2 },
+/// };
+///
+/// // the query carries an extra base, which only costs 1
+/// let result = asymmetric_alignment(b"ACGTTACGT", b"ACGTACGT", AlignmentMode::Global, &cheap_insertions, &matrix);
+/// assert_eq!(7, result.score);
+///
+/// // the query lacks a base, which costs 6
+/// let result = asymmetric_alignment(b"ACGACGT", b"ACGTACGT", AlignmentMode::Global, &cheap_insertions, &matrix);
+/// assert_eq!(1, result.score);
+/// ```
+pub fn asymmetric_alignment(
+    query: &[u8],
+    reference: &[u8],
+    mode: AlignmentMode,
+    gaps: &AsymmetricGaps,
+    matrix: &Matrix,
+) -> ScalarAlignment {
+    let (ins_open, ins_extend) = (gaps.insertion.open as i64, gaps.insertion.extend as i64);
+    let (del_open, del_extend) = (gaps.deletion.open as i64, gaps.deletion.extend as i64);
+    let free_ends = mode != AlignmentMode::Global;
+    let local = mode == AlignmentMode::Local;
+
+    // best score ending at (i, j) in any state, and ending in an insertion, for the previous query row
+    let mut h: Vec<i64> = Vec::with_capacity(reference.len() + 1);
+    let mut insertion: Vec<i64> = vec![NEG_INF; reference.len() + 1];
+    h.push(0);
+    for j in 1..=reference.len() {
+        h.push(if free_ends { 0 } else { -(del_open + (j as i64 - 1) * del_extend) });
+    }
+
+    let mut best = ScalarAlignment {
+        score: if local { 0 } else { NEG_INF },
+        query_end: 0,
+        ref_end: 0,
+    };
+
+    for (i, &q) in query.iter().enumerate() {
+        let row = i + 1;
+        let mut diagonal = h[0];
+        h[0] = if free_ends { 0 } else { -(ins_open + (row as i64 - 1) * ins_extend) };
+        // best score ending in a deletion at (row, j)
+        let mut deletion = NEG_INF;
+
+        for (j, &r) in reference.iter().enumerate() {
+            let col = j + 1;
+            // an insertion consumes a query character, coming down from the previous row
+            insertion[col] = (h[col] - ins_open).max(insertion[col] - ins_extend);
+            // a deletion consumes a reference character, coming across from the previous column
+            deletion = (h[col - 1] - del_open).max(deletion - del_extend);
+
+            let mut score = (diagonal + matrix.score(q, r) as i64).max(insertion[col]).max(deletion);
+            if local {
+                score = score.max(0);
+            }
+            diagonal = h[col];
+            h[col] = score;
+
+            let eligible = match mode {
+                AlignmentMode::Local => true,
+                AlignmentMode::SemiGlobal => row == query.len() || col == reference.len(),
+                AlignmentMode::Global => row == query.len() && col == reference.len(),
+            };
+            if eligible && score > best.score {
+                best = ScalarAlignment {
+                    score,
+                    query_end: row,
+                    ref_end: col,
+                };
+            }
+        }
+    }
+
+    // an empty sequence leaves the loop above with nothing to compare
+    if query.is_empty() || reference.is_empty() {
+        let score = if free_ends {
+            0
+        } else if query.is_empty() && !reference.is_empty() {
+            -(del_open + (reference.len() as i64 - 1) * del_extend)
+        } else if !query.is_empty() {
+            -(ins_open + (query.len() as i64 - 1) * ins_extend)
+        } else {
+            0
+        };
+        best = ScalarAlignment {
+            score,
+            query_end: query.len(),
+            ref_end: reference.len(),
+        };
+    }
+
+    best
+}
+
+/// Two affine gap costs of which each gap pays the cheaper, so short gaps can be expensive per base while long gaps
+/// (e.g. across a structural variant) are cheap per base. A gap of length `L` costs
+/// `min(short.open + (L - 1) * short.extend, long.open + (L - 1) * long.extend)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TwoPieceGaps {
+    /// The costs that are cheaper for short gaps, usually a low open and a high extend.
+    pub short: GapCosts,
+    /// The costs that are cheaper for long gaps, usually a high open and a low extend.
+    pub long: GapCosts,
+}
+
+impl TwoPieceGaps {
+    /// The cost of a gap of `len` columns, which must be at least 1.
+    pub fn cost(&self, len: usize) -> i64 {
+        let piece = |costs: GapCosts| costs.open as i64 + (len as i64 - 1) * costs.extend as i64;
+        piece(self.short).min(piece(self.long))
+    }
+}
+
+/// Scores an alignment with two-piece affine gap costs, which parasail's kernels can't express.
+///
+/// A single affine cost either makes long gaps prohibitively expensive or lets short gaps through too cheaply, so
+/// alignments spanning a large insertion or deletion come out fragmented or clipped. Like `asymmetric_alignment`, this
+/// runs a plain dynamic program in pure Rust (with two gap states per side), so it's much slower than the parasail
+/// kernels. The modes and tie-breaking are the same as for `asymmetric_alignment`.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::create("ACGT", 2, -4);
+/// let gaps = TwoPieceGaps {
+///     short: GapCosts { open: 4, extend: 2 },
+///     long: GapCosts { open: 24, extend: 0 },
+/// };
+/// assert_eq!(24, gaps.cost(20));
+///
+/// // a 20 base deletion costs 24 instead of 42
+/// let query = b"ACGTACGGTCATGCAAGTCCATGACGTTAG";
+/// let reference = b"ACGTACGGTCATGCATTTTTTTTTTTTTTTTTTTTAGTCCATGACGTTAG";
+/// let result = two_piece_alignment(query, reference, AlignmentMode::Global, &gaps, &matrix);
+/// assert_eq!(60 - 24, result.score);
+/// ```
+pub fn two_piece_alignment(
+    query: &[u8],
+    reference: &[u8],
+    mode: AlignmentMode,
+    gaps: &TwoPieceGaps,
+    matrix: &Matrix,
+) -> ScalarAlignment {
+    let pieces = [gaps.short, gaps.long].map(|costs| (costs.open as i64, costs.extend as i64));
+    let free_ends = mode != AlignmentMode::Global;
+    let local = mode == AlignmentMode::Local;
+    let end_gap = |len: usize| if free_ends || len == 0 { 0 } else { -gaps.cost(len) };
+
+    // best score ending at (i, j) in any state for the previous query row, and ending in an insertion under each
+    // piece's costs
+    let mut h: Vec<i64> = (0..=reference.len()).map(end_gap).collect();
+    let mut insertions: [Vec<i64>; 2] = [vec![NEG_INF; reference.len() + 1], vec![NEG_INF; reference.len() + 1]];
+
+    let mut best = ScalarAlignment {
+        score: if local { 0 } else { NEG_INF },
+        query_end: 0,
+        ref_end: 0,
+    };
+
+    for (i, &q) in query.iter().enumerate() {
+        let row = i + 1;
+        let mut diagonal = h[0];
+        h[0] = end_gap(row);
+        // best score ending in a deletion at (row, j) under each piece's costs
+        let mut deletions = [NEG_INF; 2];
+
+        for (j, &r) in reference.iter().enumerate() {
+            let col = j + 1;
+            let mut score = diagonal + matrix.score(q, r) as i64;
+            for (piece, &(open, extend)) in pieces.iter().enumerate() {
+                insertions[piece][col] = (h[col] - open).max(insertions[piece][col] - extend);
+                deletions[piece] = (h[col - 1] - open).max(deletions[piece] - extend);
+                score = score.max(insertions[piece][col]).max(deletions[piece]);
+            }
+            if local {
+                score = score.max(0);
+            }
+            diagonal = h[col];
+            h[col] = score;
+
+            let eligible = match mode {
+                AlignmentMode::Local => true,
+                AlignmentMode::SemiGlobal => row == query.len() || col == reference.len(),
+                AlignmentMode::Global => row == query.len() && col == reference.len(),
+            };
+            if eligible && score > best.score {
+                best = ScalarAlignment {
+                    score,
+                    query_end: row,
+                    ref_end: col,
+                };
+            }
+        }
+    }
+
+    // an empty sequence leaves the loop above with nothing to compare
+    if query.is_empty() || reference.is_empty() {
+        best = ScalarAlignment {
+            score: end_gap(query.len().max(reference.len())),
+            query_end: query.len(),
+            ref_end: reference.len(),
+        };
+    }
+
+    best
+}
+
+/// Gap costs for coding sequences, where a gap whose length isn't a multiple of three shifts the reading frame of
+/// everything after it. A gap of length `L` costs `gap.open + (L - 1) * gap.extend`, plus `frameshift` unless `L` is
+/// a multiple of three.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CodonGaps {
+    /// The costs of any gap, as for a plain affine alignment.
+    pub gap: GapCosts,
+    /// The extra cost of a gap that shifts the frame.
+    pub frameshift: i32,
+}
+
+impl CodonGaps {
+    /// The cost of a gap of `len` columns, which must be at least 1.
+    pub fn cost(&self, len: usize) -> i64 {
+        let affine = self.gap.open as i64 + (len as i64 - 1) * self.gap.extend as i64;
+        if len % 3 == 0 {
+            affine
+        } else {
+            affine + self.frameshift as i64
+        }
+    }
+}
+
+/// Scores an alignment of coding nucleotide sequences which charges frameshifting gaps extra (see `CodonGaps`), so
+/// that indels come out codon-sized wherever the sequences allow.
+///
+/// With plain affine costs, an alignment will happily trade a 3 base gap for a 1 base and a 2 base gap a few bases
+/// apart if that saves a mismatch, putting spurious frameshifts into the comparison of two genes. This runs a plain
+/// dynamic program in pure Rust, tracking each gap's length modulo 3 (three gap states per side), so it's much slower
+/// than the parasail kernels. The modes and tie-breaking are the same as for `asymmetric_alignment`.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::create("ACGT", 2, -3);
+/// let gaps = CodonGaps {
+///     gap: GapCosts { open: 5, extend: 1 },
+///     frameshift: 20,
+/// };
+/// assert_eq!(7, gaps.cost(3));
+/// assert_eq!(26, gaps.cost(2));
+///
+/// // the codon GCC is missing from the query
+/// let query = b"ATGAAACTGGGTTAA";
+/// let reference = b"ATGAAAGCCCTGGGTTAA";
+/// let result = codon_alignment(query, reference, AlignmentMode::Global, &gaps, &matrix);
+/// assert_eq!(30 - 7, result.score);
+/// ```
+pub fn codon_alignment(
+    query: &[u8],
+    reference: &[u8],
+    mode: AlignmentMode,
+    gaps: &CodonGaps,
+    matrix: &Matrix,
+) -> ScalarAlignment {
+    let (open, extend) = (gaps.gap.open as i64, gaps.gap.extend as i64);
+    let frameshift = gaps.frameshift as i64;
+    let free_ends = mode != AlignmentMode::Global;
+    let local = mode == AlignmentMode::Local;
+    let end_gap = |len: usize| if free_ends || len == 0 { 0 } else { -gaps.cost(len) };
+
+    // best score ending at (i, j) in any state for the previous query row, and ending in an insertion whose length is
+    // 0, 1, or 2 modulo 3
+    let mut h: Vec<i64> = (0..=reference.len()).map(end_gap).collect();
+    let mut insertions: [Vec<i64>; 3] = [(); 3].map(|_| vec![NEG_INF; reference.len() + 1]);
+
+    // the best way to leave a gap in each length class, charging the frameshift for the ones that shift the frame
+    let close = |by_phase: [i64; 3]| by_phase[0].max(by_phase[1] - frameshift).max(by_phase[2] - frameshift);
+
+    let mut best = ScalarAlignment {
+        score: if local { 0 } else { NEG_INF },
+        query_end: 0,
+        ref_end: 0,
+    };
+
+    for (i, &q) in query.iter().enumerate() {
+        let row = i + 1;
+        let mut diagonal = h[0];
+        h[0] = end_gap(row);
+        // best score ending in a deletion at (row, j) in each length class
+        let mut deletion = [NEG_INF; 3];
+
+        for (j, &r) in reference.iter().enumerate() {
+            let col = j + 1;
+            // a gap one longer moves to the next length class, and a new gap starts in class 1
+            let insertion = [0, 1, 2].map(|phase| insertions[phase][col]);
+            let insertion = [
+                insertion[2] - extend,
+                (h[col] - open).max(insertion[0] - extend),
+                insertion[1] - extend,
+            ];
+            deletion = [
+                deletion[2] - extend,
+                (h[col - 1] - open).max(deletion[0] - extend),
+                deletion[1] - extend,
+            ];
+            for (phase, &score) in insertion.iter().enumerate() {
+                insertions[phase][col] = score;
+            }
+
+            let mut score = (diagonal + matrix.score(q, r) as i64).max(close(insertion)).max(close(deletion));
+            if local {
+                score = score.max(0);
+            }
+            diagonal = h[col];
+            h[col] = score;
+
+            let eligible = match mode {
+                AlignmentMode::Local => true,
+                AlignmentMode::SemiGlobal => row == query.len() || col == reference.len(),
+                AlignmentMode::Global => row == query.len() && col == reference.len(),
+            };
+            if eligible && score > best.score {
+                best = ScalarAlignment {
+                    score,
+                    query_end: row,
+                    ref_end: col,
+                };
+            }
+        }
+    }
+
+    // an empty sequence leaves the loop above with nothing to compare
+    if query.is_empty() || reference.is_empty() {
+        best = ScalarAlignment {
+            score: end_gap(query.len().max(reference.len())),
+            query_end: query.len(),
+            ref_end: reference.len(),
+        };
+    }
+
+    best
+}
@@ -0,0 +1,106 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+
+use crate::matrix::Matrix;
+
+/// A way of scoring aligned characters, accepted wherever an `Aligner`, an `AlignmentSession`, or `align_batch` wants
+/// a substitution matrix, so callers who only care about matches and mismatches never have to build one.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let scheme = MatchMismatch::new(2, -3);
+/// let mut aligner = Aligner::new(&scheme, AlignmentMode::Global, 5, 2);
+/// assert_eq!(16, aligner.score(b"ACGTACGT", b"ACGTACGT"));
+///
+/// // a full matrix works the same way
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let mut aligner = Aligner::new(&matrix, AlignmentMode::Global, 5, 2);
+/// assert_eq!(8, aligner.score(b"ACGTACGT", b"ACGTACGT"));
+/// ```
+pub trait ScoringScheme {
+    /// The substitution matrix implementing the scheme.
+    fn matrix(&self) -> &Matrix;
+}
+
+impl ScoringScheme for Matrix {
+    fn matrix(&self) -> &Matrix {
+        self
+    }
+}
+
+/// Scores every pair of identical letters the same, and every pair of different letters the same, whatever the
+/// letters: the usual scoring for DNA.
+///
+/// The matrix behind it covers the letters `A` to `Z` (in either case), so ambiguity codes like `N` only match
+/// themselves. Schemes with the same scores share one matrix while any of them is alive, and it's freed along with
+/// the last of them.
+#[derive(Debug, Clone)]
+pub struct MatchMismatch {
+    match_score: i32,
+    mismatch_score: i32,
+    matrix: Arc<Matrix>,
+}
+
+// the matrix of every pair of scores a live `MatchMismatch` uses
+type MatrixCache = Mutex<HashMap<(i32, i32), Weak<Matrix>>>;
+
+impl MatchMismatch {
+    /// Creates a scheme scoring `match_score` for two identical letters and `mismatch_score`, usually negative, for
+    /// two different ones.
+    pub fn new(match_score: i32, mismatch_score: i32) -> Self {
+        static MATRICES: OnceLock<MatrixCache> = OnceLock::new();
+
+        let mut matrices = MATRICES
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let matrix = match matrices.get(&(match_score, mismatch_score)).and_then(Weak::upgrade) {
+            Some(matrix) => matrix,
+            None => {
+                // forget the matrices nothing uses any more while there's a new one to remember
+                matrices.retain(|_, matrix| matrix.strong_count() > 0);
+                let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+                let matrix = Arc::new(Matrix::create(alphabet, match_score as i64, mismatch_score as i64));
+                matrices.insert((match_score, mismatch_score), Arc::downgrade(&matrix));
+                matrix
+            }
+        };
+
+        MatchMismatch {
+            match_score,
+            mismatch_score,
+            matrix,
+        }
+    }
+
+    /// The score of two identical letters.
+    pub fn match_score(&self) -> i32 {
+        self.match_score
+    }
+
+    /// The score of two different letters.
+    pub fn mismatch_score(&self) -> i32 {
+        self.mismatch_score
+    }
+}
+
+impl PartialEq for MatchMismatch {
+    fn eq(&self, other: &MatchMismatch) -> bool {
+        (self.match_score, self.mismatch_score) == (other.match_score, other.mismatch_score)
+    }
+}
+
+impl Eq for MatchMismatch {}
+
+impl ScoringScheme for MatchMismatch {
+    fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+}
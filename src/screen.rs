@@ -0,0 +1,96 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::collections::HashSet;
+
+use crate::align::{ratio, semi_global_dx_alignment_stats};
+use crate::error::ParasailError;
+use crate::matrices::IDENTITY_WITH_PENALTY;
+
+// k-mers for the containment screen; long enough to be specific in an amplicon, short enough to survive errors
+const SCREEN_K: usize = 11;
+// how far below `min_identity` the k-mer estimate may fall before a pair is rejected without aligning
+const SCREEN_MARGIN: f64 = 0.1;
+
+/// What `contains` found out about a query it confirmed in a reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchSummary {
+    /// The score of the confirming alignment, with unit costs as in `find_matches`.
+    pub score: i64,
+    /// The fraction of alignment columns which are exact matches, not counting the unaligned reference on either
+    /// side.
+    pub identity: f64,
+    /// The fraction of the query's k-mers found in the reference, or `None` if the query was too short to screen.
+    pub kmer_containment: Option<f64>,
+    /// The ending index (0-based, exclusive) of the query's alignment in the reference.
+    pub ref_end: usize,
+}
+
+/// Checks whether the whole query appears in the reference with at least `min_identity` identity, as when asking
+/// whether a read comes from an amplicon, without computing a traceback.
+///
+/// First the query's 11-mers are looked up in the reference. Random errors at identity `p` leave about `p^11` of
+/// them intact, so if the identity that implies falls well short of `min_identity` the pair is rejected right away.
+/// Otherwise a single semi-global alignment with free reference ends and unit costs (as in `find_matches`) confirms
+/// it, counting matches and columns as it goes instead of tracing back. Queries shorter than 11 bases skip the
+/// screen.
+///
+/// Returns `None` if the query isn't in the reference, and a summary of the confirming alignment if it is, or an
+/// error if parasail fails to align them.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let amplicon = b"GATCCTAGCATCGACTAGCGGCATTACAGTTGACCATGAGCTAGGCTTACGGGCATCGATCAGC";
+///
+/// let read = b"CGACTAGCGGCATTACAGTTGACCATG";
+/// let summary = contains(read, amplicon, 0.9).unwrap().unwrap();
+/// assert_eq!(1.0, summary.identity);
+/// assert_eq!(Some(1.0), summary.kmer_containment);
+///
+/// assert!(contains(b"TTTTTTTTTTTTGGGGGGGGGGGGGGG", amplicon, 0.9).unwrap().is_none());
+/// ```
+pub fn contains(query: &[u8], reference: &[u8], min_identity: f64) -> Result<Option<MatchSummary>, ParasailError> {
+    if query.is_empty() {
+        return Ok(None);
+    }
+
+    let kmer_containment = kmer_containment(query, reference, SCREEN_K);
+    if let Some(containment) = kmer_containment {
+        if containment.powf(1.0 / SCREEN_K as f64) < min_identity - SCREEN_MARGIN {
+            return Ok(None);
+        }
+    }
+
+    let stats = semi_global_dx_alignment_stats(query, reference, 1, 1, &IDENTITY_WITH_PENALTY)?;
+    if stats.align_length == 0 {
+        return Ok(None);
+    }
+
+    let identity = ratio(stats.num_matches as usize, stats.align_length);
+    if identity < min_identity {
+        return Ok(None);
+    }
+
+    Ok(Some(MatchSummary {
+        score: stats.score,
+        identity,
+        kmer_containment,
+        ref_end: stats.ref_end,
+    }))
+}
+
+/// The fraction of the query's distinct k-mers that appear in the reference, or `None` if the query has none.
+fn kmer_containment(query: &[u8], reference: &[u8], k: usize) -> Option<f64> {
+    let query_kmers: HashSet<&[u8]> = query.windows(k).collect();
+    if query_kmers.is_empty() {
+        return None;
+    }
+
+    let reference_kmers: HashSet<&[u8]> = reference.windows(k).collect();
+    let shared = query_kmers.iter().filter(|kmer| reference_kmers.contains(*kmer)).count();
+    Some(shared as f64 / query_kmers.len() as f64)
+}
@@ -0,0 +1,368 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::cmp::Reverse;
+
+use libc::c_int;
+
+use crate::align::{local_alignment_stats_with_trace, ratio};
+use crate::error::ParasailError;
+use crate::matrix::{Matrix, MatrixType};
+use crate::profile::{profile_kernel, Profile};
+use crate::result::ParasailResult;
+use crate::seq::{n_runs, six_frames, GeneticCode, Strand};
+use parasail_sys::{
+    parasail_sg_dx_stats_striped_profile_16, parasail_sg_dx_stats_striped_profile_32,
+    parasail_sg_dx_stats_striped_profile_64, parasail_sg_dx_stats_striped_profile_8,
+    parasail_sg_dx_stats_striped_profile_sat, parasail_sg_dx_stats_striped_sat, parasail_sg_dx_trace_striped_sat,
+};
+
+/// A site in the reference where the whole query aligns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    /// The score according to the substitution matrix and gap penalty scheme used.
+    pub score: i64,
+    /// The starting index (0-based) of the site in the reference.
+    pub ref_begin: usize,
+    /// The ending index (0-based, exclusive) of the site in the reference.
+    pub ref_end: usize,
+    /// The fraction of alignment columns which are exact matches.
+    pub identity: f64,
+}
+
+/// Finds every site in the reference where the query aligns with at least `min_identity` identity.
+///
+/// This uses unit costs (matches score 1, mismatches score -1, and each gapped base costs 1), which makes the score
+/// behave like a negated edit distance. See `find_matches_with` to pick a different scoring scheme.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let primer = b"GATTACA";
+/// let reference = b"CCGATTACACCCCGATTTCACC";
+///
+/// let sites = find_matches(primer, reference, 0.8).unwrap();
+/// assert_eq!(2, sites.len());
+/// assert_eq!((2, 9), (sites[0].ref_begin, sites[0].ref_end));
+/// assert_eq!(1.0, sites[0].identity);
+/// assert_eq!((13, 20), (sites[1].ref_begin, sites[1].ref_end));
+/// ```
+pub fn find_matches(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    min_identity: f64,
+) -> Result<Vec<Match>, ParasailError> {
+    let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+    find_matches_with(query_sequence, database_sequence, min_identity, 1, 1, &matrix)
+}
+
+/// Finds every site in the reference where the query aligns with at least `min_identity` identity.
+///
+/// The whole query is aligned against the reference with free end gaps in the reference (as in
+/// `semi_global_dx_traceback`). Each site found is masked out of the reference and the stretches on either side are
+/// searched again, whether or not it passed the identity threshold, so the reported sites never overlap and a site
+/// failing the threshold doesn't hide a weaker scoring one beside it that passes. Identity is the number of
+/// exactly matching columns divided by the number of columns in the alignment, not counting the unaligned reference
+/// on either side. Sites are returned in reference order.
+///
+/// Runs of `N` in the reference at least as long as the query, such as assembly gaps, are cut out before aligning
+/// anything, and the stretches between them are searched separately. A site can't be found across such a run.
+/// Returns the first error parasail reports, if any.
+pub fn find_matches_with(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    min_identity: f64,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<Vec<Match>, ParasailError> {
+    let mut matches = Vec::new();
+    if query_sequence.is_empty() {
+        return Ok(matches);
+    }
+
+    // a site can't contain more matches than it has reference bases, and the alignment is at least as long as the
+    // query, so stretches shorter than this can never pass the threshold
+    let min_len = (min_identity * query_sequence.len() as f64).ceil().max(1.0) as usize;
+
+    // the stretches between long runs of N
+    let mut pending = Vec::new();
+    let mut stretch_start = 0;
+    for run in n_runs(database_sequence, query_sequence.len()) {
+        pending.push((stretch_start, run.start));
+        stretch_start = run.end;
+    }
+    pending.push((stretch_start, database_sequence.len()));
+    while let Some((start, end)) = pending.pop() {
+        if end - start < min_len {
+            continue;
+        }
+
+        let site = match best_site(
+            query_sequence,
+            &database_sequence[start..end],
+            open_cost,
+            gap_extend_cost,
+            substitution_matrix,
+        )? {
+            Some(site) => site,
+            None => continue,
+        };
+
+        pending.push((start, start + site.ref_begin));
+        pending.push((start + site.ref_end, end));
+        if site.identity < min_identity {
+            continue;
+        }
+        matches.push(Match {
+            ref_begin: start + site.ref_begin,
+            ref_end: start + site.ref_end,
+            ..site
+        });
+    }
+
+    matches.sort_by_key(|m| m.ref_begin);
+    Ok(matches)
+}
+
+/// Checks whether the profile's query appears in the reference with at least `min_identity` identity, returning the
+/// site if it does, with unit gap costs as in `find_matches`. See `matches_reference_with` for other gap costs.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let caps = ProfileCaps { stats: true, ..ProfileCaps::default() };
+/// let primer = Profile::new_with(b"GATTACA", &matrix, caps).unwrap();
+///
+/// let site = matches_reference(&primer, b"CCCCGATTACACCCC", 0.9).unwrap().unwrap();
+/// assert_eq!((4, 11), (site.ref_begin, site.ref_end));
+/// assert!(matches_reference(&primer, b"CCCCGATCCCCCCCC", 0.9).unwrap().is_none());
+/// ```
+pub fn matches_reference(
+    query_profile: &Profile,
+    database_sequence: &[u8],
+    min_identity: f64,
+) -> Result<Option<Match>, ParasailError> {
+    matches_reference_with(query_profile, database_sequence, min_identity, 1, 1)
+}
+
+/// Checks whether the profile's query appears in the reference with at least `min_identity` identity, as a single
+/// site found with free reference end gaps (as in `semi_global_dx_traceback`), and returns it if so. Identity is
+/// counted as in `find_matches_with`, but only the best site is considered.
+///
+/// This does as little work as it can to answer no. A reference too short to hold enough matches is turned away
+/// without aligning. Otherwise a stats kernel counts the matches and columns without a traceback, reusing the profile
+/// if it was built with `ProfileCaps::stats` and aligning from the query otherwise. Only a site that passes is traced
+/// back to find where it begins. Returns an error if parasail fails.
+pub fn matches_reference_with(
+    query_profile: &Profile,
+    database_sequence: &[u8],
+    min_identity: f64,
+    open_cost: i32,
+    gap_extend_cost: i32,
+) -> Result<Option<Match>, ParasailError> {
+    let query_sequence = query_profile.query();
+    let min_len = (min_identity * query_sequence.len() as f64).ceil().max(1.0) as usize;
+    if query_sequence.is_empty() || database_sequence.len() < min_len {
+        return Ok(None);
+    }
+
+    let (reference, reference_len) = (database_sequence.as_ptr(), database_sequence.len() as c_int);
+    let stats = unsafe {
+        ParasailResult::from_raw(if query_profile.caps().stats {
+            profile_kernel!(
+                query_profile,
+                [
+                    parasail_sg_dx_stats_striped_profile_8,
+                    parasail_sg_dx_stats_striped_profile_16,
+                    parasail_sg_dx_stats_striped_profile_32,
+                    parasail_sg_dx_stats_striped_profile_64,
+                    parasail_sg_dx_stats_striped_profile_sat
+                ],
+                reference,
+                reference_len,
+                open_cost,
+                gap_extend_cost,
+            )
+        } else {
+            parasail_sg_dx_stats_striped_sat(
+                query_sequence.as_ptr(),
+                query_sequence.len() as c_int,
+                reference,
+                reference_len,
+                open_cost,
+                gap_extend_cost,
+                **query_profile.matrix(),
+            )
+        })?
+    };
+
+    let columns = stats.length().unwrap_or(0);
+    if columns == 0 || (stats.matches().unwrap_or(0) as f64) < min_identity * columns as f64 {
+        return Ok(None);
+    }
+
+    let site = best_site(query_sequence, database_sequence, open_cost, gap_extend_cost, query_profile.matrix())?;
+    Ok(site.filter(|site| site.identity >= min_identity))
+}
+
+/// Aligns the whole query against the reference with free reference end gaps, reporting the aligned site.
+fn best_site(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<Option<Match>, ParasailError> {
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sg_dx_trace_striped_sat(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            database_sequence.as_ptr(),
+            database_sequence.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+    let cigar = result.cigar(query_sequence, database_sequence, substitution_matrix)?;
+
+    let score = result.score() as i64;
+    let mut ops = cigar.ops;
+    let mut ref_begin = cigar.ref_begin;
+    let ref_end = result.ref_end();
+
+    // gaps in the query at either end of the reference are free, so they aren't part of the site. parasail's end
+    // position already stops before the trailing one.
+    if let Some(&(b'D', len)) = ops.first() {
+        ref_begin += len;
+        ops.remove(0);
+    }
+    if let Some(&(b'D', _)) = ops.last() {
+        ops.pop();
+    }
+
+    let columns: usize = ops.iter().map(|&(_, len)| len).sum();
+    let exact: usize = ops.iter().filter(|&&(op, _)| op == b'=').map(|&(_, len)| len).sum();
+    if columns == 0 || ref_begin >= ref_end {
+        return Ok(None);
+    }
+
+    Ok(Some(Match {
+        score,
+        ref_begin,
+        ref_end,
+        identity: exact as f64 / columns as f64,
+    }))
+}
+
+/// The best local alignment of a protein in one reading frame of a nucleotide sequence, from `six_frame_search`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameHit {
+    /// The reading frame, numbered as in `seq::six_frames`: positive on the forward strand, negative on the reverse.
+    pub frame: i8,
+    /// The score according to the substitution matrix and gap penalty scheme used.
+    pub score: i64,
+    /// The index (0-based) of the first aligned residue of the protein.
+    pub query_begin: usize,
+    /// The index (0-based, exclusive) of the last aligned residue of the protein.
+    pub query_end: usize,
+    /// The index (0-based) on the forward strand of the first nucleotide of the aligned codons, whichever strand the
+    /// frame is on.
+    pub ref_begin: usize,
+    /// The index (0-based, exclusive) on the forward strand of the last nucleotide of the aligned codons.
+    pub ref_end: usize,
+    /// The length of the whole protein.
+    pub query_len: usize,
+    /// The length of the whole nucleotide sequence.
+    pub ref_len: usize,
+}
+
+impl FrameHit {
+    /// The strand the hit's reading frame is on. The coordinates are on the forward strand either way.
+    pub fn strand(&self) -> Strand {
+        Strand::of_frame(self.frame)
+    }
+
+    /// The fraction of the protein covered by the hit, or 0 for an empty protein.
+    pub fn query_coverage(&self) -> f64 {
+        ratio(self.query_end - self.query_begin, self.query_len)
+    }
+
+    /// The fraction of the nucleotide sequence covered by the hit's codons, or 0 for an empty sequence.
+    pub fn ref_coverage(&self) -> f64 {
+        ratio(self.ref_end - self.ref_begin, self.ref_len)
+    }
+}
+
+/// Searches for a protein in a nucleotide sequence by translating the sequence in all six reading frames with the
+/// given genetic code (see `seq::six_frames`) and locally aligning the protein against each, as tblastn does. Returns
+/// the best hit in each frame that has one scoring above zero, best first, or the first error parasail reports.
+///
+/// Picking the right code matters: in vertebrate mitochondria, for example, `TGA` codes for tryptophan, and the
+/// standard code would break every such protein at a stop.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// use parasailors::seq::{reverse_complement, GeneticCode, Strand};
+///
+/// let matrix = Matrix::new(MatrixType::Blosum62);
+/// // MAWKW, with each W coded as TGA, on the reverse strand
+/// let gene = reverse_complement(b"ATGGCCTGAAAATGA");
+/// let reference = [b"CCCCC".as_ref(), &gene, b"GGGG"].concat();
+///
+/// let hits = six_frame_search(b"MAWKW", &reference, GeneticCode::VertebrateMitochondrial, 11, 1, &matrix).unwrap();
+/// assert_eq!(-2, hits[0].frame);
+/// assert_eq!(Strand::Reverse, hits[0].strand());
+/// assert_eq!((0, 5), (hits[0].query_begin, hits[0].query_end));
+/// assert_eq!((5, 20), (hits[0].ref_begin, hits[0].ref_end));
+/// assert_eq!(1.0, hits[0].query_coverage());
+/// ```
+pub fn six_frame_search(
+    protein: &[u8],
+    nucleotides: &[u8],
+    code: GeneticCode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<Vec<FrameHit>, ParasailError> {
+    let mut hits: Vec<FrameHit> = Vec::new();
+    for (frame, translated) in six_frames(nucleotides, code) {
+        if translated.is_empty() {
+            continue;
+        }
+        let traced =
+            local_alignment_stats_with_trace(protein, &translated, open_cost, gap_extend_cost, substitution_matrix)?;
+        let stats = &traced.stats;
+        if stats.score <= 0 {
+            continue;
+        }
+
+        // codon positions within the translated strand
+        let offset = frame.unsigned_abs() as usize - 1;
+        let codons = offset + 3 * traced.ref_begin..offset + 3 * stats.ref_end;
+        let forward = Strand::of_frame(frame).to_forward(codons, nucleotides.len());
+
+        hits.push(FrameHit {
+            frame,
+            score: stats.score,
+            query_begin: traced.query_begin,
+            query_end: stats.query_end,
+            ref_begin: forward.start,
+            ref_end: forward.end,
+            query_len: protein.len(),
+            ref_len: nucleotides.len(),
+        });
+    }
+
+    // stable, so ties stay in frame order
+    hits.sort_by_key(|hit| Reverse(hit.score));
+    Ok(hits)
+}
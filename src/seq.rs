@@ -0,0 +1,436 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+//! Everyday sequence manipulation: reverse complements and strands, translation in any NCBI genetic code, case
+//! normalization, and finding assembly gaps.
+
+use std::fmt;
+use std::ops::Range;
+
+/// The complement of a nucleotide, following the IUPAC codes so that ambiguity codes complement to the code for the
+/// complementary set of bases (e.g. `R`, A or G, becomes `Y`, T or C). Case is kept, `U` complements to `A`, and
+/// anything unrecognized is returned unchanged.
+pub fn complement(base: u8) -> u8 {
+    let complemented = match base.to_ascii_uppercase() {
+        b'A' => b'T',
+        b'T' | b'U' => b'A',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'R' => b'Y',
+        b'Y' => b'R',
+        b'K' => b'M',
+        b'M' => b'K',
+        b'B' => b'V',
+        b'V' => b'B',
+        b'D' => b'H',
+        b'H' => b'D',
+        // S, W, and N are their own complements
+        other => other,
+    };
+
+    if base.is_ascii_lowercase() {
+        complemented.to_ascii_lowercase()
+    } else if base.is_ascii_uppercase() {
+        complemented
+    } else {
+        base
+    }
+}
+
+/// The reverse complement of a nucleotide sequence, as described for `complement`.
+///
+/// # Examples
+///
+/// ```
+/// use parasailors::seq::reverse_complement;
+/// assert_eq!(b"NACGTr".to_vec(), reverse_complement(b"yACGTN"));
+/// ```
+pub fn reverse_complement(sequence: &[u8]) -> Vec<u8> {
+    sequence.iter().rev().map(|&base| complement(base)).collect()
+}
+
+/// Reverse complements a nucleotide sequence without allocating.
+pub fn reverse_complement_in_place(sequence: &mut [u8]) {
+    sequence.reverse();
+    for base in sequence.iter_mut() {
+        *base = complement(*base);
+    }
+}
+
+/// Which strand of a nucleotide sequence something lies on.
+///
+/// Results keep their coordinates on the forward strand whichever strand they're on, as SAM and PAF do, so a hit on
+/// the reverse strand covers the reverse complement of the forward strand bases it reports.
+///
+/// # Examples
+///
+/// ```
+/// use parasailors::seq::Strand;
+/// // the first three bases of the reverse complement of a 10 base sequence
+/// assert_eq!(7..10, Strand::Reverse.to_forward(0..3, 10));
+/// assert_eq!(Strand::Reverse, Strand::of_frame(-2));
+/// assert_eq!("-", Strand::Reverse.to_string());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Strand {
+    /// The sequence as given.
+    Forward,
+    /// The reverse complement of the sequence.
+    Reverse,
+}
+
+impl Strand {
+    /// The strand a reading frame, numbered as in `six_frames`, is on.
+    pub fn of_frame(frame: i8) -> Self {
+        if frame < 0 {
+            Strand::Reverse
+        } else {
+            Strand::Forward
+        }
+    }
+
+    /// The other strand.
+    pub fn flip(self) -> Self {
+        match self {
+            Strand::Forward => Strand::Reverse,
+            Strand::Reverse => Strand::Forward,
+        }
+    }
+
+    /// `'+'` for the forward strand and `'-'` for the reverse, as in SAM, PAF, and GFA.
+    pub fn symbol(self) -> char {
+        match self {
+            Strand::Forward => '+',
+            Strand::Reverse => '-',
+        }
+    }
+
+    /// Maps a range of positions on this strand of a sequence `len` long onto the forward strand.
+    pub fn to_forward(self, range: Range<usize>, len: usize) -> Range<usize> {
+        match self {
+            Strand::Forward => range,
+            Strand::Reverse => len - range.end..len - range.start,
+        }
+    }
+}
+
+impl fmt::Display for Strand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// Upper-cases a sequence in place. Substitution matrices are usually defined over upper case letters, so soft-masked
+/// (lower case) input should go through this before alignment.
+pub fn normalize_case(sequence: &mut [u8]) {
+    sequence.make_ascii_uppercase();
+}
+
+/// Upper-cases a copy of a sequence.
+pub fn to_uppercase(sequence: &[u8]) -> Vec<u8> {
+    sequence.to_ascii_uppercase()
+}
+
+/// A table mapping codons to amino acids, identified by its NCBI translation table number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GeneticCode {
+    /// Table 1, the standard code.
+    #[default]
+    Standard,
+    /// Table 2, the vertebrate mitochondrial code.
+    VertebrateMitochondrial,
+    /// Table 3, the yeast mitochondrial code.
+    YeastMitochondrial,
+    /// Table 4, the mold, protozoan, and coelenterate mitochondrial code and the mycoplasma/spiroplasma code.
+    MoldMitochondrial,
+    /// Table 5, the invertebrate mitochondrial code.
+    InvertebrateMitochondrial,
+    /// Table 6, the ciliate, dasycladacean, and hexamita nuclear code.
+    Ciliate,
+    /// Table 9, the echinoderm and flatworm mitochondrial code.
+    EchinodermMitochondrial,
+    /// Table 10, the euplotid nuclear code.
+    Euplotid,
+    /// Table 11, the bacterial, archaeal, and plant plastid code. It only differs from the standard code in its start
+    /// codons, which don't affect translation here.
+    Bacterial,
+    /// Table 12, the alternative yeast nuclear code.
+    AlternativeYeast,
+    /// Table 13, the ascidian mitochondrial code.
+    AscidianMitochondrial,
+    /// Table 14, the alternative flatworm mitochondrial code.
+    AlternativeFlatwormMitochondrial,
+    /// Table 16, the chlorophycean mitochondrial code.
+    ChlorophyceanMitochondrial,
+    /// Table 21, the trematode mitochondrial code.
+    TrematodeMitochondrial,
+    /// Table 22, the *Scenedesmus obliquus* mitochondrial code.
+    ScenedesmusMitochondrial,
+    /// Table 23, the *Thraustochytrium* mitochondrial code.
+    ThraustochytriumMitochondrial,
+    /// Table 24, the Rhabdopleuridae mitochondrial code.
+    RhabdopleuridaeMitochondrial,
+    /// Table 25, the candidate division SR1 and gracilibacteria code.
+    Gracilibacteria,
+    /// Table 26, the *Pachysolen tannophilus* nuclear code.
+    Pachysolen,
+    /// Table 27, the karyorelict nuclear code. In this and the other codes whose stop codons also code for amino
+    /// acids (28 and 31), they translate as the amino acid, since only context tells whether they end the protein.
+    Karyorelict,
+    /// Table 28, the *Condylostoma* nuclear code.
+    Condylostoma,
+    /// Table 29, the *Mesodinium* nuclear code.
+    Mesodinium,
+    /// Table 30, the peritrich nuclear code.
+    Peritrich,
+    /// Table 31, the *Blastocrithidia* nuclear code.
+    Blastocrithidia,
+    /// Table 33, the Cephalodiscidae mitochondrial code.
+    CephalodiscidaeMitochondrial,
+}
+
+impl GeneticCode {
+    /// The NCBI translation table number.
+    pub fn ncbi_id(self) -> u8 {
+        match self {
+            GeneticCode::Standard => 1,
+            GeneticCode::VertebrateMitochondrial => 2,
+            GeneticCode::YeastMitochondrial => 3,
+            GeneticCode::MoldMitochondrial => 4,
+            GeneticCode::InvertebrateMitochondrial => 5,
+            GeneticCode::Ciliate => 6,
+            GeneticCode::EchinodermMitochondrial => 9,
+            GeneticCode::Euplotid => 10,
+            GeneticCode::Bacterial => 11,
+            GeneticCode::AlternativeYeast => 12,
+            GeneticCode::AscidianMitochondrial => 13,
+            GeneticCode::AlternativeFlatwormMitochondrial => 14,
+            GeneticCode::ChlorophyceanMitochondrial => 16,
+            GeneticCode::TrematodeMitochondrial => 21,
+            GeneticCode::ScenedesmusMitochondrial => 22,
+            GeneticCode::ThraustochytriumMitochondrial => 23,
+            GeneticCode::RhabdopleuridaeMitochondrial => 24,
+            GeneticCode::Gracilibacteria => 25,
+            GeneticCode::Pachysolen => 26,
+            GeneticCode::Karyorelict => 27,
+            GeneticCode::Condylostoma => 28,
+            GeneticCode::Mesodinium => 29,
+            GeneticCode::Peritrich => 30,
+            GeneticCode::Blastocrithidia => 31,
+            GeneticCode::CephalodiscidaeMitochondrial => 33,
+        }
+    }
+
+    /// Looks a code up by its NCBI translation table number.
+    pub fn from_ncbi_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(GeneticCode::Standard),
+            2 => Some(GeneticCode::VertebrateMitochondrial),
+            3 => Some(GeneticCode::YeastMitochondrial),
+            4 => Some(GeneticCode::MoldMitochondrial),
+            5 => Some(GeneticCode::InvertebrateMitochondrial),
+            6 => Some(GeneticCode::Ciliate),
+            9 => Some(GeneticCode::EchinodermMitochondrial),
+            10 => Some(GeneticCode::Euplotid),
+            11 => Some(GeneticCode::Bacterial),
+            12 => Some(GeneticCode::AlternativeYeast),
+            13 => Some(GeneticCode::AscidianMitochondrial),
+            14 => Some(GeneticCode::AlternativeFlatwormMitochondrial),
+            16 => Some(GeneticCode::ChlorophyceanMitochondrial),
+            21 => Some(GeneticCode::TrematodeMitochondrial),
+            22 => Some(GeneticCode::ScenedesmusMitochondrial),
+            23 => Some(GeneticCode::ThraustochytriumMitochondrial),
+            24 => Some(GeneticCode::RhabdopleuridaeMitochondrial),
+            25 => Some(GeneticCode::Gracilibacteria),
+            26 => Some(GeneticCode::Pachysolen),
+            27 => Some(GeneticCode::Karyorelict),
+            28 => Some(GeneticCode::Condylostoma),
+            29 => Some(GeneticCode::Mesodinium),
+            30 => Some(GeneticCode::Peritrich),
+            31 => Some(GeneticCode::Blastocrithidia),
+            33 => Some(GeneticCode::CephalodiscidaeMitochondrial),
+            _ => None,
+        }
+    }
+
+    /// The amino acids of all 64 codons, in NCBI's TCAG order.
+    fn table(self) -> &'static [u8; 64] {
+        match self {
+            GeneticCode::Standard | GeneticCode::Bacterial => {
+                b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"
+            }
+            GeneticCode::VertebrateMitochondrial => b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+            GeneticCode::YeastMitochondrial => b"FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::MoldMitochondrial => b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::InvertebrateMitochondrial => {
+                b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG"
+            }
+            GeneticCode::Ciliate => b"FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::EchinodermMitochondrial => b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+            GeneticCode::Euplotid => b"FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::AlternativeYeast => b"FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::AscidianMitochondrial => b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+            GeneticCode::AlternativeFlatwormMitochondrial => {
+                b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG"
+            }
+            GeneticCode::ChlorophyceanMitochondrial => {
+                b"FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"
+            }
+            GeneticCode::TrematodeMitochondrial => b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+            GeneticCode::ScenedesmusMitochondrial => {
+                b"FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"
+            }
+            GeneticCode::ThraustochytriumMitochondrial => {
+                b"FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG"
+            }
+            GeneticCode::RhabdopleuridaeMitochondrial => {
+                b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG"
+            }
+            GeneticCode::Gracilibacteria => b"FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::Pachysolen => b"FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::Karyorelict => b"FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::Condylostoma => b"FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::Mesodinium => b"FFLLSSSSYYYYCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::Peritrich => b"FFLLSSSSYYEECC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::Blastocrithidia => b"FFLLSSSSYYEECCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            GeneticCode::CephalodiscidaeMitochondrial => {
+                b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG"
+            }
+        }
+    }
+
+    /// Translates a single codon. Ambiguous bases are expanded, and the codon translates to `X` unless every
+    /// possibility gives the same amino acid. Stops are `*`. Only the first three bases are read, and a codon with
+    /// fewer than three, as at the end of a sequence whose length isn't a multiple of three, translates to `X`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::seq::GeneticCode;
+    /// assert_eq!(b'M', GeneticCode::Standard.translate_codon(b"ATG"));
+    /// assert_eq!(b'X', GeneticCode::Standard.translate_codon(b"AT"));
+    /// ```
+    pub fn translate_codon(self, codon: &[u8]) -> u8 {
+        if codon.len() < 3 {
+            return b'X';
+        }
+        let table = self.table();
+        let mut amino_acid = None;
+
+        for &first in bases(codon[0]) {
+            for &second in bases(codon[1]) {
+                for &third in bases(codon[2]) {
+                    let translated = table[first * 16 + second * 4 + third];
+                    match amino_acid {
+                        None => amino_acid = Some(translated),
+                        Some(seen) if seen != translated => return b'X',
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+
+        amino_acid.unwrap_or(b'X')
+    }
+}
+
+/// The positions in TCAG order of the bases an IUPAC code stands for, or none for characters which aren't
+/// nucleotides.
+fn bases(code: u8) -> &'static [usize] {
+    const T: usize = 0;
+    const C: usize = 1;
+    const A: usize = 2;
+    const G: usize = 3;
+    match code.to_ascii_uppercase() {
+        b'T' | b'U' => &[T],
+        b'C' => &[C],
+        b'A' => &[A],
+        b'G' => &[G],
+        b'R' => &[A, G],
+        b'Y' => &[C, T],
+        b'S' => &[C, G],
+        b'W' => &[A, T],
+        b'K' => &[G, T],
+        b'M' => &[A, C],
+        b'B' => &[C, G, T],
+        b'D' => &[A, G, T],
+        b'H' => &[A, C, T],
+        b'V' => &[A, C, G],
+        b'N' => &[T, C, A, G],
+        _ => &[],
+    }
+}
+
+/// Translates a nucleotide sequence in its first reading frame, ignoring any trailing partial codon. Stops are
+/// translated as `*` rather than ending the protein, and codons with ambiguous bases translate to `X` unless the
+/// ambiguity doesn't matter (e.g. `GCN` is always alanine).
+///
+/// # Examples
+///
+/// ```
+/// use parasailors::seq::{translate, GeneticCode};
+/// assert_eq!(b"MA*".to_vec(), translate(b"ATGGCNTGAC", GeneticCode::Standard));
+/// assert_eq!(b"MAW".to_vec(), translate(b"ATGGCNTGAC", GeneticCode::VertebrateMitochondrial));
+/// ```
+pub fn translate(sequence: &[u8], code: GeneticCode) -> Vec<u8> {
+    sequence.chunks_exact(3).map(|codon| code.translate_codon(codon)).collect()
+}
+
+/// Translates a nucleotide sequence in all six reading frames, each paired with its frame number as BLAST numbers
+/// them: 1, 2, and 3 start at the first, second, and third base of the sequence, and -1, -2, and -3 at the first,
+/// second, and third base of its reverse complement.
+///
+/// # Examples
+///
+/// ```
+/// use parasailors::seq::{six_frames, GeneticCode};
+/// let frames = six_frames(b"ATGGCCTGA", GeneticCode::Standard);
+/// assert_eq!((1, b"MA*".to_vec()), frames[0]);
+/// assert_eq!((-1, b"SGH".to_vec()), frames[3]);
+/// ```
+pub fn six_frames(sequence: &[u8], code: GeneticCode) -> Vec<(i8, Vec<u8>)> {
+    let reverse = reverse_complement(sequence);
+    let mut frames = Vec::with_capacity(6);
+    for (strand, bases) in [(1, sequence), (-1, &reverse[..])] {
+        for offset in 0..3 {
+            let frame = strand * (offset as i8 + 1);
+            frames.push((frame, translate(bases.get(offset..).unwrap_or(&[]), code)));
+        }
+    }
+    frames
+}
+
+/// The runs of `N` (in either case) at least `min_len` long, in order. Assemblies mark gaps of unknown sequence this
+/// way, sometimes for megabases, and nothing can align within them.
+///
+/// # Examples
+///
+/// ```
+/// use parasailors::seq::n_runs;
+/// assert_eq!(vec![4..9, 11..15], n_runs(b"ACGTNNNNNACNnnnGT", 3));
+/// assert!(n_runs(b"ACGTNNAC", 3).is_empty());
+/// ```
+pub fn n_runs(sequence: &[u8], min_len: usize) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (i, &base) in sequence.iter().enumerate() {
+        match (base.eq_ignore_ascii_case(&b'N'), start) {
+            (true, None) => start = Some(i),
+            (false, Some(begin)) => {
+                if i - begin >= min_len.max(1) {
+                    runs.push(begin..i);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        if sequence.len() - begin >= min_len.max(1) {
+            runs.push(begin..sequence.len());
+        }
+    }
+    runs
+}
@@ -0,0 +1,303 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use crate::error::AlignError;
+use crate::matrix::{Matrix, MatrixType};
+use crate::profile::Profile;
+
+/// A kind of biological sequence, used to tag `Seq` and `AlphabetMatrix` so they can't be mixed up.
+pub trait Alphabet {
+    /// A human readable name for error messages.
+    const NAME: &'static str;
+
+    /// Whether the character is a valid residue. Both cases are accepted.
+    fn contains(residue: u8) -> bool;
+
+    /// Whether a matrix makes sense for sequences in this alphabet. Built-in matrices are judged by their type, and
+    /// custom ones by whether their alphabet covers the alphabet's common residues.
+    fn accepts(matrix: &Matrix) -> bool;
+}
+
+/// Whether every one of `residues` is in the matrix's alphabet, ignoring case.
+fn covers(matrix: &Matrix, residues: &[u8]) -> bool {
+    let alphabet = matrix.alphabet();
+    residues.iter().all(|r| alphabet.iter().any(|a| a.eq_ignore_ascii_case(r)))
+}
+
+/// Nucleotide sequences, using the IUPAC codes (including `U` and ambiguity codes such as `N`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dna {}
+
+/// Protein sequences, using the IUPAC amino acid codes (including `B`, `Z`, `X`, `J`, `U`, `O`, and `*` for a stop).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Protein {}
+
+impl Alphabet for Dna {
+    const NAME: &'static str = "DNA";
+
+    fn contains(residue: u8) -> bool {
+        b"ACGTURYSWKMBDHVN".contains(&residue.to_ascii_uppercase())
+    }
+
+    fn accepts(matrix: &Matrix) -> bool {
+        match *matrix.matrix_type() {
+            MatrixType::Custom => covers(matrix, b"ACGT"),
+            matrix_type => matches!(
+                matrix_type,
+                MatrixType::AdaptorSearch
+                    | MatrixType::DNAFull
+                    | MatrixType::Nuc44
+                    | MatrixType::Identity
+                    | MatrixType::IdentityWithPenalty
+            ),
+        }
+    }
+}
+
+impl Alphabet for Protein {
+    const NAME: &'static str = "protein";
+
+    fn contains(residue: u8) -> bool {
+        b"ACDEFGHIKLMNPQRSTVWYBZXJUO*".contains(&residue.to_ascii_uppercase())
+    }
+
+    fn accepts(matrix: &Matrix) -> bool {
+        match *matrix.matrix_type() {
+            MatrixType::Custom => covers(matrix, b"ACDEFGHIKLMNPQRSTVWY"),
+            matrix_type => !matches!(matrix_type, MatrixType::AdaptorSearch | MatrixType::DNAFull | MatrixType::Nuc44),
+        }
+    }
+}
+
+/// A sequence checked to only contain residues of its alphabet.
+///
+/// `Seq` derefs to `[u8]`, so it can still be passed to any function taking raw sequences.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Seq<A> {
+    residues: Vec<u8>,
+    alphabet: PhantomData<A>,
+}
+
+/// A validated nucleotide sequence.
+pub type DnaSeq = Seq<Dna>;
+
+/// A validated amino acid sequence.
+pub type ProteinSeq = Seq<Protein>;
+
+impl<A: Alphabet> Seq<A> {
+    /// Checks every residue, returning an error pointing at the first one outside the alphabet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// assert!(DnaSeq::new(b"ACGTNacgt".to_vec()).is_ok());
+    /// assert!(DnaSeq::new(b"ACGTE".to_vec()).is_err());
+    /// assert!(ProteinSeq::new(b"MEEPQSDPSV".to_vec()).is_ok());
+    /// ```
+    pub fn new<S: Into<Vec<u8>>>(residues: S) -> Result<Self, AlignError> {
+        let residues = residues.into();
+        if let Some(pos) = residues.iter().position(|&r| !A::contains(r)) {
+            return Err(AlignError::InvalidResidue {
+                pos,
+                residue: residues[pos],
+                alphabet: A::NAME,
+            });
+        }
+
+        Ok(Seq {
+            residues,
+            alphabet: PhantomData,
+        })
+    }
+
+    /// The residues of the sequence.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.residues
+    }
+
+    /// Gives back the residues without the alphabet tag.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.residues
+    }
+}
+
+impl<A> Deref for Seq<A> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.residues
+    }
+}
+
+impl<A> AsRef<[u8]> for Seq<A> {
+    fn as_ref(&self) -> &[u8] {
+        &self.residues
+    }
+}
+
+/// A substitution matrix checked to suit sequences of one alphabet. Nucleotide matrices are rejected for proteins and
+/// vice versa, while the identity matrices are accepted for both. A custom matrix is accepted if its alphabet has all
+/// of `ACGT` for DNA, or all 20 standard amino acids for protein.
+///
+/// Derefs to `Matrix`, so it can still be passed to any function taking a plain matrix.
+pub struct AlphabetMatrix<A> {
+    matrix: Matrix,
+    alphabet: PhantomData<A>,
+}
+
+/// A substitution matrix for nucleotide sequences.
+pub type DnaMatrix = AlphabetMatrix<Dna>;
+
+/// A substitution matrix for amino acid sequences.
+pub type ProteinMatrix = AlphabetMatrix<Protein>;
+
+impl<A: Alphabet> AlphabetMatrix<A> {
+    /// Tags a matrix with an alphabet, returning an error if the matrix is meant for the other kind of sequence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// assert!(DnaMatrix::new(Matrix::new(MatrixType::Nuc44)).is_ok());
+    /// assert!(DnaMatrix::new(Matrix::new(MatrixType::Blosum62)).is_err());
+    ///
+    /// let custom = Matrix::identity(b"ACGT", 2, -1).unwrap();
+    /// assert!(ProteinMatrix::new(custom.to_owned_mut()).is_err());
+    /// assert!(DnaMatrix::new(custom).is_ok());
+    /// ```
+    pub fn new(matrix: Matrix) -> Result<Self, AlignError> {
+        if !A::accepts(&matrix) {
+            return Err(AlignError::IncompatibleMatrix { alphabet: A::NAME });
+        }
+
+        Ok(AlphabetMatrix {
+            matrix,
+            alphabet: PhantomData,
+        })
+    }
+
+    /// Gives back the matrix without the alphabet tag.
+    pub fn into_inner(self) -> Matrix {
+        self.matrix
+    }
+}
+
+impl<A> Deref for AlphabetMatrix<A> {
+    type Target = Matrix;
+
+    fn deref(&self) -> &Matrix {
+        &self.matrix
+    }
+}
+
+/// The kind of sequence `detect_alphabet` thinks it was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SequenceAlphabet {
+    /// Nucleotides with thymine.
+    Dna,
+    /// Nucleotides with uracil.
+    Rna,
+    /// Amino acids.
+    Protein,
+    /// Empty, or containing characters which fit neither.
+    Unknown,
+}
+
+impl SequenceAlphabet {
+    fn name(self) -> &'static str {
+        match self {
+            SequenceAlphabet::Dna => Dna::NAME,
+            SequenceAlphabet::Rna => "RNA",
+            SequenceAlphabet::Protein => Protein::NAME,
+            SequenceAlphabet::Unknown => "unknown",
+        }
+    }
+}
+
+/// Guesses whether a sequence is DNA, RNA, or protein, ignoring case, whitespace, and gaps (`-` and `.`).
+///
+/// Every nucleotide letter is also an amino acid letter, so a sequence made only of nucleotide codes in which at
+/// least 90% of the residues are `A`, `C`, `G`, `T`, `U`, or `N` is called nucleic acid (RNA if it has `U` but no `T`).
+/// Otherwise it's called protein if every residue is an amino acid code.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// assert_eq!(SequenceAlphabet::Dna, detect_alphabet(b"ACGTTGCANNRACG"));
+/// assert_eq!(SequenceAlphabet::Rna, detect_alphabet(b"ACGUUGCA"));
+/// assert_eq!(SequenceAlphabet::Protein, detect_alphabet(b"MEEPQSDPSVEPPLSQ"));
+/// assert_eq!(SequenceAlphabet::Unknown, detect_alphabet(b"ACGT1234"));
+/// ```
+pub fn detect_alphabet(sequence: &[u8]) -> SequenceAlphabet {
+    let residues: Vec<u8> = sequence
+        .iter()
+        .filter(|r| !r.is_ascii_whitespace() && **r != b'-' && **r != b'.')
+        .map(|r| r.to_ascii_uppercase())
+        .collect();
+    if residues.is_empty() {
+        return SequenceAlphabet::Unknown;
+    }
+
+    let all_nucleotide_codes = residues.iter().all(|&r| Dna::contains(r));
+    let plain = residues.iter().filter(|&&r| b"ACGTUN".contains(&r)).count();
+    if all_nucleotide_codes && plain * 10 >= residues.len() * 9 {
+        let has_u = residues.contains(&b'U');
+        let has_t = residues.contains(&b'T');
+        return if has_u && !has_t { SequenceAlphabet::Rna } else { SequenceAlphabet::Dna };
+    }
+
+    if residues.iter().all(|&r| Protein::contains(r)) {
+        SequenceAlphabet::Protein
+    } else {
+        SequenceAlphabet::Unknown
+    }
+}
+
+/// Checks that a matrix suits the detected alphabet of a sequence: nucleotide matrices for DNA and RNA, protein
+/// matrices for protein, and in every case a matrix alphabet covering all of the sequence's residues. Sequences of
+/// unknown alphabet always pass.
+pub(crate) fn check_matrix_alphabet(sequence: &[u8], matrix: &Matrix) -> Result<(), AlignError> {
+    let detected = detect_alphabet(sequence);
+    let suits_type = match detected {
+        SequenceAlphabet::Dna | SequenceAlphabet::Rna => Dna::accepts(matrix),
+        SequenceAlphabet::Protein => Protein::accepts(matrix),
+        SequenceAlphabet::Unknown => return Ok(()),
+    };
+
+    let alphabet = matrix.alphabet();
+    let covered = sequence
+        .iter()
+        .filter(|r| !r.is_ascii_whitespace() && **r != b'-' && **r != b'.')
+        .all(|r| alphabet.iter().any(|a| a.eq_ignore_ascii_case(r)));
+
+    if suits_type && covered {
+        Ok(())
+    } else {
+        Err(AlignError::IncompatibleMatrix { alphabet: detected.name() })
+    }
+}
+
+impl<'a> Profile<'a> {
+    /// Creates a profile from a validated sequence and a matrix for the same alphabet, so that e.g. DNA can't be
+    /// profiled with BLOSUM62 by mistake. The profile works with all of the profile-based alignment functions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let matrix = DnaMatrix::new(Matrix::new(MatrixType::DNAFull)).unwrap();
+    /// let query = DnaSeq::new(b"ACGTACGT".to_vec()).unwrap();
+    /// let profile = Profile::from_seq(&query, &matrix);
+    /// assert_eq!(40, global_alignment_score(&profile, b"ACGTACGT", 10, 1).unwrap());
+    /// ```
+    pub fn from_seq<A: Alphabet>(query: &'a Seq<A>, matrix: &'a AlphabetMatrix<A>) -> Self {
+        Profile::new(query.as_bytes(), &matrix.matrix)
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::align::{
+    global_alignment_stats_profile, global_traceback_profile, local_alignment_stats_profile, local_traceback_profile,
+    semi_global_alignment_stats_profile, semi_global_traceback_profile, AlignmentStats, TracebackResults,
+};
+use crate::batch::AlignmentMode;
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::profile::{Profile, ScoreWidth, StatsProfile};
+use crate::scheme::ScoringScheme;
+
+/// One query aligned against many references with the same matrix, gap costs, and mode, fixed for the life of the
+/// session so every score, stats, and traceback for the query is computed the same way.
+///
+/// The query's stats profile is built once up front, from the session's matrix, and used for every score, stats,
+/// and traceback.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let session = AlignmentSession::new(b"ACGTACGT", &matrix, AlignmentMode::SemiGlobal, 1, 1).unwrap();
+///
+/// for reference in &[&b"TTACGTACGTTT"[..], b"ACGTTCGT"] {
+///     let score = session.score(reference).unwrap();
+///     assert_eq!(score as i64, session.stats(reference).unwrap().score);
+///     assert_eq!(score as i64, session.traceback(reference).unwrap().score);
+/// }
+/// ```
+pub struct AlignmentSession<'a> {
+    query: &'a [u8],
+    matrix: &'a Matrix,
+    profile: StatsProfile<'a>,
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+}
+
+impl<'a> AlignmentSession<'a> {
+    /// Starts a session, building the query's stats profile with saturating score widths. Returns an error if
+    /// parasail can't build it, as for an empty query.
+    pub fn new<S: ScoringScheme + ?Sized>(
+        query: &'a [u8],
+        scheme: &'a S,
+        mode: AlignmentMode,
+        open_cost: i32,
+        gap_extend_cost: i32,
+    ) -> Result<Self, ParasailError> {
+        let matrix = scheme.matrix();
+        Ok(AlignmentSession {
+            query,
+            matrix,
+            profile: StatsProfile::new(query, matrix)?,
+            mode,
+            open_cost,
+            gap_extend_cost,
+        })
+    }
+
+    /// Rebuilds the query's profile with the given score width, such as a narrower one for a long scan of short
+    /// references. Returns an error if parasail can't build it.
+    pub fn with_width(mut self, width: ScoreWidth) -> Result<Self, ParasailError> {
+        self.profile = StatsProfile::with_width(self.query, self.matrix, width)?;
+        Ok(self)
+    }
+
+    /// The query every reference is aligned against.
+    pub fn query(&self) -> &'a [u8] {
+        self.query
+    }
+
+    /// The substitution matrix.
+    pub fn matrix(&self) -> &'a Matrix {
+        self.matrix
+    }
+
+    /// The query's profile.
+    pub fn profile(&self) -> &Profile<'a> {
+        &self.profile
+    }
+
+    /// The kind of alignment.
+    pub fn mode(&self) -> AlignmentMode {
+        self.mode
+    }
+
+    /// The gap open and extension costs.
+    pub fn gap_costs(&self) -> (i32, i32) {
+        (self.open_cost, self.gap_extend_cost)
+    }
+
+    /// Scores the query against a reference with the session's profile.
+    pub fn score(&self, reference: &[u8]) -> Result<i32, ParasailError> {
+        self.mode.score(&self.profile, reference, self.open_cost, self.gap_extend_cost)
+    }
+
+    /// Aligns the query against a reference with the session's profile and counts matches, gaps, and the like.
+    pub fn stats(&self, reference: &[u8]) -> Result<AlignmentStats, ParasailError> {
+        let stats = match self.mode {
+            AlignmentMode::Global => global_alignment_stats_profile,
+            AlignmentMode::SemiGlobal => semi_global_alignment_stats_profile,
+            AlignmentMode::Local => local_alignment_stats_profile,
+        };
+        stats(&self.profile, reference, self.open_cost, self.gap_extend_cost)
+    }
+
+    /// Aligns the query against a reference with the session's profile and traces the alignment back.
+    pub fn traceback(&self, reference: &[u8]) -> Result<TracebackResults, ParasailError> {
+        let traceback = match self.mode {
+            AlignmentMode::Global => global_traceback_profile,
+            AlignmentMode::SemiGlobal => semi_global_traceback_profile,
+            AlignmentMode::Local => local_traceback_profile,
+        };
+        traceback(&self.profile, reference, self.open_cost, self.gap_extend_cost)
+    }
+}
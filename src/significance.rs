@@ -0,0 +1,115 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::align::local_alignment_score;
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::profile::Profile;
+
+/// How an observed alignment score compares to scores against shuffled references.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Significance {
+    /// The local alignment score of the query against the real reference.
+    pub score: i64,
+    /// The estimated probability of scoring at least `score` against an unrelated sequence of the same composition.
+    pub p_value: f64,
+    /// The mean score against the shuffled references.
+    pub shuffled_mean: f64,
+    /// The standard deviation of the scores against the shuffled references.
+    pub shuffled_std_dev: f64,
+}
+
+impl Significance {
+    /// How many standard deviations the observed score lies above the shuffled mean, or 0 if the shuffled scores
+    /// don't vary.
+    pub fn z_score(&self) -> f64 {
+        if self.shuffled_std_dev == 0.0 {
+            0.0
+        } else {
+            (self.score as f64 - self.shuffled_mean) / self.shuffled_std_dev
+        }
+    }
+}
+
+/// Estimates the significance of a local alignment score by permutation: the query is re-aligned against `shuffles`
+/// random permutations of the reference, which keep its composition but destroy any real similarity.
+///
+/// The p-value is `(1 + k) / (1 + shuffles)`, where `k` is the number of shuffled references scoring at least as
+/// well as the real one, so it's never zero and can't resolve anything below `1 / (1 + shuffles)`. This works with
+/// any matrix and gap costs, unlike Karlin-Altschul statistics, at the price of `shuffles` extra alignments. The
+/// shuffles are drawn from `seed`, so the same inputs always give the same estimate. Returns an error if parasail
+/// fails on any of the alignments.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let query = b"ACGTTGCATGCCGATAGCTA";
+/// let reference = b"TTGACAGGTACGTTGCATGCCGATAGCTAGGACTTAGCAT";
+///
+/// let significance = shuffle_significance(query, reference, 2, 1, &matrix, 99, 7).unwrap();
+/// assert_eq!(20, significance.score);
+/// assert!(significance.p_value <= 0.05);
+/// ```
+pub fn shuffle_significance(
+    query: &[u8],
+    reference: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    matrix: &Matrix,
+    shuffles: usize,
+    seed: u64,
+) -> Result<Significance, ParasailError> {
+    let profile = Profile::try_new(query, matrix)?;
+    let score = local_alignment_score(&profile, reference, open_cost, gap_extend_cost)? as i64;
+
+    let mut rng = SplitMix64(seed);
+    let mut shuffled = reference.to_vec();
+    let mut at_least = 0;
+    let (mut sum, mut sum_squares) = (0.0, 0.0);
+
+    for _ in 0..shuffles {
+        // Fisher-Yates, continuing from the previous permutation
+        for i in (1..shuffled.len()).rev() {
+            let j = (rng.next() % (i as u64 + 1)) as usize;
+            shuffled.swap(i, j);
+        }
+
+        let shuffled_score = local_alignment_score(&profile, &shuffled, open_cost, gap_extend_cost)? as i64;
+        if shuffled_score >= score {
+            at_least += 1;
+        }
+        sum += shuffled_score as f64;
+        sum_squares += (shuffled_score * shuffled_score) as f64;
+    }
+
+    let (shuffled_mean, shuffled_std_dev) = if shuffles == 0 {
+        (0.0, 0.0)
+    } else {
+        let mean = sum / shuffles as f64;
+        (mean, (sum_squares / shuffles as f64 - mean * mean).max(0.0).sqrt())
+    };
+
+    Ok(Significance {
+        score,
+        p_value: (1 + at_least) as f64 / (1 + shuffles) as f64,
+        shuffled_mean,
+        shuffled_std_dev,
+    })
+}
+
+/// A small, fast generator which is plenty for shuffling and keeps results reproducible without a dependency.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
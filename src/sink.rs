@@ -0,0 +1,235 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+
+/// Somewhere to send results as soon as they're computed, such as the sending half of a channel, so a consumer can
+/// write them out while the rest are still being aligned (see `align_batch_streaming`).
+///
+/// Implemented for the standard library's `Sender` and `SyncSender`, and for the senders of `crossbeam-channel` and
+/// `flume` behind the `crossbeam` and `flume` features, and for `BoundedSender`. A bounded channel makes the aligning
+/// threads wait whenever the consumer falls behind, so results never pile up in memory; `bounded_results` makes one
+/// which also reports how full it is.
+pub trait ResultSink<T> {
+    /// Sends one result, blocking while the sink is full. Returns `false` if nothing is listening any more, in which
+    /// case the work feeding the sink should stop.
+    fn send_result(&self, result: T) -> bool;
+}
+
+impl<T> ResultSink<T> for mpsc::Sender<T> {
+    fn send_result(&self, result: T) -> bool {
+        self.send(result).is_ok()
+    }
+}
+
+impl<T> ResultSink<T> for mpsc::SyncSender<T> {
+    fn send_result(&self, result: T) -> bool {
+        self.send(result).is_ok()
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl<T> ResultSink<T> for crossbeam_channel::Sender<T> {
+    fn send_result(&self, result: T) -> bool {
+        self.send(result).is_ok()
+    }
+}
+
+#[cfg(feature = "flume")]
+impl<T> ResultSink<T> for flume::Sender<T> {
+    fn send_result(&self, result: T) -> bool {
+        self.send(result).is_ok()
+    }
+}
+
+/// Creates a channel holding at most `capacity` results, for streaming from functions such as
+/// `align_batch_streaming` to a slower consumer. Sending blocks while the channel is full, so fast producers are held
+/// back instead of buffering without bound, and both halves can report how full the channel is through
+/// `QueueMetrics`. A `capacity` of 0 hands each result straight to a waiting receiver.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let references: Vec<&[u8]> = vec![b"ACGTACGT", b"TTTTTTTT", b"ACGTTCGT"];
+///
+/// let (sender, receiver) = bounded_results(1);
+/// let metrics = receiver.metrics().clone();
+/// let complete = std::thread::scope(|scope| {
+///     let writer = scope.spawn(move || {
+///         let mut scores: Vec<(usize, i32)> = receiver.collect();
+///         scores.sort();
+///         scores
+///     });
+///     let mode = AlignmentMode::Global;
+///     let complete = align_batch_streaming(b"ACGTACGT", &references, mode, 1, 1, &matrix, 2, &sender).unwrap();
+///     drop(sender);
+///     assert_eq!(vec![(0, 8), (1, -4), (2, 6)], writer.join().unwrap());
+///     complete
+/// });
+/// assert!(complete);
+/// assert_eq!(1, metrics.capacity());
+/// assert_eq!(0, metrics.depth());
+/// ```
+pub fn bounded_results<T>(capacity: usize) -> (BoundedSender<T>, BoundedReceiver<T>) {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+    let metrics = QueueMetrics {
+        counters: Arc::new(QueueCounters {
+            capacity,
+            sent: AtomicUsize::new(0),
+            received: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            stalls: AtomicUsize::new(0),
+        }),
+    };
+
+    let sender = BoundedSender {
+        sender,
+        metrics: metrics.clone(),
+    };
+    (sender, BoundedReceiver { receiver, metrics })
+}
+
+/// How full a channel made by `bounded_results` is, shared by both of its halves.
+///
+/// The counts are updated just after each send and receive, so a snapshot taken while results are changing hands
+/// can be off by those results.
+#[derive(Debug, Clone)]
+pub struct QueueMetrics {
+    counters: Arc<QueueCounters>,
+}
+
+#[derive(Debug)]
+struct QueueCounters {
+    capacity: usize,
+    sent: AtomicUsize,
+    received: AtomicUsize,
+    peak: AtomicUsize,
+    stalls: AtomicUsize,
+}
+
+impl QueueMetrics {
+    /// The most results the channel holds before senders have to wait.
+    pub fn capacity(&self) -> usize {
+        self.counters.capacity
+    }
+
+    /// The number of results sent but not yet received.
+    pub fn depth(&self) -> usize {
+        let received = self.counters.received.load(Ordering::Relaxed);
+        self.counters.sent.load(Ordering::Relaxed).saturating_sub(received)
+    }
+
+    /// The highest `depth` seen so far. A peak at `capacity` means the consumer fell behind at some point.
+    pub fn peak_depth(&self) -> usize {
+        self.counters.peak.load(Ordering::Relaxed)
+    }
+
+    /// The number of sends which found the channel full and had to wait for the consumer.
+    pub fn stalls(&self) -> usize {
+        self.counters.stalls.load(Ordering::Relaxed)
+    }
+}
+
+/// The sending half of a channel made by `bounded_results`. Clone it to send from several places.
+#[derive(Debug)]
+pub struct BoundedSender<T> {
+    sender: mpsc::SyncSender<T>,
+    metrics: QueueMetrics,
+}
+
+impl<T> BoundedSender<T> {
+    /// Sends a result, waiting while the channel is full. Returns `false` if the receiver has been dropped.
+    pub fn send(&self, result: T) -> bool {
+        let sent = match self.sender.try_send(result) {
+            Ok(()) => true,
+            Err(mpsc::TrySendError::Full(result)) => {
+                self.metrics.counters.stalls.fetch_add(1, Ordering::Relaxed);
+                self.sender.send(result).is_ok()
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        };
+
+        if sent {
+            let counters = &self.metrics.counters;
+            counters.sent.fetch_add(1, Ordering::Relaxed);
+            counters.peak.fetch_max(self.metrics.depth(), Ordering::Relaxed);
+        }
+        sent
+    }
+
+    /// The channel's fill level.
+    pub fn metrics(&self) -> &QueueMetrics {
+        &self.metrics
+    }
+}
+
+impl<T> Clone for BoundedSender<T> {
+    fn clone(&self) -> Self {
+        BoundedSender {
+            sender: self.sender.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<T> ResultSink<T> for BoundedSender<T> {
+    fn send_result(&self, result: T) -> bool {
+        self.send(result)
+    }
+}
+
+/// The receiving half of a channel made by `bounded_results`. Iterating over it receives results until every
+/// sender has been dropped.
+#[derive(Debug)]
+pub struct BoundedReceiver<T> {
+    receiver: mpsc::Receiver<T>,
+    metrics: QueueMetrics,
+}
+
+impl<T> BoundedReceiver<T> {
+    /// Waits for the next result, or returns `None` once the channel is empty and every sender has been dropped.
+    pub fn recv(&self) -> Option<T> {
+        let result = self.receiver.recv().ok()?;
+        self.metrics.counters.received.fetch_add(1, Ordering::Relaxed);
+        Some(result)
+    }
+
+    /// The channel's fill level. Clone it to keep watching after the receiver has moved to another thread.
+    pub fn metrics(&self) -> &QueueMetrics {
+        &self.metrics
+    }
+}
+
+impl<T> Iterator for BoundedReceiver<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.recv()
+    }
+}
+
+#[test]
+fn test_bounded_results_hold_back_the_sender() {
+    use std::thread;
+    use std::time::Duration;
+
+    let (sender, receiver) = bounded_results(2);
+    let metrics = receiver.metrics().clone();
+
+    let producer = thread::spawn(move || (0..5).all(|i| sender.send(i)));
+    // the third send can't finish until something is received
+    while metrics.stalls() == 0 {
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(2, metrics.depth());
+
+    assert_eq!(vec![0, 1, 2, 3, 4], receiver.collect::<Vec<_>>());
+    assert!(producer.join().unwrap());
+    assert_eq!(0, metrics.depth());
+    assert!(metrics.peak_depth() >= 2);
+}
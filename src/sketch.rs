@@ -0,0 +1,236 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::cmp::Ordering;
+
+use crate::minimizer::mix;
+
+/// A bottom-s MinHash sketch of a DNA sequence, as made by Mash: the `size` smallest hashes of its canonical k-mers.
+///
+/// Two sketches estimate how similar their sequences are without aligning them, in time proportional to the sketch
+/// size rather than the sequence lengths. Sketch a database once, then rank it against each query's sketch (see
+/// `rank_by_sketch`) to decide which references are worth aligning.
+///
+/// A k-mer and its reverse complement hash the same, so sketches don't depend on strand. Bases are read
+/// case-insensitively, and k-mers containing anything other than `A`, `C`, `G`, or `T` are skipped.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let a = Sketch::new(b"GATCCTAGCATCGACTAGCGGCATTACAGTTGACCATGAGCTAGGCTTACG", 11, 64);
+/// let b = Sketch::new(b"GATCCTAGCATCGACTAGCGGCATTACAGTTGACCATGAGCTAGGCTTACG", 11, 64);
+/// let c = Sketch::new(b"GGCATCGATCAGCGACTTAGGCAATCTAGCGCGATATCGCTAGGACTCAGCA", 11, 64);
+///
+/// assert_eq!(1.0, a.jaccard(&b));
+/// assert_eq!(0.0, a.jaccard(&c));
+/// assert_eq!(1.0, a.identity(&b));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sketch {
+    k: usize,
+    size: usize,
+    // ascending, without repeats
+    hashes: Vec<u64>,
+}
+
+impl Sketch {
+    /// Sketches `sequence` with k-mers of length `k`, keeping at most `size` hashes. Larger sketches give tighter
+    /// estimates; Mash defaults to `k = 21` and `size = 1000`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0 or more than 32, or if `size` is 0.
+    pub fn new(sequence: &[u8], k: usize, size: usize) -> Self {
+        assert!(k > 0 && k <= 32, "k must be between 1 and 32");
+        assert!(size > 0, "a sketch must keep at least one hash");
+
+        let mut hashes = canonical_hashes(sequence, k);
+        hashes.sort_unstable();
+        hashes.dedup();
+        hashes.truncate(size);
+
+        Sketch { k, size, hashes }
+    }
+
+    /// The k-mer length of the sketch.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The most hashes the sketch keeps.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The hashes in the sketch, smallest first. There are fewer than `size` only if the sequence has fewer distinct
+    /// k-mers.
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// Estimates the Jaccard similarity of the two sequences' k-mer sets: of the `size` smallest hashes among both
+    /// sketches, the fraction found in both.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sketches were made with different `k`.
+    pub fn jaccard(&self, other: &Sketch) -> f64 {
+        assert_eq!(self.k, other.k, "sketches must share k to be compared");
+
+        let size = self.size.min(other.size);
+        let (mut i, mut j) = (0, 0);
+        let (mut shared, mut seen) = (0, 0);
+        while seen < size && i < self.hashes.len() && j < other.hashes.len() {
+            match self.hashes[i].cmp(&other.hashes[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    shared += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+            seen += 1;
+        }
+        // whatever is left of the longer sketch fills out the union's smallest hashes
+        seen = (seen + (self.hashes.len() - i) + (other.hashes.len() - j)).min(size);
+
+        if seen == 0 {
+            0.0
+        } else {
+            shared as f64 / seen as f64
+        }
+    }
+
+    /// Estimates the fraction of this sequence's k-mers found in the other's, as when asking whether a read comes
+    /// from a longer reference. Only this sketch's hashes small enough that `other` would have kept them count.
+    ///
+    /// The estimate is rough when `other`'s sequence is so much longer that few of this sketch's hashes fall under
+    /// its largest; sketch references with a larger `size` to compensate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sketches were made with different `k`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use parasailors::*;
+    /// let reference = b"GATCCTAGCATCGACTAGCGGCATTACAGTTGACCATGAGCTAGGCTTACGGGCATCGATCAGCGACTTAGGCAATCTAGCG";
+    /// let read = &reference[20..60];
+    ///
+    /// let reference = Sketch::new(reference, 11, 1000);
+    /// assert_eq!(1.0, Sketch::new(read, 11, 1000).containment(&reference));
+    /// ```
+    pub fn containment(&self, other: &Sketch) -> f64 {
+        assert_eq!(self.k, other.k, "sketches must share k to be compared");
+
+        // a full sketch only knows about hashes up to its largest one
+        let limit = match other.hashes.last() {
+            Some(&largest) if other.hashes.len() >= other.size => largest,
+            _ => u64::MAX,
+        };
+
+        let candidates: Vec<u64> = self.hashes.iter().copied().filter(|&h| h <= limit).collect();
+        if candidates.is_empty() {
+            return 0.0;
+        }
+        let shared = candidates.iter().filter(|h| other.hashes.binary_search(h).is_ok()).count();
+        shared as f64 / candidates.len() as f64
+    }
+
+    /// Estimates the identity of the two sequences from their Jaccard similarity, with the Mash distance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sketches were made with different `k`.
+    pub fn identity(&self, other: &Sketch) -> f64 {
+        mash_identity(self.jaccard(other), self.k)
+    }
+
+    /// Estimates the identity of this sequence to the part of the other it's contained in, from `containment`, as
+    /// `mash screen` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sketches were made with different `k`.
+    pub fn containment_identity(&self, other: &Sketch) -> f64 {
+        self.containment(other).powf(1.0 / self.k as f64)
+    }
+}
+
+/// Ranks a database by how well each sketch contains the query's (see `Sketch::containment_identity`), returning the
+/// indices and estimated identities of the `n` best, best first. Ties go to the earlier sketch. References with no
+/// k-mers in common with the query are left out.
+///
+/// # Panics
+///
+/// Panics if any sketch was made with a different `k` from the query's.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let references: Vec<&[u8]> = vec![
+///     b"TTGACCATGAGCTAGGCTTACGATCGGATCCTAGCATCGACTAGCGGCATTACAG",
+///     b"GGCATCGATCAGCGACTTAGGCAATCTAGCGCGATATCGCTAGGACTCAGCAAT",
+/// ];
+/// let database: Vec<Sketch> = references.iter().map(|r| Sketch::new(r, 11, 200)).collect();
+///
+/// let query = Sketch::new(b"GATCAGCGACTTAGGCAATCTAGC", 11, 200);
+/// let ranked = rank_by_sketch(&query, &database, 5);
+/// assert_eq!(1, ranked.len());
+/// assert_eq!(1, ranked[0].0);
+/// ```
+pub fn rank_by_sketch(query: &Sketch, database: &[Sketch], n: usize) -> Vec<(usize, f64)> {
+    let mut ranked: Vec<(usize, f64)> = database
+        .iter()
+        .enumerate()
+        .map(|(i, sketch)| (i, query.containment_identity(sketch)))
+        .filter(|&(_, identity)| identity > 0.0)
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked.truncate(n);
+    ranked
+}
+
+/// The identity implied by a k-mer Jaccard similarity, assuming mutations are independent (Ondov et al., 2016).
+pub(crate) fn mash_identity(jaccard: f64, k: usize) -> f64 {
+    if jaccard <= 0.0 {
+        return 0.0;
+    }
+    let distance = -(2.0 * jaccard / (1.0 + jaccard)).ln() / k as f64;
+    (1.0 - distance).clamp(0.0, 1.0)
+}
+
+/// The hash of the canonical form (the smaller of it and its reverse complement) of each unambiguous k-mer.
+fn canonical_hashes(sequence: &[u8], k: usize) -> Vec<u64> {
+    let mask = if k == 32 { u64::MAX } else { (1 << (2 * k)) - 1 };
+    let mut hashes = Vec::with_capacity(sequence.len().saturating_sub(k - 1));
+    let (mut forward, mut reverse, mut valid) = (0u64, 0u64, 0);
+
+    for &base in sequence {
+        let code = match base.to_ascii_uppercase() {
+            b'A' => 0,
+            b'C' => 1,
+            b'G' => 2,
+            b'T' => 3,
+            _ => {
+                valid = 0;
+                continue;
+            }
+        };
+        forward = ((forward << 2) | code) & mask;
+        reverse = (reverse >> 2) | ((3 - code) << (2 * (k - 1)));
+        valid += 1;
+
+        if valid >= k {
+            hashes.push(mix(forward.min(reverse), mask));
+        }
+    }
+
+    hashes
+}
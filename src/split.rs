@@ -0,0 +1,139 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::align::{semi_global_dx_traceback, TracebackResults};
+use crate::dp_table::{semi_global_dx_score_table, DpTable};
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+
+/// Controls how `split_alignment` splits a query in two.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitParams {
+    /// The fewest query residues each segment may have.
+    pub min_segment: usize,
+    /// The cost of the jump between the segments, whatever its length, like the penalty for an intron.
+    pub junction_cost: i64,
+}
+
+impl Default for SplitParams {
+    /// Segments of at least 20 residues, and a junction cost of 10.
+    fn default() -> Self {
+        SplitParams {
+            min_segment: 20,
+            junction_cost: 10,
+        }
+    }
+}
+
+/// A query aligned as two segments, which can land anywhere in the reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitAlignment {
+    /// The sum of the segments' scores, less the junction cost.
+    pub score: i64,
+    /// The query index (0-based) the second segment starts at.
+    pub junction: usize,
+    /// The alignment of the query before the junction.
+    pub first: TracebackResults,
+    /// The alignment of the query from the junction on. Its `query_end` counts from the start of the whole query.
+    pub second: TracebackResults,
+}
+
+impl SplitAlignment {
+    /// The reference positions either side of the jump: just past the end of the first segment, and the start of the
+    /// second. For a spliced read these are the ends of the intron.
+    pub fn ref_junction(&self) -> (usize, usize) {
+        (self.first.ref_end, self.second.ref_begin())
+    }
+
+    /// How far along the reference the second segment starts from the end of the first, which is negative if it
+    /// starts before it, as for a tandem duplication.
+    pub fn ref_distance(&self) -> i64 {
+        let (donor, acceptor) = self.ref_junction();
+        acceptor as i64 - donor as i64
+    }
+}
+
+/// Aligns a query as two pieces against regions of the reference which may be far apart, for reads spanning a splice
+/// junction or the breakpoint of a structural variant. Returns `None` if the query is too short to split into two
+/// segments of at least `min_segment` residues, or the first error parasail reports.
+///
+/// Each piece is aligned like `semi_global_dx_traceback`: all of it, against any stretch of the reference. The best
+/// score of every prefix of the query comes from the score table of one such pass, and of every suffix from a pass
+/// over both sequences reversed, so the best junction is found without aligning each split separately. The gap
+/// between the segments costs `junction_cost` however long it is, and the segments may also overlap in the reference
+/// or come in either order. The two chosen pieces are then traced back. The score tables take `4 * query_len *
+/// ref_len` bytes each, so the reference should be a candidate region rather than a whole genome.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let reference = b"ATATCACACCCAACCTTCAAATGCCGTGCCCTAACGCCCTAATCCTGCGCTAGGGGTTGCAGCGACCAGATGGCATCGTTAAGAACCGCC";
+/// let read = [&reference[10..35], &reference[60..85]].concat();
+///
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let params = SplitParams::default();
+/// let split = split_alignment(&read, reference, 3, 1, &matrix, &params).unwrap().unwrap();
+///
+/// assert_eq!(50 - params.junction_cost, split.score);
+/// // where exactly the read splits is ambiguous if the exons' ends look alike, but the jump is the same
+/// assert_eq!((10 + split.junction, 60 + split.junction - 25), split.ref_junction());
+/// assert_eq!(25, split.ref_distance());
+/// ```
+pub fn split_alignment(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+    params: &SplitParams,
+) -> Result<Option<SplitAlignment>, ParasailError> {
+    let (n, min_segment) = (query_sequence.len(), params.min_segment.max(1));
+    if n < 2 * min_segment || database_sequence.is_empty() {
+        return Ok(None);
+    }
+
+    // prefixes[i] is the best score of query[..=i], and suffixes[i] of query[n - 1 - i..]
+    let prefixes = row_maxima(&semi_global_dx_score_table(
+        query_sequence,
+        database_sequence,
+        open_cost,
+        gap_extend_cost,
+        substitution_matrix,
+    )?);
+    let reversed = |sequence: &[u8]| sequence.iter().rev().cloned().collect::<Vec<u8>>();
+    let suffixes = row_maxima(&semi_global_dx_score_table(
+        &reversed(query_sequence),
+        &reversed(database_sequence),
+        open_cost,
+        gap_extend_cost,
+        substitution_matrix,
+    )?);
+
+    let mut junction = min_segment;
+    for k in min_segment + 1..=n - min_segment {
+        if prefixes[k - 1] + suffixes[n - 1 - k] > prefixes[junction - 1] + suffixes[n - 1 - junction] {
+            junction = k;
+        }
+    }
+
+    let align = |piece: &[u8]| {
+        semi_global_dx_traceback(piece, database_sequence, open_cost, gap_extend_cost, substitution_matrix)
+    };
+    let (first, mut second) = (align(&query_sequence[..junction])?, align(&query_sequence[junction..])?);
+    second.query_end += junction;
+
+    Ok(Some(SplitAlignment {
+        score: first.score + second.score - params.junction_cost,
+        junction,
+        first,
+        second,
+    }))
+}
+
+/// The highest score in each row of a table.
+fn row_maxima(table: &DpTable) -> Vec<i64> {
+    (0..table.rows()).map(|i| table.row(i).iter().copied().max().unwrap_or(0) as i64).collect()
+}
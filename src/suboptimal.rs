@@ -0,0 +1,192 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use libc::c_int;
+
+use crate::align::ratio;
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+use crate::result::ParasailResult;
+use parasail_sys::parasail_sw_trace_striped_sat;
+
+/// The coordinates and score of a single local alignment between a query and a reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalHit {
+    /// The score according to the substitution matrix and gap penalty scheme used.
+    pub score: i64,
+    /// The starting index (0-based) of the alignment in the query.
+    pub query_begin: usize,
+    /// The ending index (0-based, exclusive) of the alignment in the query.
+    pub query_end: usize,
+    /// The starting index (0-based) of the alignment in the reference.
+    pub ref_begin: usize,
+    /// The ending index (0-based, exclusive) of the alignment in the reference.
+    pub ref_end: usize,
+    /// The length of the whole query.
+    pub query_len: usize,
+    /// The length of the whole reference.
+    pub ref_len: usize,
+}
+
+impl LocalHit {
+    /// The fraction of the query covered by the hit, or 0 for an empty query.
+    pub fn query_coverage(&self) -> f64 {
+        ratio(self.query_end - self.query_begin, self.query_len)
+    }
+
+    /// The fraction of the reference covered by the hit, or 0 for an empty reference.
+    pub fn ref_coverage(&self) -> f64 {
+        ratio(self.ref_end - self.ref_begin, self.ref_len)
+    }
+}
+
+/// Reports up to `max_alignments` non-overlapping local alignments of the query against the reference, best first.
+///
+/// After the best local alignment is found, the part of the reference it covers is masked out and the query is
+/// realigned against the remaining stretches of the reference on either side, in the spirit of Waterman-Eggert. This
+/// repeats until `max_alignments` hits have been reported or no remaining alignment scores at least `min_score`. The
+/// reported reference intervals never overlap, although the query intervals may. Ties are broken in favor of the
+/// leftmost hit in the reference. Returns an error if parasail fails to align any of the stretches.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let query = b"GATTACA";
+/// let reference = b"CCGATTACACCCCGATTACACC";
+///
+/// let hits = suboptimal_local_alignments(query, reference, 1, 1, &matrix, 5, 5).unwrap();
+/// assert_eq!(2, hits.len());
+/// assert_eq!((7, 2, 9), (hits[0].score, hits[0].ref_begin, hits[0].ref_end));
+/// assert_eq!((7, 13, 20), (hits[1].score, hits[1].ref_begin, hits[1].ref_end));
+/// assert_eq!(1.0, hits[0].query_coverage());
+/// ```
+pub fn suboptimal_local_alignments(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+    max_alignments: usize,
+    min_score: i64,
+) -> Result<Vec<LocalHit>, ParasailError> {
+    let mut hits = Vec::new();
+    if query_sequence.is_empty() {
+        return Ok(hits);
+    }
+
+    let mut segments = vec![Segment::new(0, database_sequence.len())];
+
+    while hits.len() < max_alignments {
+        // only the stretches on either side of the last reported hit need to be realigned
+        for segment in segments.iter_mut().filter(|s| !s.searched) {
+            segment.best = best_local_hit(
+                query_sequence,
+                database_sequence,
+                segment.start,
+                segment.end,
+                open_cost,
+                gap_extend_cost,
+                substitution_matrix,
+            )?;
+            segment.searched = true;
+        }
+
+        let mut best: Option<(usize, &LocalHit)> = None;
+        for (i, segment) in segments.iter().enumerate() {
+            if let Some(ref hit) = segment.best {
+                match best {
+                    Some((_, current)) if current.score >= hit.score => {}
+                    _ => best = Some((i, hit)),
+                }
+            }
+        }
+
+        let (index, hit) = match best {
+            Some((index, hit)) if hit.score >= min_score => (index, hit.clone()),
+            _ => break,
+        };
+
+        let masked = segments.remove(index);
+        if hit.ref_end < masked.end {
+            segments.insert(index, Segment::new(hit.ref_end, masked.end));
+        }
+        if masked.start < hit.ref_begin {
+            segments.insert(index, Segment::new(masked.start, hit.ref_begin));
+        }
+        hits.push(hit);
+    }
+
+    Ok(hits)
+}
+
+/// A stretch of the reference which hasn't been covered by a reported hit yet, kept sorted by start.
+struct Segment {
+    start: usize,
+    end: usize,
+    searched: bool,
+    best: Option<LocalHit>,
+}
+
+impl Segment {
+    fn new(start: usize, end: usize) -> Self {
+        Segment {
+            start,
+            end,
+            searched: false,
+            best: None,
+        }
+    }
+}
+
+/// Finds the best local alignment of the query within `database_sequence[start..end]`, reporting coordinates in
+/// terms of the whole reference.
+fn best_local_hit(
+    query_sequence: &[u8],
+    database_sequence: &[u8],
+    start: usize,
+    end: usize,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<Option<LocalHit>, ParasailError> {
+    let segment = &database_sequence[start..end];
+    if segment.is_empty() {
+        return Ok(None);
+    }
+
+    let result = unsafe {
+        ParasailResult::from_raw(parasail_sw_trace_striped_sat(
+            query_sequence.as_ptr(),
+            query_sequence.len() as c_int,
+            segment.as_ptr(),
+            segment.len() as c_int,
+            open_cost,
+            gap_extend_cost,
+            **substitution_matrix,
+        ))?
+    };
+
+    let score = result.score() as i64;
+    if score <= 0 {
+        return Ok(None);
+    }
+
+    // the cigar is the cheapest way to have parasail walk the trace back to the alignment's start, although it walks
+    // on through the gaps before it
+    let mut cigar = result.cigar(query_sequence, segment, substitution_matrix)?;
+    cigar.strip_leading_gaps();
+
+    Ok(Some(LocalHit {
+        score,
+        query_begin: cigar.query_begin,
+        query_end: result.query_end(),
+        ref_begin: start + cigar.ref_begin,
+        ref_end: start + result.ref_end(),
+        query_len: query_sequence.len(),
+        ref_len: database_sequence.len(),
+    }))
+}
@@ -0,0 +1,179 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::align::{AlignmentStats, AlignmentStatsWithTrace};
+use crate::batch::ReferenceHit;
+use crate::search::{FrameHit, Match};
+use crate::suboptimal::LocalHit;
+
+/// The values of one column of a table of results.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValues {
+    /// Signed integers, such as scores and frames.
+    Int(Vec<i64>),
+    /// Unsigned integers, such as coordinates, lengths, and counts.
+    UInt(Vec<u64>),
+    /// Fractions, such as identity and coverage.
+    Float(Vec<f64>),
+}
+
+impl ColumnValues {
+    /// The number of values in the column.
+    pub fn len(&self) -> usize {
+        match *self {
+            ColumnValues::Int(ref values) => values.len(),
+            ColumnValues::UInt(ref values) => values.len(),
+            ColumnValues::Float(ref values) => values.len(),
+        }
+    }
+
+    /// Whether the column has no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A named column of a table of results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    /// The column's name, the same as the field it comes from (or the method, for derived values like coverage).
+    pub name: &'static str,
+    /// One value per row.
+    pub values: ColumnValues,
+}
+
+/// Result types which can be laid out as a table with a row per result and a column per field, so a batch of them can
+/// be handed to a columnar format in one go (see `to_record_batch` and `to_dataframe`, behind the `arrow` and `polars`
+/// features).
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let hits = vec![
+///     ReferenceHit { score: 8, index: 2, mapq: 15 },
+///     ReferenceHit { score: 6, index: 0, mapq: 0 },
+/// ];
+///
+/// let columns = ReferenceHit::columns(&hits);
+/// assert_eq!("score", columns[0].name);
+/// assert_eq!(ColumnValues::Int(vec![8, 6]), columns[0].values);
+/// ```
+pub trait Tabular: Sized {
+    /// Lays out the rows as columns, always the same ones in the same order, each with a value per row.
+    fn columns(rows: &[Self]) -> Vec<Column>;
+}
+
+fn int<T>(name: &'static str, rows: &[T], value: impl Fn(&T) -> i64) -> Column {
+    Column {
+        name,
+        values: ColumnValues::Int(rows.iter().map(value).collect()),
+    }
+}
+
+fn uint<T>(name: &'static str, rows: &[T], value: impl Fn(&T) -> u64) -> Column {
+    Column {
+        name,
+        values: ColumnValues::UInt(rows.iter().map(value).collect()),
+    }
+}
+
+fn float<T>(name: &'static str, rows: &[T], value: impl Fn(&T) -> f64) -> Column {
+    Column {
+        name,
+        values: ColumnValues::Float(rows.iter().map(value).collect()),
+    }
+}
+
+impl Tabular for AlignmentStats {
+    fn columns(rows: &[Self]) -> Vec<Column> {
+        vec![
+            int("score", rows, |r| r.score),
+            uint("num_matches", rows, |r| r.num_matches),
+            uint("num_positive_subs", rows, |r| r.num_positive_subs),
+            uint("align_length", rows, |r| r.align_length as u64),
+            uint("query_end", rows, |r| r.query_end as u64),
+            uint("ref_end", rows, |r| r.ref_end as u64),
+            uint("query_len", rows, |r| r.query_len as u64),
+            uint("ref_len", rows, |r| r.ref_len as u64),
+        ]
+    }
+}
+
+impl Tabular for AlignmentStatsWithTrace {
+    fn columns(rows: &[Self]) -> Vec<Column> {
+        vec![
+            int("score", rows, |r| r.stats.score),
+            uint("num_matches", rows, |r| r.stats.num_matches),
+            uint("num_positive_subs", rows, |r| r.stats.num_positive_subs),
+            uint("num_mismatches", rows, |r| r.num_mismatches),
+            uint("num_gap_opens", rows, |r| r.num_gap_opens),
+            uint("num_gap_bases", rows, |r| r.num_gap_bases),
+            uint("align_length", rows, |r| r.stats.align_length as u64),
+            uint("query_begin", rows, |r| r.query_begin as u64),
+            uint("query_end", rows, |r| r.stats.query_end as u64),
+            uint("ref_begin", rows, |r| r.ref_begin as u64),
+            uint("ref_end", rows, |r| r.stats.ref_end as u64),
+            uint("query_len", rows, |r| r.stats.query_len as u64),
+            uint("ref_len", rows, |r| r.stats.ref_len as u64),
+            float("query_coverage", rows, |r| r.query_coverage()),
+            float("ref_coverage", rows, |r| r.ref_coverage()),
+        ]
+    }
+}
+
+impl Tabular for LocalHit {
+    fn columns(rows: &[Self]) -> Vec<Column> {
+        vec![
+            int("score", rows, |r| r.score),
+            uint("query_begin", rows, |r| r.query_begin as u64),
+            uint("query_end", rows, |r| r.query_end as u64),
+            uint("ref_begin", rows, |r| r.ref_begin as u64),
+            uint("ref_end", rows, |r| r.ref_end as u64),
+            uint("query_len", rows, |r| r.query_len as u64),
+            uint("ref_len", rows, |r| r.ref_len as u64),
+            float("query_coverage", rows, |r| r.query_coverage()),
+            float("ref_coverage", rows, |r| r.ref_coverage()),
+        ]
+    }
+}
+
+impl Tabular for FrameHit {
+    fn columns(rows: &[Self]) -> Vec<Column> {
+        vec![
+            int("frame", rows, |r| r.frame as i64),
+            int("score", rows, |r| r.score),
+            uint("query_begin", rows, |r| r.query_begin as u64),
+            uint("query_end", rows, |r| r.query_end as u64),
+            uint("ref_begin", rows, |r| r.ref_begin as u64),
+            uint("ref_end", rows, |r| r.ref_end as u64),
+            uint("query_len", rows, |r| r.query_len as u64),
+            uint("ref_len", rows, |r| r.ref_len as u64),
+            float("query_coverage", rows, |r| r.query_coverage()),
+            float("ref_coverage", rows, |r| r.ref_coverage()),
+        ]
+    }
+}
+
+impl Tabular for Match {
+    fn columns(rows: &[Self]) -> Vec<Column> {
+        vec![
+            int("score", rows, |r| r.score),
+            uint("ref_begin", rows, |r| r.ref_begin as u64),
+            uint("ref_end", rows, |r| r.ref_end as u64),
+            float("identity", rows, |r| r.identity),
+        ]
+    }
+}
+
+impl Tabular for ReferenceHit {
+    fn columns(rows: &[Self]) -> Vec<Column> {
+        vec![
+            int("score", rows, |r| r.score),
+            uint("index", rows, |r| r.index as u64),
+            uint("mapq", rows, |r| r.mapq as u64),
+        ]
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+//! Scaffolding for testing code built on parasailors: reproducible random sequences, alignments with known scores,
+//! and assertions that two backends agree. None of it is needed outside of tests, so it's only built with the
+//! `test-utils` feature, which a crate can turn on in its `[dev-dependencies]`.
+//!
+//! # Examples
+//!
+//! ```
+//! use parasailors::test_utils::{assert_matches_reference, SequenceGenerator};
+//! use parasailors::*;
+//!
+//! let mut generator = SequenceGenerator::new(7);
+//! let query = generator.dna(60);
+//! let reference = generator.mutate(&query, 0.1);
+//!
+//! let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+//! let pairs = [(&query[..], &reference[..])];
+//! assert_matches_reference(&ParasailBackend, &pairs, AlignmentMode::Global, 3, 1, &matrix);
+//! ```
+
+use crate::backend::AlignmentBackend;
+use crate::batch::AlignmentMode;
+use crate::matrix::{Matrix, MatrixType};
+use crate::scalar::{asymmetric_alignment, AsymmetricGaps};
+use crate::significance::SplitMix64;
+
+const DNA: &[u8] = b"ACGT";
+const PROTEIN: &[u8] = b"ARNDCQEGHILKMFPSTWYV";
+
+/// Makes random sequences from a seed, so a failing test can be replayed exactly.
+pub struct SequenceGenerator {
+    rng: SplitMix64,
+}
+
+impl SequenceGenerator {
+    /// Creates a generator. The same seed always gives the same sequences.
+    pub fn new(seed: u64) -> Self {
+        SequenceGenerator { rng: SplitMix64(seed) }
+    }
+
+    /// A uniformly random DNA sequence of `A`, `C`, `G`, and `T`.
+    pub fn dna(&mut self, len: usize) -> Vec<u8> {
+        self.from_alphabet(DNA, len)
+    }
+
+    /// A uniformly random protein sequence over the 20 standard amino acids.
+    pub fn protein(&mut self, len: usize) -> Vec<u8> {
+        self.from_alphabet(PROTEIN, len)
+    }
+
+    /// A uniformly random sequence of characters from `alphabet`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alphabet` is empty.
+    pub fn from_alphabet(&mut self, alphabet: &[u8], len: usize) -> Vec<u8> {
+        assert!(!alphabet.is_empty(), "can't draw from an empty alphabet");
+        (0..len).map(|_| alphabet[self.below(alphabet.len())]).collect()
+    }
+
+    /// A copy of `sequence` where each position is edited with probability `rate`: substituted, deleted, or followed
+    /// by an inserted character, with equal odds. New characters are drawn from those already in the sequence, so DNA
+    /// stays DNA and protein stays protein.
+    pub fn mutate(&mut self, sequence: &[u8], rate: f64) -> Vec<u8> {
+        let mut mutated = Vec::with_capacity(sequence.len() + sequence.len() / 8);
+        for &c in sequence {
+            if self.unit() >= rate {
+                mutated.push(c);
+                continue;
+            }
+            match self.below(3) {
+                0 => mutated.push(sequence[self.below(sequence.len())]),
+                1 => {}
+                _ => {
+                    mutated.push(c);
+                    mutated.push(sequence[self.below(sequence.len())]);
+                }
+            }
+        }
+        mutated
+    }
+
+    // slightly biased for huge `n`, which doesn't matter for picking characters
+    fn below(&mut self, n: usize) -> usize {
+        (self.rng.next() % n as u64) as usize
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.rng.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// An alignment with a score worked out by hand, for checking a backend or wrapper against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    /// What the fixture checks, for failure messages.
+    pub name: &'static str,
+    /// The query sequence.
+    pub query: &'static [u8],
+    /// The reference sequence.
+    pub reference: &'static [u8],
+    /// The kind of alignment.
+    pub mode: AlignmentMode,
+    /// The gap open cost, in parasail's convention.
+    pub open_cost: i32,
+    /// The gap extension cost.
+    pub gap_extend_cost: i32,
+    /// The substitution matrix.
+    pub matrix: MatrixType,
+    /// The optimal score.
+    pub score: i32,
+}
+
+/// Alignments with known optimal scores, covering each mode, mismatches, gaps, and free end gaps.
+pub fn fixtures() -> Vec<Fixture> {
+    let fixture = |name, query, reference, mode, open_cost, gap_extend_cost, matrix, score| Fixture {
+        name,
+        query,
+        reference,
+        mode,
+        open_cost,
+        gap_extend_cost,
+        matrix,
+        score,
+    };
+
+    use crate::batch::AlignmentMode::*;
+    use crate::matrix::MatrixType::*;
+    vec![
+        fixture("identical", b"ACGTACGT", b"ACGTACGT", Global, 1, 1, IdentityWithPenalty, 8),
+        fixture("one mismatch", b"ACGTACGT", b"ACGTTCGT", Global, 1, 1, IdentityWithPenalty, 6),
+        fixture("unrelated", b"ACGTACGT", b"TTTTTTTT", Global, 1, 1, IdentityWithPenalty, -4),
+        fixture("one insertion", b"ACGTTACGT", b"ACGTACGT", Global, 1, 1, IdentityWithPenalty, 7),
+        fixture("one deletion", b"ACGACGT", b"ACGTACGT", Global, 6, 2, IdentityWithPenalty, 1),
+        fixture(
+            "free reference ends",
+            b"AAAACCCCCCCCCCGGG",
+            b"AAAAAAAAAACCCCCCCCCCGGGGGGGGGGTTTTTTTTTTTNNNNNNNNN",
+            SemiGlobal,
+            1,
+            1,
+            Identity,
+            17,
+        ),
+        fixture("semi-global mismatch", b"ACCGTGGAT", b"AAAAACCGTTGAT", SemiGlobal, 5, 1, IdentityWithPenalty, 7),
+        fixture("local hit", b"GATTACA", b"CCGATTACACCCCGATTTCACC", Local, 1, 1, IdentityWithPenalty, 7),
+        fixture("local protein", b"HEAGAWGHEE", b"HEAGAWGHEE", Local, 11, 1, Blosum62, 62),
+    ]
+}
+
+/// Asserts that every fixture scores as expected with `backend`.
+///
+/// # Panics
+///
+/// Panics, naming the fixture, at the first one that doesn't.
+pub fn assert_fixtures<B: AlignmentBackend>(backend: &B) {
+    for fixture in fixtures() {
+        let matrix = Matrix::new(fixture.matrix);
+        let score = backend.score(
+            fixture.query,
+            fixture.reference,
+            fixture.mode,
+            fixture.open_cost,
+            fixture.gap_extend_cost,
+            &matrix,
+        );
+        let score = score.unwrap_or_else(|err| panic!("fixture {:?} failed: {}", fixture.name, err));
+        assert_eq!(fixture.score, score, "fixture {:?} scored wrong", fixture.name);
+    }
+}
+
+/// Asserts that two backends give every pair the same score.
+///
+/// # Panics
+///
+/// Panics, showing the pair and both scores, at the first pair they disagree on.
+pub fn assert_backends_agree<A: AlignmentBackend, B: AlignmentBackend>(
+    first: &A,
+    second: &B,
+    pairs: &[(&[u8], &[u8])],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    matrix: &Matrix,
+) {
+    let first_scores = first.score_pairs(pairs, mode, open_cost, gap_extend_cost, matrix).unwrap();
+    let second_scores = second.score_pairs(pairs, mode, open_cost, gap_extend_cost, matrix).unwrap();
+    for ((&pair, &a), &b) in pairs.iter().zip(&first_scores).zip(&second_scores) {
+        assert_same_score(pair, a as i64, b as i64, mode);
+    }
+}
+
+/// Asserts that a backend gives every pair the same score as the crate's plain dynamic program (see
+/// `asymmetric_alignment`), which is slow but simple enough to trust.
+///
+/// # Panics
+///
+/// Panics, showing the pair and both scores, at the first pair they disagree on.
+pub fn assert_matches_reference<B: AlignmentBackend>(
+    backend: &B,
+    pairs: &[(&[u8], &[u8])],
+    mode: AlignmentMode,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    matrix: &Matrix,
+) {
+    let gaps = AsymmetricGaps::symmetric(open_cost, gap_extend_cost);
+    let scores = backend.score_pairs(pairs, mode, open_cost, gap_extend_cost, matrix).unwrap();
+    for (&pair, &score) in pairs.iter().zip(&scores) {
+        let expected = asymmetric_alignment(pair.0, pair.1, mode, &gaps, matrix).score;
+        assert_same_score(pair, expected, score as i64, mode);
+    }
+}
+
+fn assert_same_score(pair: (&[u8], &[u8]), expected: i64, actual: i64, mode: AlignmentMode) {
+    assert!(
+        expected == actual,
+        "{:?} scores differ ({} vs {}) for query {} and reference {}",
+        mode,
+        expected,
+        actual,
+        String::from_utf8_lossy(pair.0),
+        String::from_utf8_lossy(pair.1),
+    );
+}
+
+#[test]
+fn test_fixtures_match_dynamic_programming() {
+    for fixture in fixtures().into_iter().filter(|f| f.matrix != MatrixType::Blosum62) {
+        let matrix = Matrix::new(fixture.matrix);
+        let gaps = AsymmetricGaps::symmetric(fixture.open_cost, fixture.gap_extend_cost);
+        let expected = asymmetric_alignment(fixture.query, fixture.reference, fixture.mode, &gaps, &matrix).score;
+        assert_eq!(expected, fixture.score as i64, "{}", fixture.name);
+    }
+}
+
+#[test]
+fn test_generator_is_reproducible() {
+    let mut first = SequenceGenerator::new(42);
+    let mut second = SequenceGenerator::new(42);
+    let sequence = first.dna(500);
+    assert_eq!(sequence, second.dna(500));
+    assert!(sequence.iter().all(|c| DNA.contains(c)));
+
+    let mutated = first.mutate(&sequence, 0.05);
+    assert_eq!(mutated, second.mutate(&sequence, 0.05));
+    assert_ne!(sequence, mutated);
+    assert_eq!(sequence, first.mutate(&sequence, 0.0));
+}
@@ -0,0 +1,136 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Keeps the best `capacity` scoring items offered to it, ignoring anything scoring below a cutoff.
+///
+/// Among equal scores, items offered earlier rank higher and are the ones kept when there isn't room for all of
+/// them, so the result only depends on the order items were offered in. Memory stays bounded by `capacity` no matter
+/// how many items are pushed.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let mut top = TopHits::new(2, 10);
+/// top.push(12, "a");
+/// top.push(5, "below cutoff");
+/// top.push(30, "b");
+/// top.push(12, "tied with a, but later");
+///
+/// assert_eq!(vec![(30, "b"), (12, "a")], top.into_sorted_vec());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TopHits<T> {
+    capacity: usize,
+    min_score: i64,
+    offered: u64,
+    // the worst kept hit sits at the top
+    heap: BinaryHeap<Ranked<T>>,
+}
+
+impl<T> TopHits<T> {
+    /// Creates a collector keeping at most `capacity` items scoring at least `min_score`.
+    pub fn new(capacity: usize, min_score: i64) -> Self {
+        TopHits {
+            capacity,
+            min_score,
+            offered: 0,
+            heap: BinaryHeap::with_capacity(capacity),
+        }
+    }
+
+    /// Offers an item, returning whether it was kept. An item kept now may still be pushed out by later, better
+    /// scoring items.
+    pub fn push(&mut self, score: i64, item: T) -> bool {
+        let order = self.offered;
+        self.offered += 1;
+
+        if score < self.min_score || self.capacity == 0 {
+            return false;
+        }
+
+        if self.heap.len() == self.capacity {
+            match self.heap.peek() {
+                Some(worst) if worst.score >= score => return false,
+                _ => {
+                    self.heap.pop();
+                }
+            }
+        }
+
+        self.heap.push(Ranked { score, order, item });
+        true
+    }
+
+    /// The lowest score an item needs to be kept right now: the cutoff until the collector fills up, and then the
+    /// next score above its worst hit.
+    pub fn threshold(&self) -> i64 {
+        match self.heap.peek() {
+            Some(worst) if self.heap.len() == self.capacity => worst.score.saturating_add(1).max(self.min_score),
+            _ => self.min_score,
+        }
+    }
+
+    /// The number of items currently kept.
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Whether no items have been kept.
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// The kept items with their scores, best first, with ties in the order they were offered.
+    pub fn into_sorted_vec(self) -> Vec<(i64, T)> {
+        // ascending by "worseness" is best first
+        self.heap.into_sorted_vec().into_iter().map(|hit| (hit.score, hit.item)).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Ranked<T> {
+    score: i64,
+    order: u64,
+    item: T,
+}
+
+impl<T> PartialEq for Ranked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Ranked<T> {}
+
+impl<T> PartialOrd for Ranked<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Ranked<T> {
+    /// Orders from best to worst, so lower scores and later offers compare greater.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.score.cmp(&self.score).then(self.order.cmp(&other.order))
+    }
+}
+
+#[test]
+fn test_top_hits_ties_and_cutoff() {
+    let mut top = TopHits::new(3, 0);
+    for (i, &score) in [4, 7, -1, 7, 4, 9, 7, 4].iter().enumerate() {
+        top.push(score, i);
+    }
+    assert_eq!(8, top.threshold());
+    assert_eq!(vec![(9, 5), (7, 1), (7, 3)], top.into_sorted_vec());
+
+    let mut empty: TopHits<()> = TopHits::new(0, 0);
+    assert!(!empty.push(10, ()));
+    assert!(empty.is_empty());
+}
@@ -0,0 +1,170 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::distance_matrix::DistanceMatrix;
+
+/// Builds an unrooted tree from pairwise distances by neighbor joining (Saitou and Nei), as Newick text with branch
+/// lengths.
+///
+/// Negative branch lengths, which neighbor joining can produce from non-additive distances, are clamped to zero. Ties
+/// are broken in favor of the earliest pair, so the same matrix always gives the same tree. Names containing Newick
+/// punctuation or whitespace are single quoted.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let names = ["a", "b", "c", "d"].iter().map(|n| n.to_string()).collect();
+/// let mut distances = DistanceMatrix::new(names);
+/// distances.set(0, 1, 0.3);
+/// distances.set(0, 2, 0.5);
+/// distances.set(0, 3, 0.6);
+/// distances.set(1, 2, 0.6);
+/// distances.set(1, 3, 0.7);
+/// distances.set(2, 3, 0.3);
+///
+/// assert_eq!("((a:0.1,b:0.2):0.3,c:0.1,d:0.2);", neighbor_joining(&distances));
+/// ```
+pub fn neighbor_joining(distances: &DistanceMatrix) -> String {
+    let mut nodes: Vec<String> = distances.names.iter().map(|name| quote(name)).collect();
+    let mut d: Vec<Vec<f64>> = (0..nodes.len())
+        .map(|i| (0..nodes.len()).map(|j| distances.get(i, j)).collect())
+        .collect();
+
+    match nodes.len() {
+        0 => return ";".to_string(),
+        1 => return format!("{};", nodes[0]),
+        2 => return format!("({}:{},{}:{});", nodes[0], length(d[0][1] / 2.0), nodes[1], length(d[0][1] / 2.0)),
+        _ => {}
+    }
+
+    while nodes.len() > 3 {
+        let n = nodes.len();
+        let totals: Vec<f64> = d.iter().map(|row| row.iter().sum()).collect();
+
+        let (mut a, mut b, mut best) = (0, 1, f64::INFINITY);
+        for i in 0..n {
+            for j in i + 1..n {
+                let q = (n as f64 - 2.0) * d[i][j] - totals[i] - totals[j];
+                if q < best {
+                    best = q;
+                    a = i;
+                    b = j;
+                }
+            }
+        }
+
+        let to_a = d[a][b] / 2.0 + (totals[a] - totals[b]) / (2.0 * (n as f64 - 2.0));
+        let to_b = d[a][b] - to_a;
+        let joined = format!("({}:{},{}:{})", nodes[a], length(to_a), nodes[b], length(to_b));
+
+        let to_joined: Vec<f64> = (0..n).map(|k| (d[a][k] + d[b][k] - d[a][b]) / 2.0).collect();
+        // the new node takes a's place, and b is removed
+        nodes[a] = joined;
+        for k in 0..n {
+            d[a][k] = to_joined[k];
+            d[k][a] = to_joined[k];
+        }
+        d[a][a] = 0.0;
+        nodes.remove(b);
+        d.remove(b);
+        for row in d.iter_mut() {
+            row.remove(b);
+        }
+    }
+
+    let x = (d[0][1] + d[0][2] - d[1][2]) / 2.0;
+    let y = (d[0][1] + d[1][2] - d[0][2]) / 2.0;
+    let z = (d[0][2] + d[1][2] - d[0][1]) / 2.0;
+    format!("({}:{},{}:{},{}:{});", nodes[0], length(x), nodes[1], length(y), nodes[2], length(z))
+}
+
+/// Builds a rooted, ultrametric tree from pairwise distances by UPGMA (average linkage), as Newick text with branch
+/// lengths.
+///
+/// UPGMA assumes a constant rate of change, so it's only a good estimate of the true tree when that roughly holds;
+/// `neighbor_joining` doesn't need it. Ties are broken in favor of the earliest pair.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let names = ["a", "b", "c"].iter().map(|n| n.to_string()).collect();
+/// let mut distances = DistanceMatrix::new(names);
+/// distances.set(0, 1, 0.2);
+/// distances.set(0, 2, 0.6);
+/// distances.set(1, 2, 0.6);
+///
+/// assert_eq!("((a:0.1,b:0.1):0.2,c:0.3);", upgma(&distances));
+/// ```
+pub fn upgma(distances: &DistanceMatrix) -> String {
+    let mut nodes: Vec<String> = distances.names.iter().map(|name| quote(name)).collect();
+    let mut sizes: Vec<usize> = vec![1; nodes.len()];
+    let mut heights: Vec<f64> = vec![0.0; nodes.len()];
+    let mut d: Vec<Vec<f64>> = (0..nodes.len())
+        .map(|i| (0..nodes.len()).map(|j| distances.get(i, j)).collect())
+        .collect();
+
+    if nodes.is_empty() {
+        return ";".to_string();
+    }
+
+    while nodes.len() > 1 {
+        let n = nodes.len();
+        let (mut a, mut b, mut best) = (0, 1, f64::INFINITY);
+        for (i, row) in d.iter().enumerate() {
+            for (j, &distance) in row.iter().enumerate().skip(i + 1) {
+                if distance < best {
+                    best = distance;
+                    a = i;
+                    b = j;
+                }
+            }
+        }
+
+        let height = d[a][b] / 2.0;
+        nodes[a] = format!(
+            "({}:{},{}:{})",
+            nodes[a],
+            length(height - heights[a]),
+            nodes[b],
+            length(height - heights[b])
+        );
+
+        let (size_a, size_b) = (sizes[a] as f64, sizes[b] as f64);
+        let averaged: Vec<f64> = (0..n).map(|k| (d[a][k] * size_a + d[b][k] * size_b) / (size_a + size_b)).collect();
+        for k in 0..n {
+            d[a][k] = averaged[k];
+            d[k][a] = averaged[k];
+        }
+        d[a][a] = 0.0;
+        sizes[a] += sizes[b];
+        heights[a] = height;
+
+        nodes.remove(b);
+        sizes.remove(b);
+        heights.remove(b);
+        d.remove(b);
+        for row in d.iter_mut() {
+            row.remove(b);
+        }
+    }
+
+    format!("{};", nodes[0])
+}
+
+/// Formats a branch length, clamping negative lengths to zero and rounding away floating point noise.
+fn length(value: f64) -> f64 {
+    (value.max(0.0) * 1e10).round() / 1e10
+}
+
+/// Quotes a name if it contains anything Newick treats specially.
+fn quote(name: &str) -> String {
+    if name.chars().any(|c| c.is_whitespace() || "()[]':;,".contains(c)) {
+        format!("'{}'", name.replace('\'', "''"))
+    } else {
+        name.to_string()
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::ops::Range;
+
+use crate::align::{semi_global_dx_traceback, TracebackResults};
+use crate::error::{AlignError, ParasailError};
+use crate::matrix::Matrix;
+
+/// The kind of difference a `Variant` describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VariantKind {
+    /// A single base substitution.
+    Snv,
+    /// Bases present in the query but not the reference.
+    Insertion,
+    /// Bases present in the reference but not the query.
+    Deletion,
+}
+
+/// A difference between a query and the reference it was aligned to, in VCF-style normalized form.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Variant {
+    /// The index (0-based) in the reference of the first base of `ref_allele`.
+    pub pos: usize,
+    /// The reference bases replaced by the variant. Indels include the reference base before the event (or after it,
+    /// for an event at the very start of the reference), as in VCF.
+    pub ref_allele: Vec<u8>,
+    /// The query bases replacing `ref_allele`.
+    pub alt_allele: Vec<u8>,
+    /// What kind of difference this is.
+    pub kind: VariantKind,
+}
+
+/// Walks an alignment and reports every substitution, insertion, and deletion of the query relative to the reference.
+///
+/// The alignment should come from one of the traceback functions with the reference as the database sequence, and
+/// `reference` must be the whole reference that was aligned against. Gaps at either end of the alignment are treated
+/// as the read not covering that part of the reference, not as indels. Indels are shifted as far left as possible
+/// within repeats so that equivalent events always get the same record, and bases are compared case-insensitively.
+/// Variants are returned in reference order.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let reference = b"ACGTTTTACGGACCA";
+/// let read = b"ACGTTTACGCACCA";
+///
+/// let alignment = semi_global_traceback(read, reference, 1, 1, &matrix).unwrap();
+/// let variants = call_variants(&alignment, reference);
+///
+/// // one T lost from the homopolymer, reported at its leftmost position
+/// assert_eq!(VariantKind::Deletion, variants[0].kind);
+/// assert_eq!(2, variants[0].pos);
+/// assert_eq!(b"GT".to_vec(), variants[0].ref_allele);
+/// assert_eq!(b"G".to_vec(), variants[0].alt_allele);
+///
+/// assert_eq!(VariantKind::Snv, variants[1].kind);
+/// assert_eq!(10, variants[1].pos);
+/// assert_eq!(b"C".to_vec(), variants[1].alt_allele);
+/// ```
+pub fn call_variants(alignment: &TracebackResults, reference: &[u8]) -> Vec<Variant> {
+    let query = alignment.query_trace.as_bytes();
+    let ref_trace = alignment.ref_trace.as_bytes();
+
+    // end gaps in either sequence mean the read doesn't cover that stretch
+    let first = match (0..query.len()).find(|&k| query[k] != b'-' && ref_trace[k] != b'-') {
+        Some(first) => first,
+        None => return Vec::new(),
+    };
+    let last = (0..query.len())
+        .rev()
+        .find(|&k| query[k] != b'-' && ref_trace[k] != b'-')
+        .unwrap_or(first);
+
+    let ref_residues = ref_trace.iter().filter(|&&c| c != b'-').count();
+    let skipped = ref_trace[..first].iter().filter(|&&c| c != b'-').count();
+    let mut pos = alignment.ref_end - ref_residues + skipped;
+
+    let mut variants = Vec::new();
+    let mut k = first;
+    while k <= last {
+        if ref_trace[k] == b'-' {
+            let start = k;
+            while k <= last && ref_trace[k] == b'-' {
+                k += 1;
+            }
+            variants.push(insertion(reference, pos, &query[start..k]));
+        } else if query[k] == b'-' {
+            let start = pos;
+            while k <= last && query[k] == b'-' {
+                k += 1;
+                pos += 1;
+            }
+            variants.push(deletion(reference, start, pos));
+        } else {
+            if !query[k].eq_ignore_ascii_case(&ref_trace[k]) {
+                variants.push(Variant {
+                    pos,
+                    ref_allele: vec![ref_trace[k]],
+                    alt_allele: vec![query[k]],
+                    kind: VariantKind::Snv,
+                });
+            }
+            k += 1;
+            pos += 1;
+        }
+    }
+
+    variants.sort_by_key(|v| v.pos);
+    variants
+}
+
+/// Removes single base insertions and deletions lying in a reference homopolymer at least `min_run` bases long,
+/// keeping everything else in order.
+///
+/// Nanopore and other long reads often miscount homopolymer lengths, so these indels are mostly sequencing errors
+/// and would swamp the real variants. `variants` must be normalized records against `reference`, as `call_variants`
+/// makes them.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let reference = b"ACGTTTTACGA";
+/// let variants = vec![
+///     Variant { pos: 2, ref_allele: b"GT".to_vec(), alt_allele: b"G".to_vec(), kind: VariantKind::Deletion },
+///     Variant { pos: 8, ref_allele: b"C".to_vec(), alt_allele: b"CT".to_vec(), kind: VariantKind::Insertion },
+/// ];
+///
+/// let kept = drop_homopolymer_indels(&variants, reference, 3);
+/// assert_eq!(1, kept.len());
+/// assert_eq!(8, kept[0].pos);
+/// ```
+pub fn drop_homopolymer_indels(variants: &[Variant], reference: &[u8], min_run: usize) -> Vec<Variant> {
+    variants
+        .iter()
+        .filter(|v| !in_homopolymer(v, reference, min_run))
+        .cloned()
+        .collect()
+}
+
+/// Realigns a read to the stretch of reference around `anchor`, e.g. the span of a candidate indel, and reports the
+/// alignment in coordinates of the whole reference.
+///
+/// The read is aligned end to end against `reference[anchor]` padded by up to `padding` bases on either side (less at
+/// the ends of the reference), with free end gaps in the reference so the read can land anywhere in the window. The
+/// window should be a little wider than the read's span, since the read can't align beyond it. Only `ref_end` is
+/// shifted to the whole reference, and the trace keeps the window's leading end gaps like any other semi-global
+/// traceback, so the result can go straight to `call_variants` with the whole reference.
+///
+/// Returns `AlignError::InvalidParameter` if `anchor` is reversed or runs past the end of the reference, or the error
+/// parasail reports if the alignment itself fails.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let reference = b"TTTTTTTTTTACGTTTTACGGACCATTTTTTTTTT";
+/// let read = b"ACGTTTACGCACCA";
+///
+/// let alignment = realign_around(read, reference, 12..22, 6, 1, 1, &matrix).unwrap();
+/// assert_eq!(25, alignment.ref_end);
+///
+/// let variants = call_variants(&alignment, reference);
+/// assert_eq!((VariantKind::Deletion, 12), (variants[0].kind, variants[0].pos));
+/// assert_eq!((VariantKind::Snv, 20), (variants[1].kind, variants[1].pos));
+/// ```
+pub fn realign_around(
+    query_sequence: &[u8],
+    reference: &[u8],
+    anchor: Range<usize>,
+    padding: usize,
+    open_cost: i32,
+    gap_extend_cost: i32,
+    substitution_matrix: &Matrix,
+) -> Result<TracebackResults, ParasailError> {
+    if anchor.start > anchor.end || anchor.end > reference.len() {
+        return Err(AlignError::InvalidParameter {
+            reason: "anchor must be a range within the reference",
+        }
+        .into());
+    }
+
+    let start = anchor.start.saturating_sub(padding);
+    let end = anchor.end.saturating_add(padding).min(reference.len());
+    let mut alignment = semi_global_dx_traceback(
+        query_sequence,
+        &reference[start..end],
+        open_cost,
+        gap_extend_cost,
+        substitution_matrix,
+    )?;
+    alignment.ref_end += start;
+    Ok(alignment)
+}
+
+/// Whether a variant is a single base indel in a reference homopolymer at least `min_run` long.
+fn in_homopolymer(variant: &Variant, reference: &[u8], min_run: usize) -> bool {
+    let (longer, shorter) = match variant.kind {
+        VariantKind::Snv => return false,
+        VariantKind::Insertion => (&variant.alt_allele, &variant.ref_allele),
+        VariantKind::Deletion => (&variant.ref_allele, &variant.alt_allele),
+    };
+    if longer.len() != shorter.len() + 1 {
+        return false;
+    }
+
+    // the anchor base comes first, except for events at the very start of the reference
+    let (base, at) = if variant.pos == 0 && longer[0] != shorter[0] {
+        (longer[0], 0)
+    } else {
+        (longer[1], variant.pos + 1)
+    };
+
+    let same = |c: &u8| c.eq_ignore_ascii_case(&base);
+    let before = reference[..at.min(reference.len())].iter().rev().take_while(|c| same(c)).count();
+    let after = reference.get(at..).unwrap_or(&[]).iter().take_while(|c| same(c)).count();
+    before + after >= min_run
+}
+
+/// Builds a normalized record for `inserted` appearing just before `reference[pos]`.
+fn insertion(reference: &[u8], mut pos: usize, inserted: &[u8]) -> Variant {
+    let mut inserted = inserted.to_vec();
+
+    // shift left while the base before the insertion could equally have been the last inserted base
+    while pos > 0 && reference[pos - 1].eq_ignore_ascii_case(&inserted[inserted.len() - 1]) {
+        inserted.rotate_right(1);
+        pos -= 1;
+    }
+
+    if pos > 0 {
+        let anchor = reference[pos - 1];
+        let mut alt = vec![anchor];
+        alt.extend_from_slice(&inserted);
+        Variant {
+            pos: pos - 1,
+            ref_allele: vec![anchor],
+            alt_allele: alt,
+            kind: VariantKind::Insertion,
+        }
+    } else {
+        let anchor = reference[0];
+        inserted.push(anchor);
+        Variant {
+            pos: 0,
+            ref_allele: vec![anchor],
+            alt_allele: inserted,
+            kind: VariantKind::Insertion,
+        }
+    }
+}
+
+/// Builds a normalized record for `reference[start..end]` being deleted. Deletions always have an aligned base after
+/// them, so `reference[end]` exists.
+fn deletion(reference: &[u8], mut start: usize, mut end: usize) -> Variant {
+    // shift left while the base before the deletion could equally have been the last deleted base
+    while start > 0 && reference[start - 1].eq_ignore_ascii_case(&reference[end - 1]) {
+        start -= 1;
+        end -= 1;
+    }
+
+    if start > 0 {
+        Variant {
+            pos: start - 1,
+            ref_allele: reference[start - 1..end].to_vec(),
+            alt_allele: vec![reference[start - 1]],
+            kind: VariantKind::Deletion,
+        }
+    } else {
+        Variant {
+            pos: 0,
+            ref_allele: reference[..=end].to_vec(),
+            alt_allele: vec![reference[end]],
+            kind: VariantKind::Deletion,
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use std::fmt;
+
+use libc::c_int;
+use parasail_sys::{
+    parasail_can_use_altivec, parasail_can_use_avx2, parasail_can_use_neon, parasail_can_use_sse2,
+    parasail_can_use_sse41, parasail_version,
+};
+
+/// Which parasail build this crate is running against, for bug reports and reproducibility records.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VersionInfo {
+    /// The version of this crate.
+    pub crate_version: &'static str,
+    /// The major version of the parasail library.
+    pub parasail_major: u32,
+    /// The minor version of the parasail library.
+    pub parasail_minor: u32,
+    /// The patch version of the parasail library.
+    pub parasail_patch: u32,
+    /// The instruction sets parasail was compiled with and can use on this CPU, e.g. `"sse4.1"` or `"avx2"`.
+    pub instruction_sets: Vec<&'static str>,
+}
+
+impl VersionInfo {
+    /// The parasail version as `major.minor.patch`.
+    pub fn parasail_version(&self) -> String {
+        format!("{}.{}.{}", self.parasail_major, self.parasail_minor, self.parasail_patch)
+    }
+}
+
+impl fmt::Display for VersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "parasailors {} (parasail {}", self.crate_version, self.parasail_version())?;
+        if self.instruction_sets.is_empty() {
+            write!(f, "; no SIMD)")
+        } else {
+            write!(f, "; {})", self.instruction_sets.join(", "))
+        }
+    }
+}
+
+/// Reports the version of the wrapped parasail library and which of its SIMD instruction sets are available.
+///
+/// An instruction set is listed if parasail was compiled with it and the current CPU supports it, which is exactly
+/// when parasail's dispatcher will pick kernels using it. Results can differ between instruction sets only when a
+/// score saturates, but the numbers in a bug report should still say where they came from.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let info = version();
+/// assert!(info.to_string().starts_with("parasailors "));
+/// println!("{}", info);
+/// ```
+pub fn version() -> VersionInfo {
+    let (mut major, mut minor, mut patch): (c_int, c_int, c_int) = (0, 0, 0);
+    let mut instruction_sets = Vec::new();
+
+    unsafe {
+        parasail_version(&mut major, &mut minor, &mut patch);
+
+        let available = [
+            ("sse2", parasail_can_use_sse2()),
+            ("sse4.1", parasail_can_use_sse41()),
+            ("avx2", parasail_can_use_avx2()),
+            ("altivec", parasail_can_use_altivec()),
+            ("neon", parasail_can_use_neon()),
+        ];
+        for &(name, usable) in available.iter() {
+            if usable != 0 {
+                instruction_sets.push(name);
+            }
+        }
+    }
+
+    VersionInfo {
+        crate_version: env!("CARGO_PKG_VERSION"),
+        parasail_major: major as u32,
+        parasail_minor: minor as u32,
+        parasail_patch: patch as u32,
+        instruction_sets,
+    }
+}
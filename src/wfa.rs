@@ -0,0 +1,222 @@
+// Copyright (c) 2016 Adam Perry <adam.n.perry@gmail.com>
+//
+// This software may be modified and distributed under the terms of the MIT license.  See the
+// LICENSE file for details.
+
+use crate::backend::{AlignmentBackend, ParasailBackend, SubstitutionTable};
+use crate::batch::AlignmentMode;
+use crate::error::ParasailError;
+use crate::matrix::Matrix;
+
+/// A pure Rust port of the gap-affine [wavefront alignment algorithm](https://github.com/smarco/WFA2-lib) (WFA), for
+/// globally aligning long, nearly identical sequences. Requires the `wfa` feature.
+///
+/// Instead of filling the dynamic programming matrix cell by cell, WFA follows only the furthest reaching alignment
+/// along each diagonal for each penalty, sliding along runs of matches for free. The work grows with the length times
+/// the number of differences rather than with the product of the lengths, so two near-identical 100kb sequences take
+/// milliseconds instead of minutes. Very divergent sequences are much slower than with `ParasailBackend`.
+///
+/// The scores are exactly parasail's. WFA needs a plain match/mismatch scheme, so a pair is passed on to
+/// `ParasailBackend` if the matrix scores the characters in it with more than two distinct values, if matches don't
+/// score above mismatches, or if the gap open cost is less than the extend cost. Only global alignment is supported,
+/// and other modes are passed on too.
+///
+/// # Examples
+///
+/// ```
+/// # use parasailors::*;
+/// let matrix = Matrix::new(MatrixType::IdentityWithPenalty);
+/// let pairs: Vec<(&[u8], &[u8])> = vec![(b"ACGTACGTTTACGT", b"ACGTACGTACGT")];
+///
+/// let scores = WavefrontBackend.score_pairs(&pairs, AlignmentMode::Global, 2, 1, &matrix).unwrap();
+/// assert_eq!(ParasailBackend.score_pairs(&pairs, AlignmentMode::Global, 2, 1, &matrix).unwrap(), scores);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WavefrontBackend;
+
+impl AlignmentBackend for WavefrontBackend {
+    fn score_pairs(
+        &self,
+        pairs: &[(&[u8], &[u8])],
+        mode: AlignmentMode,
+        open_cost: i32,
+        gap_extend_cost: i32,
+        matrix: &Matrix,
+    ) -> Result<Vec<i32>, ParasailError> {
+        if mode != AlignmentMode::Global || open_cost < gap_extend_cost {
+            return ParasailBackend.score_pairs(pairs, mode, open_cost, gap_extend_cost, matrix);
+        }
+
+        pairs
+            .iter()
+            .map(|&(query, reference)| {
+                let table = SubstitutionTable::new(&[(query, reference)], matrix);
+                match Penalties::new(&table, open_cost, gap_extend_cost) {
+                    Some(penalties) => Ok(wavefront_score(query, reference, &table, &penalties)),
+                    None => ParasailBackend.score(query, reference, mode, open_cost, gap_extend_cost, matrix),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Whether `WavefrontBackend` aligns the pair itself in global mode, rather than passing it on to `ParasailBackend`.
+pub(crate) fn supports(query: &[u8], reference: &[u8], open_cost: i32, gap_extend_cost: i32, matrix: &Matrix) -> bool {
+    let table = SubstitutionTable::new(&[(query, reference)], matrix);
+    open_cost >= gap_extend_cost && Penalties::new(&table, open_cost, gap_extend_cost).is_some()
+}
+
+/// The match/mismatch scores and gap costs of an alignment, restated as the non-negative penalties WFA minimizes.
+///
+/// With match score `a`, mismatch score `b`, and gaps of length `L` costing `o + L * e` (parasail's open cost less one
+/// extension), an alignment's score is `(a * (n + m) - p) / 2`, where `n` and `m` are the sequence lengths and `p`
+/// is its total penalty with mismatches costing `2 * (a - b)`, gaps opening at `2 * o`, and gaps extending at
+/// `2 * e + a` (Eizenga and Paten, 2022).
+struct Penalties {
+    match_score: i64,
+    mismatch: i64,
+    open: i64,
+    extend: i64,
+}
+
+impl Penalties {
+    fn new(table: &SubstitutionTable, open_cost: i32, gap_extend_cost: i32) -> Option<Self> {
+        let mut values = table.values().to_vec();
+        values.sort_unstable();
+        values.dedup();
+        let (match_score, mismatch_score) = match *values.as_slice() {
+            [only] => (only as i64, only as i64 - 1),
+            [low, high] => (high as i64, low as i64),
+            _ => return None,
+        };
+
+        let penalties = Penalties {
+            match_score,
+            mismatch: 2 * (match_score - mismatch_score),
+            open: 2 * (open_cost - gap_extend_cost) as i64,
+            extend: 2 * gap_extend_cost as i64 + match_score,
+        };
+        (penalties.extend > 0).then_some(penalties)
+    }
+}
+
+/// Marks a diagonal with no alignment reaching it at some penalty.
+const NONE: i64 = i64::MIN / 4;
+
+/// The furthest reaching reference offsets on each diagonal (reference offset minus query offset) for one penalty,
+/// for alignments ending in a match or mismatch (`m`), an insertion (`ins`), or a deletion (`del`).
+struct Wavefront {
+    lo: i64,
+    hi: i64,
+    m: Vec<i64>,
+    ins: Vec<i64>,
+    del: Vec<i64>,
+}
+
+impl Wavefront {
+    fn new(lo: i64, hi: i64) -> Self {
+        let width = (hi - lo + 1) as usize;
+        Wavefront {
+            lo,
+            hi,
+            m: vec![NONE; width],
+            ins: vec![NONE; width],
+            del: vec![NONE; width],
+        }
+    }
+
+    fn get(&self, component: fn(&Wavefront) -> &Vec<i64>, k: i64) -> i64 {
+        if k < self.lo || k > self.hi {
+            NONE
+        } else {
+            component(self)[(k - self.lo) as usize]
+        }
+    }
+}
+
+/// The global alignment score of `query` against `reference`, found by growing wavefronts of increasing penalty until
+/// one reaches the end of both sequences.
+fn wavefront_score(query: &[u8], reference: &[u8], table: &SubstitutionTable, penalties: &Penalties) -> i32 {
+    let (n, m) = (query.len() as i64, reference.len() as i64);
+    let target = m - n;
+    let is_match = |q: u8, r: u8| table.score(q, r) as i64 == penalties.match_score;
+
+    // slides an offset along a diagonal while the characters match
+    let extend = |k: i64, mut h: i64| {
+        while h >= 0 && h < m && h - k < n && is_match(query[(h - k) as usize], reference[h as usize]) {
+            h += 1;
+        }
+        h
+    };
+    // an offset is only valid if it stays within both sequences
+    let valid = |k: i64, h: i64| if h >= 0 && h <= m && h - k >= 0 && h - k <= n { h } else { NONE };
+
+    let mut start = Wavefront::new(0, 0);
+    start.m[0] = extend(0, 0);
+    let mut wavefronts: Vec<Option<Wavefront>> = vec![Some(start)];
+
+    let mut penalty = 0i64;
+    loop {
+        if let Some(wavefront) = &wavefronts[penalty as usize] {
+            if wavefront.get(|w| &w.m, target) >= m {
+                let score = (penalties.match_score * (n + m) - penalty) / 2;
+                return score as i32;
+            }
+        }
+
+        penalty += 1;
+        let earlier = |cost: i64| {
+            let at = penalty - cost;
+            if at < 0 {
+                None
+            } else {
+                wavefronts[at as usize].as_ref()
+            }
+        };
+        let (mismatch, open, extend_gap) = (
+            earlier(penalties.mismatch),
+            earlier(penalties.open + penalties.extend),
+            earlier(penalties.extend),
+        );
+
+        let sources = [mismatch, open, extend_gap];
+        let lo = sources.iter().flatten().map(|w| w.lo).min();
+        let hi = sources.iter().flatten().map(|w| w.hi).max();
+        let (lo, hi) = match (lo, hi) {
+            (Some(lo), Some(hi)) => (lo - 1, hi + 1),
+            _ => {
+                wavefronts.push(None);
+                continue;
+            }
+        };
+
+        let mut next = Wavefront::new(lo, hi);
+        let mut reached = false;
+        for k in lo..=hi {
+            let at = |w: Option<&Wavefront>, component: fn(&Wavefront) -> &Vec<i64>, k: i64| {
+                w.map_or(NONE, |w| w.get(component, k))
+            };
+
+            // an insertion consumes a query character, moving down a diagonal without advancing the reference
+            let ins = at(open, |w| &w.m, k + 1).max(at(extend_gap, |w| &w.ins, k + 1));
+            // a deletion consumes a reference character, moving up a diagonal
+            let del = at(open, |w| &w.m, k - 1).max(at(extend_gap, |w| &w.del, k - 1)) + 1;
+            let substitution = at(mismatch, |w| &w.m, k) + 1;
+
+            let i = (k - lo) as usize;
+            next.ins[i] = valid(k, ins);
+            next.del[i] = valid(k, del);
+            let best = valid(k, substitution).max(next.ins[i]).max(next.del[i]);
+            next.m[i] = if best == NONE { NONE } else { extend(k, best) };
+            reached |= best != NONE;
+        }
+
+        wavefronts.push(if reached { Some(next) } else { None });
+
+        // only the last few wavefronts are ever looked back at
+        let horizon = penalties.mismatch.max(penalties.open + penalties.extend);
+        if penalty > horizon {
+            wavefronts[(penalty - horizon - 1) as usize] = None;
+        }
+    }
+}